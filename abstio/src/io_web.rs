@@ -200,6 +200,8 @@ pub fn write_file(path: String, contents: String) -> Result<String> {
         "application/json"
     } else if path.ends_with("bin") {
         "application/octet-stream"
+    } else if path.ends_with("html") {
+        "text/html"
     } else {
         bail!("Don't know MIME type for {path}");
     };
@@ -56,7 +56,9 @@ fn parse_args() -> anyhow::Result<Vec<String>> {
     if url_parts.len() != 2 {
         bail!("URL {url} doesn't seem to have query params");
     }
-    let parts = url_parts[1]
+    // Ignore any fragment
+    let query = url_parts[1].split('#').next().unwrap();
+    let parts = query
         .split("&")
         .map(|x| x.replace("%20", " ").to_string())
         .collect::<Vec<_>>();
@@ -21,11 +21,13 @@ impl Layer for SteepStreets {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
         if ctx.redo_mouseover() {
             self.tooltip = None;
-            if let Some(ID::Road(r)) = app.mouseover_unzoomed_roads_and_intersections(ctx) {
-                self.tooltip = Some(Text::from(format!(
-                    "{:.1}% incline",
-                    app.primary.map.get_r(r).percent_incline.abs() * 100.0
-                )));
+            if app.opts.show_layer_tooltips {
+                if let Some(ID::Road(r)) = app.mouseover_unzoomed_roads_and_intersections(ctx) {
+                    self.tooltip = Some(Text::from(format!(
+                        "{:.1}% incline",
+                        app.primary.map.get_r(r).percent_incline.abs() * 100.0
+                    )));
+                }
             }
         }
 
@@ -173,7 +175,7 @@ impl Layer for ElevationContours {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
         if ctx.redo_mouseover() {
             self.tooltip = None;
-            if ctx.canvas.is_unzoomed() {
+            if ctx.canvas.is_unzoomed() && app.opts.show_layer_tooltips {
                 if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
                     if let Some((elevation, _)) = self
                         .closest_elevation
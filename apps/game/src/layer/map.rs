@@ -1,16 +1,19 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
 use maplit::btreeset;
 
 use crate::ID;
 use abstutil::{prettyprint_usize, Counter};
 use geom::{Distance, Time};
-use map_gui::tools::{ColorDiscrete, ColorNetwork};
-use map_model::{AmenityType, Direction, LaneType};
+use map_gui::tools::{ColorDiscrete, ColorNetwork, HideableCategories};
+use map_model::{connectivity, AmenityType, Direction, LaneID, LaneType, Map, PathConstraints};
 use sim::AgentType;
 use widgetry::mapspace::ToggleZoomed;
-use widgetry::tools::ColorLegend;
-use widgetry::{Color, EventCtx, GfxCtx, Line, Panel, Text, Widget};
+use widgetry::tools::{ColorLegend, PopupMsg};
+use widgetry::{Choice, Color, EventCtx, GfxCtx, Line, Outcome, Panel, Text, TextExt, Widget};
 
-use crate::app::App;
+use crate::app::{App, Transition};
 use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
 
 pub struct BikeActivity {
@@ -32,7 +35,7 @@ impl Layer for BikeActivity {
         }
 
         // Show a tooltip with count, only when unzoomed
-        if ctx.canvas.is_unzoomed() {
+        if ctx.canvas.is_unzoomed() && app.opts.show_layer_tooltips {
             if ctx.redo_mouseover() || recalc_tooltip {
                 self.tooltip = None;
                 if let Some(ID::Road(r)) = app.mouseover_unzoomed_roads_and_intersections(ctx) {
@@ -163,22 +166,185 @@ impl BikeActivity {
     }
 }
 
+pub struct Amenities {
+    panel: Panel,
+    draw: ToggleZoomed,
+    categories: HideableCategories,
+    only: Option<AmenityType>,
+}
+
+impl Layer for Amenities {
+    fn name(&self) -> Option<&'static str> {
+        Some("amenities")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Some(LayerOutcome::Close),
+                _ => unreachable!(),
+            },
+            Outcome::Changed(x) => {
+                if let Some(draw) = self.categories.toggle(ctx, &x) {
+                    self.draw = draw;
+                    return None;
+                }
+                let only = self.panel.dropdown_value("amenity type");
+                if only != self.only {
+                    *self = Amenities::new(ctx, app, only);
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl Amenities {
+    /// If `only` is specified, just highlight buildings with that type of amenity, and dim the
+    /// rest.
+    pub fn new(ctx: &mut EventCtx, app: &App, only: Option<AmenityType>) -> Amenities {
+        let mut categories = Vec::new();
+        if let Some(t) = only {
+            categories.push((t.to_string(), Color::RED));
+            categories.push(("other amenities".to_string(), Color::grey(0.5).alpha(0.3)));
+        } else {
+            categories.push((AmenityType::Bike.to_string(), Color::ORANGE));
+            categories.push((AmenityType::Food.to_string(), Color::RED));
+            categories.push((AmenityType::School.to_string(), Color::CYAN));
+            categories.push((AmenityType::Shopping.to_string(), Color::PURPLE));
+            categories.push(("other".to_string(), Color::GREEN));
+        }
+        let mut colorer = ColorDiscrete::no_fading(app, categories);
+
+        for b in app.primary.map.all_buildings() {
+            if b.amenities.is_empty() {
+                continue;
+            }
+            let types: Vec<AmenityType> = b
+                .amenities
+                .iter()
+                .filter_map(|a| AmenityType::categorize(&a.amenity_type))
+                .collect();
+            let category = if let Some(t) = only {
+                if types.contains(&t) {
+                    t.to_string()
+                } else {
+                    "other amenities".to_string()
+                }
+            } else if types.contains(&AmenityType::Bike) {
+                // Bike parking is often tagged on a building with other amenities, but it's the
+                // thing cycling planners are looking for
+                AmenityType::Bike.to_string()
+            } else {
+                match types.first() {
+                    Some(t @ (AmenityType::Food | AmenityType::School | AmenityType::Shopping)) => {
+                        t.to_string()
+                    }
+                    _ => "other".to_string(),
+                }
+            };
+            colorer.add_b(b.id, category);
+        }
+        let (categories, draw, legend) = colorer.build_hideable(ctx);
+
+        let mut choices = vec![Choice::new("all types", None)];
+        for t in AmenityType::all() {
+            choices.push(Choice::new(t.to_string(), Some(t)));
+        }
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Amenities"),
+            Widget::row(vec![
+                "Show:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(ctx, "amenity type", only, choices),
+            ]),
+            "Click a category to hide or show it".text_widget(ctx),
+            legend,
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        Amenities {
+            panel,
+            draw,
+            categories,
+            only,
+        }
+    }
+}
+
 pub struct Static {
     panel: Panel,
     pub draw: ToggleZoomed,
+    categories: HideableCategories,
     name: &'static str,
+    /// Only some layers explain individual lanes when hovered
+    lane_tooltips: HashMap<LaneID, Text>,
+    tooltip: Option<Text>,
 }
 
 impl Layer for Static {
     fn name(&self) -> Option<&'static str> {
         Some(self.name)
     }
-    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Option<LayerOutcome> {
-        <dyn Layer>::simple_event(ctx, &mut self.panel)
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if self.lane_tooltips.is_empty() || !app.opts.show_layer_tooltips {
+            self.tooltip = None;
+        } else if ctx.redo_mouseover() {
+            // Unzoomed, this finds roads; zoomed in, individual lanes
+            self.tooltip = match app.mouseover_unzoomed_roads_and_intersections(ctx) {
+                Some(ID::Road(r)) => app
+                    .primary
+                    .map
+                    .get_r(r)
+                    .lanes
+                    .iter()
+                    .find_map(|l| self.lane_tooltips.get(&l.id))
+                    .cloned(),
+                Some(ID::Lane(l)) => self.lane_tooltips.get(&l).cloned(),
+                _ => None,
+            };
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Some(LayerOutcome::Close),
+                // Only the high stress layer has this button
+                "Export to GeoJSON" => Some(LayerOutcome::Transition(Transition::Push(
+                    match export_high_stress(app) {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {path}")],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    },
+                ))),
+                _ => unreachable!(),
+            },
+            Outcome::Changed(x) => {
+                if let Some(draw) = self.categories.toggle(ctx, &x) {
+                    self.draw = draw;
+                }
+                None
+            }
+            _ => None,
+        }
     }
     fn draw(&self, g: &mut GfxCtx, _: &App) {
         self.panel.draw(g);
         self.draw.draw(g);
+        if let Some(ref txt) = self.tooltip {
+            g.draw_mouse_tooltip(txt.clone());
+        }
     }
     fn draw_minimap(&self, g: &mut GfxCtx) {
         g.redraw(&self.draw.unzoomed);
@@ -193,12 +359,19 @@ impl Static {
         title: String,
         extra: Widget,
     ) -> Static {
-        let (draw, legend) = colorer.build(ctx);
+        let (categories, draw, legend) = colorer.build_hideable(ctx);
         let panel = Panel::new_builder(Widget::col(vec![header(ctx, &title), extra, legend]))
             .aligned_pair(PANEL_PLACEMENT)
             .build(ctx);
 
-        Static { panel, draw, name }
+        Static {
+            panel,
+            draw,
+            categories,
+            name,
+            lane_tooltips: HashMap::new(),
+            tooltip: None,
+        }
     }
 
     pub fn edits(ctx: &mut EventCtx, app: &App) -> Static {
@@ -235,51 +408,6 @@ impl Static {
         )
     }
 
-    pub fn amenities(ctx: &mut EventCtx, app: &App) -> Static {
-        let food = Color::RED;
-        let school = Color::CYAN;
-        let shopping = Color::PURPLE;
-        let other = Color::GREEN;
-
-        let mut draw = ToggleZoomed::builder();
-        for b in app.primary.map.all_buildings() {
-            if b.amenities.is_empty() {
-                continue;
-            }
-            let mut color = None;
-            for a in &b.amenities {
-                if let Some(t) = AmenityType::categorize(&a.amenity_type) {
-                    color = Some(match t {
-                        AmenityType::Food => food,
-                        AmenityType::School => school,
-                        AmenityType::Shopping => shopping,
-                        _ => other,
-                    });
-                    break;
-                }
-            }
-            let color = color.unwrap_or(other);
-            draw.unzoomed.push(color, b.polygon.clone());
-            draw.zoomed.push(color.alpha(0.4), b.polygon.clone());
-        }
-
-        let panel = Panel::new_builder(Widget::col(vec![
-            header(ctx, "Amenities"),
-            ColorLegend::row(ctx, food, AmenityType::Food.to_string()),
-            ColorLegend::row(ctx, school, AmenityType::School.to_string()),
-            ColorLegend::row(ctx, shopping, AmenityType::Shopping.to_string()),
-            ColorLegend::row(ctx, other, "other".to_string()),
-        ]))
-        .aligned_pair(PANEL_PLACEMENT)
-        .build(ctx);
-
-        Static {
-            panel,
-            draw: draw.build(ctx),
-            name: "amenities",
-        }
-    }
-
     pub fn no_sidewalks(ctx: &mut EventCtx, app: &App) -> Static {
         let mut colorer = ColorDiscrete::new(app, vec![("no sidewalks", Color::RED)]);
         for l in app.primary.map.all_lanes() {
@@ -314,13 +442,28 @@ impl Static {
                 colorer.add_l(l.id, "biking blackhole");
             }
         }
-        Static::new(
+        let mut layer = Static::new(
             ctx,
             colorer,
             "blackholes",
             "blackholes".to_string(),
             Widget::nothing(),
-        )
+        );
+
+        let map = &app.primary.map;
+        for (constraints, mode) in [
+            (PathConstraints::Car, "car"),
+            (PathConstraints::Bike, "bike"),
+        ] {
+            for (l, reason) in explain_blackholes(map, constraints, mode) {
+                layer
+                    .lane_tooltips
+                    .entry(l)
+                    .or_insert_with(Text::new)
+                    .add_line(reason);
+            }
+        }
+        layer
     }
 
     pub fn high_stress(ctx: &mut EventCtx, app: &App) -> Static {
@@ -339,12 +482,122 @@ impl Static {
             colorer,
             "high stress",
             "High stress roads for biking".to_string(),
-            Text::from_multiline(vec![
-                Line("High stress defined as:"),
-                Line("- arterial classification"),
-                Line("- no dedicated cycle lane"),
-            ])
-            .into_widget(ctx),
+            Widget::col(vec![
+                Text::from_multiline(vec![
+                    Line("High stress defined as:"),
+                    Line("- arterial classification"),
+                    Line("- no dedicated cycle lane"),
+                ])
+                .into_widget(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Export to GeoJSON")
+                    .build_def(ctx),
+            ]),
         )
     }
 }
+
+/// For every lane outside the main strongly connected component for some mode, say which way the
+/// connection to the main network is missing.
+fn explain_blackholes(
+    map: &Map,
+    constraints: PathConstraints,
+    mode: &str,
+) -> Vec<(LaneID, String)> {
+    let (main, disconnected) = connectivity::find_scc(map, constraints);
+    if disconnected.is_empty() {
+        return Vec::new();
+    }
+
+    let mut forwards: HashMap<LaneID, Vec<LaneID>> = HashMap::new();
+    let mut backwards: HashMap<LaneID, Vec<LaneID>> = HashMap::new();
+    for turn in map.all_turns() {
+        if constraints.can_use(map.get_l(turn.id.src), map)
+            && constraints.can_use(map.get_l(turn.id.dst), map)
+        {
+            forwards.entry(turn.id.src).or_default().push(turn.id.dst);
+            backwards.entry(turn.id.dst).or_default().push(turn.id.src);
+        }
+    }
+    let reachable_from_main = floodfill(&main, &forwards);
+    let can_reach_main = floodfill(&main, &backwards);
+
+    disconnected
+        .into_iter()
+        .map(|l| {
+            let reason = match (
+                reachable_from_main.contains(&l),
+                can_reach_main.contains(&l),
+            ) {
+                (true, false) => {
+                    format!("a {mode} can get here from the main network, but can't get back out")
+                }
+                (false, true) => format!("unreachable by {mode} from the main network"),
+                (false, false) => {
+                    format!("cut off from the main network by {mode} in both directions")
+                }
+                // By definition, this would be part of the main network
+                (true, true) => format!("disconnected from the main network by {mode}"),
+            };
+            (l, reason)
+        })
+        .collect()
+}
+
+fn floodfill(start: &HashSet<LaneID>, edges: &HashMap<LaneID, Vec<LaneID>>) -> HashSet<LaneID> {
+    let mut visited = start.clone();
+    let mut queue: Vec<LaneID> = start.iter().cloned().collect();
+    while let Some(l) = queue.pop() {
+        for next in edges.get(&l).into_iter().flatten() {
+            if visited.insert(*next) {
+                queue.push(*next);
+            }
+        }
+    }
+    visited
+}
+
+/// Write every road that's high stress for biking in at least one direction as a LineString,
+/// with the inputs to `high_stress_for_bikes` as properties.
+fn export_high_stress(app: &App) -> Result<String> {
+    let map = &app.primary.map;
+    let mut features = Vec::new();
+    for r in map.all_roads() {
+        let stress_fwd = r.high_stress_for_bikes(map, Direction::Fwd);
+        let stress_back = r.high_stress_for_bikes(map, Direction::Back);
+        if !stress_fwd && !stress_back {
+            continue;
+        }
+        let bike_lane = |dir| {
+            r.lanes
+                .iter()
+                .any(|l| l.lane_type == LaneType::Biking && l.dir == dir)
+        };
+
+        let mut properties = serde_json::Map::new();
+        properties.insert("osm_way_id".to_string(), r.orig_id.osm_way_id.0.into());
+        properties.insert("name".to_string(), r.get_name(None).into());
+        properties.insert("rank".to_string(), format!("{:?}", r.get_rank()).into());
+        properties.insert(
+            "bike_lane_fwd".to_string(),
+            bike_lane(Direction::Fwd).into(),
+        );
+        properties.insert(
+            "bike_lane_back".to_string(),
+            bike_lane(Direction::Back).into(),
+        );
+        properties.insert("high_stress_fwd".to_string(), stress_fwd.into());
+        properties.insert("high_stress_back".to_string(), stress_back.into());
+        features.push((
+            r.center_pts.to_geojson(Some(map.get_gps_bounds())),
+            properties,
+        ));
+    }
+
+    let gj = geom::geometries_with_properties_to_geojson(features);
+    abstio::write_file(
+        format!("high_stress_{}.geojson", map.get_name().as_filename()),
+        abstutil::to_json(&gj),
+    )
+}
@@ -106,6 +106,7 @@ impl PickLayer {
                 Widget::col(vec![
                     "Traffic".text_widget(ctx),
                     btn("delay", Key::D),
+                    btn("congestion", Key::Q),
                     btn("throughput", Key::T),
                     btn("traffic jams", Key::J),
                     btn("cycling activity", Key::B),
@@ -168,7 +169,7 @@ impl State<App> for PickLayer {
                     app.primary.layer = None;
                 }
                 "amenities" => {
-                    app.primary.layer = Some(Box::new(map::Static::amenities(ctx, app)));
+                    app.primary.layer = Some(Box::new(map::Amenities::new(ctx, app, None)));
                 }
                 "backpressure" => {
                     app.primary.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
@@ -179,6 +180,9 @@ impl State<App> for PickLayer {
                 "delay" => {
                     app.primary.layer = Some(Box::new(traffic::Delay::new(ctx, app)));
                 }
+                "congestion" => {
+                    app.primary.layer = Some(Box::new(traffic::RoadCongestion::new(ctx, app)));
+                }
                 "pedestrian crowding" => {
                     app.primary.layer = Some(Box::new(traffic::PedestrianCrowding::new(ctx, app)));
                 }
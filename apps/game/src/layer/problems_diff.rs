@@ -40,7 +40,7 @@ impl Layer for RelativeProblemMap {
         }
 
         // TODO Reinventing CompareCounts...
-        if ctx.redo_mouseover() || recalc_tooltip {
+        if app.opts.show_layer_tooltips && (ctx.redo_mouseover() || recalc_tooltip) {
             self.tooltip = None;
             if let Some((before, after)) = match app.mouseover_unzoomed_roads_and_intersections(ctx)
             {
@@ -1,13 +1,13 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use maplit::btreeset;
 
 use crate::ID;
 use abstutil::{prettyprint_usize, Counter};
 use geom::{Circle, Distance, Duration, Percent, Polygon, Pt2D, Time};
 use map_gui::tools::ColorNetwork;
-use map_model::{IntersectionID, Map, Traversable};
+use map_model::{IntersectionID, Map, RoadID, Traversable};
 use sim::{AgentType, VehicleType};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::mapspace::{DummyID, World};
@@ -110,7 +110,7 @@ impl Layer for Throughput {
         }
 
         // Show a tooltip with count, only when unzoomed
-        if ctx.canvas.is_unzoomed() {
+        if ctx.canvas.is_unzoomed() && app.opts.show_layer_tooltips {
             if ctx.redo_mouseover() || recalc_tooltip {
                 self.tooltip = None;
                 match app.mouseover_unzoomed_roads_and_intersections(ctx) {
@@ -162,6 +162,23 @@ impl Layer for Throughput {
                         },
                     )));
                 }
+                "Export time series" => {
+                    return Some(LayerOutcome::Transition(Transition::Push(
+                        match export_throughput_time_series(ctx, app) {
+                            Ok(path) => PopupMsg::new_state(
+                                ctx,
+                                "Data exported",
+                                vec![format!(
+                                    "Hourly throughput for the roads on screen exported to {}",
+                                    path
+                                )],
+                            ),
+                            Err(err) => {
+                                PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                            }
+                        },
+                    )));
+                }
                 _ => unreachable!(),
             },
             Outcome::Changed(_) => {
@@ -235,6 +252,10 @@ impl Throughput {
             .flex_wrap(ctx, Percent::int(20)),
             ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0", "highest"]),
             ctx.style().btn_plain.text("Export to CSV").build_def(ctx),
+            ctx.style()
+                .btn_plain
+                .text("Export time series")
+                .build_def(ctx),
         ]))
         .aligned_pair(PANEL_PLACEMENT)
         .build(ctx);
@@ -272,7 +293,7 @@ impl Layer for CompareThroughput {
         }
 
         // Show a tooltip with count, only when unzoomed
-        if ctx.canvas.is_unzoomed() {
+        if ctx.canvas.is_unzoomed() && app.opts.show_layer_tooltips {
             if ctx.redo_mouseover() || recalc_tooltip {
                 self.tooltip = None;
                 match app.mouseover_unzoomed_roads_and_intersections(ctx) {
@@ -619,6 +640,115 @@ impl Delay {
     }
 }
 
+// Colors roads by the average delay of vehicles waiting at their end to turn
+pub struct RoadCongestion {
+    time: Time,
+    // Per road, how many delays were measured and their sum
+    delays: BTreeMap<RoadID, (usize, Duration)>,
+    draw: ToggleZoomed,
+    panel: Panel,
+    tooltip: Option<Text>,
+}
+
+impl Layer for RoadCongestion {
+    fn name(&self) -> Option<&'static str> {
+        Some("congestion")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        let mut recalc_tooltip = false;
+        if app.primary.sim.time() != self.time {
+            *self = RoadCongestion::new(ctx, app);
+            recalc_tooltip = true;
+        }
+
+        if ctx.canvas.is_unzoomed() && app.opts.show_layer_tooltips {
+            if ctx.redo_mouseover() || recalc_tooltip {
+                self.tooltip = None;
+                if let Some(ID::Road(r)) = app.mouseover_unzoomed_roads_and_intersections(ctx) {
+                    if let Some((count, total)) = self.delays.get(&r) {
+                        self.tooltip = Some(Text::from_multiline(vec![
+                            Line(format!("Average delay: {}", *total / (*count as f64))),
+                            Line(format!(
+                                "Total delay: {} over {} vehicles",
+                                total,
+                                prettyprint_usize(*count)
+                            ))
+                            .secondary(),
+                        ]));
+                    }
+                }
+            }
+        } else {
+            self.tooltip = None;
+        }
+
+        <dyn Layer>::simple_event(ctx, &mut self.panel)
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+        if let Some(ref txt) = self.tooltip {
+            g.draw_mouse_tooltip(txt.clone());
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl RoadCongestion {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> RoadCongestion {
+        let delays = road_delays(app);
+        let mut colorer = ColorNetwork::new(app);
+        for (r, (count, total)) in &delays {
+            let avg = *total / (*count as f64);
+            colorer.add_r(
+                *r,
+                app.cs
+                    .good_to_bad_red
+                    .eval((avg / Duration::minutes(2)).min(1.0)),
+            );
+        }
+
+        RoadCongestion {
+            time: app.primary.sim.time(),
+            delays,
+            draw: colorer.build(ctx),
+            panel: Panel::new_builder(Widget::col(vec![
+                header(ctx, "Congestion"),
+                "Average delay for vehicles waiting to turn at the end of each road"
+                    .text_widget(ctx),
+                "(Only measured at traffic signals)".text_widget(ctx),
+                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0", "1 min", "2+ min"]),
+            ]))
+            .aligned_pair(PANEL_PLACEMENT)
+            .build(ctx),
+            tooltip: None,
+        }
+    }
+}
+
+// Group the delays measured at traffic signals by the road vehicles were waiting on. Reuses what
+// Analytics already records, so savestates and prebaked results don't change format.
+fn road_delays(app: &App) -> BTreeMap<RoadID, (usize, Duration)> {
+    let map = &app.primary.map;
+    let mut delays = BTreeMap::new();
+    for (i, list) in &app.primary.sim.get_analytics().intersection_delays {
+        let movements: Vec<_> = map.get_i(*i).movements.keys().collect();
+        for (idx, _, delay, agent_type) in list {
+            if matches!(agent_type, AgentType::Pedestrian | AgentType::TransitRider) {
+                continue;
+            }
+            let entry = delays
+                .entry(movements[*idx as usize].from.road)
+                .or_insert((0, Duration::ZERO));
+            entry.0 += 1;
+            entry.1 += *delay;
+        }
+    }
+    delays
+}
+
 pub struct PedestrianCrowding {
     time: Time,
     panel: Panel,
@@ -779,3 +909,34 @@ fn export_throughput(app: &App) -> Result<(String, String)> {
 
     Ok((path1, path2))
 }
+
+/// Export hourly throughput summed over all roads currently on screen, so somebody can zoom into
+/// one corridor and plot how it's used over the day
+fn export_throughput_time_series(ctx: &EventCtx, app: &App) -> Result<String> {
+    let bounds = ctx.canvas.get_screen_bounds();
+    let roads: BTreeSet<RoadID> = app
+        .primary
+        .map
+        .all_roads()
+        .iter()
+        .filter(|r| bounds.contains(r.center_pts.middle()))
+        .map(|r| r.id)
+        .collect();
+    if roads.is_empty() {
+        bail!("No roads are visible on screen");
+    }
+
+    let path = format!(
+        "road_throughput_time_series_{}_{}.csv",
+        app.primary.map.get_name().as_filename(),
+        app.primary.sim.time().as_filename()
+    );
+    abstio::write_file(
+        path,
+        app.primary
+            .sim
+            .get_analytics()
+            .road_thruput
+            .export_time_series_csv(&roads, app.primary.sim.time()),
+    )
+}
@@ -162,13 +162,14 @@ impl Layers {
                 }
                 "steep streets" => {
                     if self.panel.is_checked("steep streets") {
-                        let (mut colorer, _, _) =
+                        let (colorer, _, _) =
                             crate::layer::elevation::SteepStreets::make_colorer(ctx, app);
+                        let mut draw = colorer.into_builder();
                         // The Colorer fades the map as the very first thing in the batch, but we
                         // don't want to do that twice.
                         // TODO Can't use no_fading without complicating make_colorer...
-                        colorer.draw.unzoomed.shift();
-                        self.steep_streets = Some(colorer.draw.unzoomed.upload(ctx));
+                        draw.unzoomed.shift();
+                        self.steep_streets = Some(draw.unzoomed.upload(ctx));
                     } else {
                         self.steep_streets = None;
                     }
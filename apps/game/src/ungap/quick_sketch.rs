@@ -1,6 +1,11 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use serde::Serialize;
+
 use crate::ID;
 use geom::Distance;
-use map_model::{BufferType, EditCmd, LaneID, LaneSpec, LaneType, RoadID};
+use map_model::{BufferType, EditCmd, EditRoad, LaneID, LaneSpec, LaneType, Map, MapEdits, RoadID};
 use widgetry::tools::{PopupMsg, URLManager};
 use widgetry::{
     lctrl, Choice, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, TextExt, Widget,
@@ -16,6 +21,9 @@ pub struct QuickSketch {
     top_panel: Panel,
     layers: Layers,
     route_sketcher: RouteSketcher,
+    /// The most recently hovered road that's been modified. It stays set after the cursor leaves,
+    /// so the revert button can be clicked.
+    revert_candidate: Option<RoadID>,
 
     map_edit_key: usize,
 }
@@ -32,6 +40,7 @@ impl QuickSketch {
             top_panel: Panel::empty(ctx),
             layers,
             route_sketcher: RouteSketcher::new(app),
+            revert_candidate: None,
 
             map_edit_key: usize::MAX,
         };
@@ -71,6 +80,25 @@ impl QuickSketch {
                     ],
                 ),
             ]));
+            let default_width = if self.top_panel.has_widget("buffer width") {
+                self.top_panel.dropdown_value("buffer width")
+            } else {
+                None
+            };
+            col.push(Widget::row(vec![
+                "Buffer width".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "buffer width",
+                    default_width,
+                    vec![
+                        Choice::new("typical", None),
+                        Choice::new("0.5m", Some(Distance::meters(0.5))),
+                        Choice::new("1.0m", Some(Distance::meters(1.0))),
+                        Choice::new("1.5m", Some(Distance::meters(1.5))),
+                    ],
+                ),
+            ]));
             col.push(
                 Widget::custom_row(vec![ctx
                     .style()
@@ -83,6 +111,22 @@ impl QuickSketch {
             );
         }
 
+        if let Some(r) = self.revert_candidate {
+            col.push(
+                ctx.style()
+                    .btn_outline
+                    .text(format!(
+                        "Revert {}",
+                        app.primary
+                            .map
+                            .get_r(r)
+                            .get_name(app.opts.language.as_ref())
+                    ))
+                    .hotkey(Key::R)
+                    .build_widget(ctx, "revert"),
+            );
+        }
+
         let proposals = proposal_management(ctx, app).section(ctx);
         self.top_panel = Tab::AddLanes.make_left_panel(
             ctx,
@@ -106,6 +150,11 @@ impl State<App> for QuickSketch {
         let key = app.primary.map.get_edits_change_key();
         if self.map_edit_key != key {
             self.map_edit_key = key;
+            if let Some(r) = self.revert_candidate {
+                if !app.primary.map.get_edits().changed_roads.contains(&r) {
+                    self.revert_candidate = None;
+                }
+            }
             self.update_top_panel(ctx, app);
         }
 
@@ -127,6 +176,13 @@ impl State<App> for QuickSketch {
                     });
             }
             if let Some(ID::Road(r)) = app.primary.current_selection {
+                if self.revert_candidate != Some(r)
+                    && app.primary.map.get_edits().changed_roads.contains(&r)
+                {
+                    self.revert_candidate = Some(r);
+                    self.update_top_panel(ctx, app);
+                }
+
                 // If it's light rail, a footway, etc, then the first lane should trigger
                 // can_edit_lane
                 if ctx.normal_left_click() && can_edit_lane(app, LaneID { road: r, offset: 0 }) {
@@ -145,11 +201,17 @@ impl State<App> for QuickSketch {
                         app,
                         self.route_sketcher.all_roads(app),
                         self.top_panel.dropdown_value("buffer type"),
+                        self.top_panel.dropdown_value("buffer width"),
                     );
                     self.route_sketcher = RouteSketcher::new(app);
                     self.update_top_panel(ctx, app);
                     return Transition::Push(PopupMsg::new_state(ctx, "Changes made", messages));
                 }
+                "revert" => {
+                    revert_road(ctx, app, self.revert_candidate.take().unwrap());
+                    self.update_top_panel(ctx, app);
+                    return Transition::Keep;
+                }
                 "Open a proposal" => {
                     // Dummy mode, just to allow all edits
                     // TODO Actually, should we make one to express that only road edits are
@@ -174,6 +236,21 @@ impl State<App> for QuickSketch {
                 "Share proposal" => {
                     return Transition::Push(share::ShareProposal::new_state(ctx, app, "--ungap"));
                 }
+                "Export lane changes" => {
+                    return Transition::Push(match export_lane_changes(app) {
+                        Ok(paths) => PopupMsg::new_state(
+                            ctx,
+                            "Lane changes exported",
+                            paths
+                                .into_iter()
+                                .map(|path| format!("Wrote {}", path))
+                                .collect(),
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                }
                 x => {
                     // TODO More brittle routing of outcomes.
                     if self.route_sketcher.on_click(x) {
@@ -211,12 +288,14 @@ fn make_quick_changes(
     app: &mut App,
     roads: Vec<RoadID>,
     buffer_type: Option<BufferType>,
+    buffer_width: Option<Distance>,
 ) -> Vec<String> {
     // TODO Erasing changes
 
     let mut edits = app.primary.map.get_edits().clone();
     let mut changed = 0;
     let mut unchanged = 0;
+    let mut warnings = Vec::new();
     for r in roads {
         let old = app.primary.map.get_r_edit(r);
         let mut new = old.clone();
@@ -225,10 +304,16 @@ fn make_quick_changes(
             buffer_type,
             app.primary.map.get_config().driving_side,
         );
+        if let (Some(bt), Some(width)) = (buffer_type, buffer_width) {
+            set_new_buffer_widths(&old.lanes_ltr, &mut new.lanes_ltr, bt, width);
+        }
         if old == new {
             unchanged += 1;
         } else {
             changed += 1;
+            if let Some(warning) = check_widths(app, r, &old.lanes_ltr, &new.lanes_ltr) {
+                warnings.push(warning);
+            }
             edits.commands.push(EditCmd::ChangeRoad { r, old, new });
         }
     }
@@ -241,9 +326,196 @@ fn make_quick_changes(
     if unchanged > 0 {
         messages.push(format!("Didn't modify {} segments -- the road isn't wide enough, or there's already a bike lane", unchanged));
     }
+    if !warnings.is_empty() {
+        messages.push(String::new());
+        messages.push(format!(
+            "Double-check the width of {} segments:",
+            warnings.len()
+        ));
+        messages.extend(warnings);
+    }
     messages
 }
 
+/// Flag changes that don't fit in the original road width or squeeze a driving lane too much
+fn check_widths(app: &App, r: RoadID, old: &[LaneSpec], new: &[LaneSpec]) -> Option<String> {
+    // Below this, even a car lane is sub-standard
+    let min_driving_width = Distance::meters(2.5);
+
+    let name = app
+        .primary
+        .map
+        .get_r(r)
+        .get_name(app.opts.language.as_ref());
+    let old_total: Distance = old.iter().map(|spec| spec.width).sum();
+    let new_total: Distance = new.iter().map(|spec| spec.width).sum();
+    if new_total > old_total {
+        return Some(format!(
+            "{}: result is {} wider than available space",
+            name,
+            (new_total - old_total).to_string(&app.opts.units)
+        ));
+    }
+    if let Some(narrowest) = new
+        .iter()
+        .filter(|spec| spec.lt == LaneType::Driving)
+        .map(|spec| spec.width)
+        .min()
+    {
+        if narrowest < min_driving_width {
+            return Some(format!(
+                "{}: a driving lane would only be {} wide",
+                name,
+                narrowest.to_string(&app.opts.units)
+            ));
+        }
+    }
+    None
+}
+
+/// maybe_add_bike_lanes always uses the typical width for buffers. Override it for the buffers it
+/// just inserted, leaving any that existed before alone.
+fn set_new_buffer_widths(
+    old: &[LaneSpec],
+    new: &mut [LaneSpec],
+    buffer_type: BufferType,
+    width: Distance,
+) {
+    let lt = LaneType::Buffer(buffer_type);
+    let mut existing = old.iter().filter(|spec| spec.lt == lt).count();
+    for spec in new.iter_mut().filter(|spec| spec.lt == lt) {
+        if existing > 0 {
+            existing -= 1;
+        } else {
+            spec.width = width;
+        }
+    }
+}
+
+/// How a road looked in the base map, before any of these edits
+fn original_road(map: &Map, edits: &MapEdits, r: RoadID) -> EditRoad {
+    // The first command touching this road remembers how it started
+    edits
+        .commands
+        .iter()
+        .find_map(|cmd| match cmd {
+            EditCmd::ChangeRoad {
+                r: changed, old, ..
+            } if *changed == r => Some(old.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| EditRoad::get_orig_from_osm(map.get_r(r), map.get_config()))
+}
+
+/// Restore one road to its state in the base map, keeping all other edits
+fn revert_road(ctx: &mut EventCtx, app: &mut App, r: RoadID) {
+    let map = &app.primary.map;
+    let mut edits = map.get_edits().clone();
+    let orig = original_road(map, &edits, r);
+    edits.commands.push(EditCmd::ChangeRoad {
+        r,
+        old: map.get_r_edit(r),
+        new: orig,
+    });
+    apply_map_edits(ctx, app, edits);
+}
+
+/// Lists the lanes from left to right, like "parking|driving|driving|parking"
+fn describe_cross_section(lanes_ltr: &[LaneSpec]) -> String {
+    lanes_ltr
+        .iter()
+        .map(|spec| spec.lt.short_name())
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// For every road whose lanes changed, write the cross-section before and after, as both a CSV
+/// and an HTML table. Returns the paths written.
+fn export_lane_changes(app: &App) -> Result<Vec<String>> {
+    let map = &app.primary.map;
+    let edits = map.get_edits();
+
+    let mut rows = Vec::new();
+    for r in &edits.changed_roads {
+        let before = original_road(map, edits, *r).lanes_ltr;
+        let after = map.get_r_edit(*r).lanes_ltr;
+        // Speed limit or access changes don't affect the cross-section
+        if before == after {
+            continue;
+        }
+        let road = map.get_r(*r);
+        rows.push(LaneChangeRow {
+            osm_way_id: road.orig_id.osm_way_id.0,
+            name: road.get_name(app.opts.language.as_ref()),
+            before: describe_cross_section(&before),
+            after: describe_cross_section(&after),
+        });
+    }
+
+    let basename = format!(
+        "lane_changes_{}_{}",
+        map.get_name().as_filename(),
+        edits.edits_name
+    );
+
+    let mut out = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut out);
+        for row in &rows {
+            writer.serialize(row)?;
+        }
+        writer.flush()?;
+    }
+    let csv = String::from_utf8(out)?;
+
+    let escape = |x: &str| {
+        x.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    let mut html = String::new();
+    writeln!(html, "<html><head><meta charset=\"utf-8\">")?;
+    writeln!(
+        html,
+        "<title>Lane changes in {}</title></head><body>",
+        escape(&edits.edits_name)
+    )?;
+    writeln!(
+        html,
+        "<h1>Lane changes in {}</h1>",
+        escape(&edits.edits_name)
+    )?;
+    writeln!(html, "<p>Lanes are listed from left to right.</p>")?;
+    writeln!(
+        html,
+        "<table border=\"1\"><tr><th>Road</th><th>Before</th><th>After</th></tr>"
+    )?;
+    for row in &rows {
+        writeln!(
+            html,
+            "<tr><td><a href=\"https://www.openstreetmap.org/way/{}\">{}</a></td><td>{}</td><td>{}</td></tr>",
+            row.osm_way_id,
+            escape(&row.name),
+            escape(&row.before),
+            escape(&row.after)
+        )?;
+    }
+    writeln!(html, "</table></body></html>")?;
+
+    Ok(vec![
+        abstio::write_file(format!("{}.csv", basename), csv)?,
+        abstio::write_file(format!("{}.html", basename), html)?,
+    ])
+}
+
+#[derive(Serialize)]
+struct LaneChangeRow {
+    osm_way_id: i64,
+    name: String,
+    before: String,
+    after: String,
+}
+
 fn proposal_management(ctx: &mut EventCtx, app: &App) -> Widget {
     let mut col = Vec::new();
     let edits = app.primary.map.get_edits();
@@ -305,6 +577,13 @@ fn proposal_management(ctx: &mut EventCtx, app: &App) -> Widget {
             .disabled(edits.commands.is_empty())
             .build_def(ctx),
     );
+    col.push(
+        ctx.style()
+            .btn_outline
+            .text("Export lane changes")
+            .disabled(edits.commands.is_empty())
+            .build_def(ctx),
+    );
 
     Widget::col(col)
 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use abstio::MapName;
 use abstutil::Timer;
 use geom::{Duration, Pt2D, Time};
@@ -8,12 +10,13 @@ use map_gui::render::{DrawMap, DrawOptions};
 use map_gui::tools::CameraState;
 use map_gui::tools::DrawSimpleRoadLabels;
 use map_gui::{AppLike, ID};
-use map_model::{osm, CrossingType, IntersectionID, Map, RoutingParams};
+use map_model::{osm, CrossingType, IntersectionID, Map, RoadID, RoutingParams};
+use serde::{Deserialize, Serialize};
 use widgetry::tools::URLManager;
 use widgetry::{Canvas, Drawable, EventCtx, GfxCtx, SharedAppState, State, Warper};
 
 use crate::logic::Partitioning;
-use crate::{logic, pages, render, Edits, FilterType, NeighbourhoodID};
+use crate::{logic, pages, render, Edits, Enforcement, FilterType, NeighbourhoodID};
 
 pub type Transition = widgetry::Transition<App>;
 
@@ -31,13 +34,20 @@ pub struct PerMap {
 
     // The last edited neighbourhood
     pub current_neighbourhood: Option<NeighbourhoodID>,
+    /// What the lock on the cell layout last compared against
+    pub checked_cells: Option<pages::CheckedCells>,
 
     // These capture modal filters that exist in the map already. Whenever we pathfind in this app
     // in the "before changes" case, we have to use these. Do NOT use the map's built-in
     // pathfinder. (https://github.com/a-b-street/abstreet/issues/852 would make this more clear)
     pub routing_params_before_changes: RoutingParams,
+    // The same filters, for anything comparing against the street network as it was loaded
+    pub edits_before_changes: Edits,
     pub proposals: crate::save::Proposals,
     pub impact: logic::Impact,
+    /// The start and end of every walking trip in the travel demand model, once it's loaded for
+    /// the crossings page
+    pub walking_desire_lines: Option<Vec<(Pt2D, Pt2D)>>,
 
     pub consultation: Option<NeighbourhoodID>,
     pub consultation_id: Option<String>,
@@ -47,6 +57,11 @@ pub struct PerMap {
     pub draw_all_local_road_labels: Option<DrawSimpleRoadLabels>,
     pub draw_poi_icons: Drawable,
     pub draw_bus_routes: Drawable,
+    pub draw_turn_restrictions: Drawable,
+    pub draw_one_way_edits: Drawable,
+    /// Edited roads at their original width, for comparing against the unedited map
+    pub draw_unedited_roads: Drawable,
+    pub draw_speed_limit_edits: Drawable,
 
     pub current_trip_name: Option<String>,
 }
@@ -57,12 +72,16 @@ impl PerMap {
         mut map: Map,
         opts: &Options,
         cs: &ColorScheme,
+        filter_icon_scale: f64,
+        filter_zoom: render::ZoomThresholds,
+        filter_style: crate::FilterStyle,
         timer: &mut Timer,
     ) -> Self {
         // Do this before creating the default partitioning. Non-driveable roads in OSM get turned
         // into driveable roads and a filter here, and we want the partitioning to "see" those
         // roads.
         let edits = logic::transform_existing_filters(&mut map, timer);
+        let edits_before_changes = edits.clone();
         let mut proposals = crate::save::Proposals::new(&map, edits, timer);
 
         let mut routing_params_before_changes = map.routing_params().clone();
@@ -71,24 +90,49 @@ impl PerMap {
             .edits
             .update_routing_params(&mut routing_params_before_changes);
 
-        let draw_all_filters = proposals.current_proposal.edits.draw(ctx, &map);
+        let draw_all_filters = proposals
+            .current_proposal
+            .edits
+            .draw(ctx, &map, filter_icon_scale, filter_zoom, filter_style)
+            .with_summary(render::render_filter_summary(
+                ctx,
+                &map,
+                &proposals.current_proposal.partitioning,
+                &proposals.current_proposal.edits,
+            ));
 
         logic::populate_existing_crossings(&map, &mut proposals.current_proposal.edits);
+        // Crossings that already exist aren't a change somebody needs to save
+        proposals.mark_saved();
 
         // Create DrawMap after transform_existing_filters, which modifies road widths
         let draw_map = DrawMap::new(ctx, &map, opts, cs, timer);
         let draw_poi_icons = render::render_poi_icons(ctx, &map);
         let draw_bus_routes = render::render_bus_routes(ctx, &map);
+        let draw_turn_restrictions = render::render_turn_restrictions(ctx, &map);
+        let draw_one_way_edits =
+            render::render_one_way_edits(ctx, &map, &proposals.current_proposal.edits);
+        let draw_unedited_roads =
+            render::render_unedited_roads(ctx, &map, cs, &proposals.current_proposal.edits);
+        let draw_speed_limit_edits = render::render_speed_limit_edits(
+            ctx,
+            &map,
+            &proposals.current_proposal.edits,
+            &opts.units,
+        );
 
         let per_map = Self {
             map,
             draw_map,
 
             current_neighbourhood: None,
+            checked_cells: None,
 
             routing_params_before_changes,
+            edits_before_changes,
             proposals,
             impact: logic::Impact::empty(ctx),
+            walking_desire_lines: None,
 
             consultation: None,
             consultation_id: None,
@@ -98,6 +142,10 @@ impl PerMap {
             draw_all_local_road_labels: None,
             draw_poi_icons,
             draw_bus_routes,
+            draw_turn_restrictions,
+            draw_one_way_edits,
+            draw_unedited_roads,
+            draw_speed_limit_edits,
 
             current_trip_name: None,
         };
@@ -115,7 +163,12 @@ impl PerMap {
 
 pub struct Session {
     pub edit_mode: pages::EditMode,
+    pub view_mode: pages::ViewMode,
     pub filter_type: FilterType,
+    /// The filter types picked most recently, newest first. Not remembered across runs.
+    pub recent_filter_types: Vec<FilterType>,
+    /// Only used when filter_type is a bus gate
+    pub bus_gate_enforcement: Enforcement,
     pub crossing_type: CrossingType,
 
     // Remember form settings in different tabs.
@@ -126,12 +179,94 @@ pub struct Session {
     pub show_walking_cycling_routes: bool,
     // Select boundary:
     pub add_intermediate_blocks: bool,
+    // Design LTN:
+    /// Fade everything outside the cell containing this road. Any road works as an anchor, since
+    /// cells get renumbered after every edit.
+    pub focus_cell: Option<RoadID>,
+    /// Per map, the color (an index into `colors::CELLS`) picked for the cell containing each
+    /// road. Remembered across runs, so screenshots stay consistent.
+    pub cell_colors: BTreeMap<MapName, BTreeMap<RoadID, usize>>,
+
+    // Save a proposal:
+    /// Leave out filters that were detected from the map, not placed by the user
+    pub save_user_edits_only: bool,
 
     // Shared in all modes
     pub layers: crate::components::Layers,
     pub manage_proposals: bool,
 }
 
+const MAX_RECENT_FILTER_TYPES: usize = 3;
+
+/// The parts of `Session` remembered across runs of the tool
+#[derive(Serialize, Deserialize)]
+struct SavedSession {
+    filter_type: FilterType,
+    #[serde(default)]
+    bus_gate_enforcement: Enforcement,
+    #[serde(default)]
+    filter_detail_zoom: Option<f64>,
+    #[serde(default)]
+    filter_zoom_step: Option<f64>,
+    /// Stored as lists, since JSON keys have to be strings
+    #[serde(default)]
+    cell_colors: Vec<(MapName, Vec<(RoadID, usize)>)>,
+}
+
+impl Session {
+    /// Change the type of filter to place, and remember it for the quick palette
+    pub fn pick_filter_type(&mut self, filter_type: FilterType) {
+        self.filter_type = filter_type;
+        self.recent_filter_types.retain(|x| *x != filter_type);
+        self.recent_filter_types.insert(0, filter_type);
+        self.recent_filter_types.truncate(MAX_RECENT_FILTER_TYPES);
+    }
+
+    /// Remember the current filter type as the default for next time
+    pub fn save_settings(&self) {
+        abstio::write_json(
+            abstio::path_player("ltn_session.json"),
+            &SavedSession {
+                filter_type: self.filter_type,
+                bus_gate_enforcement: self.bus_gate_enforcement,
+                filter_detail_zoom: Some(self.layers.filter_detail_zoom),
+                filter_zoom_step: Some(self.layers.filter_zoom_step),
+                cell_colors: self
+                    .cell_colors
+                    .iter()
+                    .map(|(name, colors)| (name.clone(), colors.clone().into_iter().collect()))
+                    .collect(),
+            },
+        );
+    }
+
+    fn load_settings(&mut self) {
+        match abstio::maybe_read_json::<SavedSession>(
+            abstio::path_player("ltn_session.json"),
+            &mut Timer::throwaway(),
+        ) {
+            Ok(saved) => {
+                self.filter_type = saved.filter_type;
+                self.bus_gate_enforcement = saved.bus_gate_enforcement;
+                if let Some(zoom) = saved.filter_detail_zoom {
+                    self.layers.filter_detail_zoom = zoom;
+                }
+                if let Some(step) = saved.filter_zoom_step {
+                    self.layers.filter_zoom_step = step;
+                }
+                self.cell_colors = saved
+                    .cell_colors
+                    .into_iter()
+                    .map(|(name, colors)| (name, colors.into_iter().collect()))
+                    .collect();
+            }
+            Err(err) => {
+                info!("No saved session settings, using defaults. {}", err);
+            }
+        }
+    }
+}
+
 impl AppLike for App {
     #[inline]
     fn map(&self) -> &Map {
@@ -164,10 +299,20 @@ impl AppLike for App {
 
     fn map_switched(&mut self, ctx: &mut EventCtx, map: Map, timer: &mut Timer) {
         CameraState::save(ctx.canvas, self.per_map.map.get_name());
-        self.per_map = PerMap::new(ctx, map, &self.opts, &self.cs, timer);
+        // Before PerMap::new, which labels speed limits
+        self.opts.units.metric = map.get_name().city.uses_metric();
+        self.per_map = PerMap::new(
+            ctx,
+            map,
+            &self.opts,
+            &self.cs,
+            self.session.layers.filter_icon_scale,
+            self.session.layers.filter_zoom(),
+            self.session.layers.filter_style,
+            timer,
+        );
         self.per_map.draw_major_road_labels =
             DrawSimpleRoadLabels::only_major_roads(ctx, self, render::colors::MAIN_ROAD_LABEL);
-        self.opts.units.metric = self.per_map.map.get_name().city.uses_metric();
     }
 
     fn draw_with_opts(&self, g: &mut GfxCtx, _l: DrawOptions) {
@@ -223,19 +368,26 @@ impl App {
         abstutil::logger::setup();
         ctx.canvas.settings = opts.canvas_settings.clone();
 
-        let session = Session {
+        let mut session = Session {
             edit_mode: pages::EditMode::Filters,
+            view_mode: pages::ViewMode::Cells,
             filter_type: FilterType::WalkCycleOnly,
+            recent_filter_types: Vec::new(),
+            bus_gate_enforcement: Enforcement::Camera,
             crossing_type: CrossingType::Unsignalized,
 
             draw_neighbourhood_style: pages::PickAreaStyle::Simple,
             main_road_penalty: 1.0,
             show_walking_cycling_routes: false,
             add_intermediate_blocks: true,
+            focus_cell: None,
+            cell_colors: BTreeMap::new(),
+            save_user_edits_only: false,
 
             layers: crate::components::Layers::new(ctx),
             manage_proposals: false,
         };
+        session.load_settings();
 
         let cs = ColorScheme::new(ctx, opts.color_scheme);
         let app = App {
@@ -245,6 +397,9 @@ impl App {
                 Map::almost_blank(),
                 &opts,
                 &cs,
+                session.layers.filter_icon_scale,
+                session.layers.filter_zoom(),
+                session.layers.filter_style,
                 &mut Timer::throwaway(),
             ),
             cs,
@@ -290,12 +445,14 @@ impl App {
 
     pub fn calculate_draw_all_local_road_labels(&mut self, ctx: &mut EventCtx) {
         if self.per_map.draw_all_local_road_labels.is_none() {
-            self.per_map.draw_all_local_road_labels = Some(DrawSimpleRoadLabels::new(
-                ctx,
-                self,
-                render::colors::LOCAL_ROAD_LABEL,
-                Box::new(|r| r.get_rank() == osm::RoadRank::Local && !r.is_light_rail()),
-            ));
+            self.per_map.draw_all_local_road_labels =
+                Some(DrawSimpleRoadLabels::new_with_name_overrides(
+                    ctx,
+                    self,
+                    render::colors::LOCAL_ROAD_LABEL,
+                    Box::new(|r| r.get_rank() == osm::RoadRank::Local && !r.is_light_rail()),
+                    self.edits().road_names.clone(),
+                ));
         }
     }
 }
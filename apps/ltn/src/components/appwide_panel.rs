@@ -35,25 +35,44 @@ impl AppwidePanel {
             return match x.as_ref() {
                 "Home" => {
                     if app.per_map.consultation.is_none() {
-                        Some(Transition::Clear(vec![
-                            map_gui::tools::TitleScreen::new_state(
-                                ctx,
-                                app,
-                                map_gui::tools::Executable::LTN,
-                                Box::new(|ctx, app, _| pages::PickArea::new_state(ctx, app)),
-                            ),
-                        ]))
+                        Some(confirm_leaving(
+                            ctx,
+                            app,
+                            preserve_state,
+                            Box::new(|ctx, app| {
+                                Transition::Clear(vec![map_gui::tools::TitleScreen::new_state(
+                                    ctx,
+                                    app,
+                                    map_gui::tools::Executable::LTN,
+                                    Box::new(|ctx, app, _| pages::PickArea::new_state(ctx, app)),
+                                )])
+                            }),
+                        ))
                     } else {
                         Some(Transition::Push(pages::About::new_state(ctx)))
                     }
                 }
-                "change map" => Some(Transition::Push(map_gui::tools::CityPicker::new_state(
+                "change map" => Some(confirm_leaving(
                     ctx,
                     app,
-                    Box::new(|ctx, app| Transition::Replace(pages::PickArea::new_state(ctx, app))),
-                ))),
+                    preserve_state,
+                    Box::new(|ctx, app| {
+                        Transition::Push(map_gui::tools::CityPicker::new_state(
+                            ctx,
+                            app,
+                            Box::new(|ctx, app| {
+                                Transition::Replace(pages::PickArea::new_state(ctx, app))
+                            }),
+                        ))
+                    }),
+                )),
                 "search" => Some(Transition::Push(
-                    map_gui::tools::Navigator::new_state_with_target_zoom(ctx, app, 4.0),
+                    map_gui::tools::Navigator::new_state_with_road_names(
+                        ctx,
+                        app,
+                        4.0,
+                        app.edits().road_names.clone(),
+                    ),
                 )),
                 "help" => Some(Transition::Push(PopupMsg::new_state(ctx, "Help", help()))),
                 "about this tool" => Some(Transition::Push(pages::About::new_state(ctx))),
@@ -119,6 +138,36 @@ fn launch_impact(ctx: &mut EventCtx, app: &mut App) -> Transition {
         })))
 }
 
+/// Leaving the map discards the current proposal, so if it has unsaved changes, first ask
+/// whether to save it. Otherwise just `leave`.
+fn confirm_leaving(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    preserve_state: &crate::save::PreserveState,
+    leave: Box<dyn FnOnce(&mut EventCtx, &mut App) -> Transition>,
+) -> Transition {
+    if !app.per_map.proposals.has_unsaved_changes() {
+        return leave(ctx, app);
+    }
+    let preserve_state = preserve_state.clone();
+    Transition::Push(ChooseSomething::new_state(
+        ctx,
+        "This proposal has unsaved changes",
+        vec![
+            Choice::string("Keep editing"),
+            Choice::string("Save first"),
+            Choice::string("Leave without saving"),
+        ],
+        Box::new(move |choice, ctx, app| match choice.as_ref() {
+            "Save first" => {
+                Transition::Replace(crate::save::SaveDialog::new_state(ctx, app, preserve_state))
+            }
+            "Leave without saving" => Transition::Multi(vec![Transition::Pop, leave(ctx, app)]),
+            _ => Transition::Pop,
+        }),
+    ))
+}
+
 fn make_top_panel(ctx: &mut EventCtx, app: &App, mode: Mode) -> Panel {
     let consultation = app.per_map.consultation.is_some();
 
@@ -240,6 +289,7 @@ fn make_top_panel(ctx: &mut EventCtx, app: &App, mode: Mode) -> Panel {
     Panel::new_builder(Widget::col(col).corner_rounding(CornerRounding::NoRounding))
         .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
         .dims_width(PanelDims::ExactPercent(1.0))
+        .keyboard_navigation()
         .build(ctx)
 }
 
@@ -280,5 +330,6 @@ fn make_left_panel(ctx: &mut EventCtx, app: &App, top_panel: &Panel, mode: Mode)
         .dims_height(PanelDims::ExactPixels(
             ctx.canvas.window_height - top_height,
         ))
+        .keyboard_navigation()
         .build(ctx)
 }
@@ -9,8 +9,8 @@ use widgetry::{
 };
 
 use crate::components::Mode;
-use crate::render::colors;
-use crate::{pages, App, FilterType, Transition};
+use crate::render::{colors, ZoomThresholds};
+use crate::{pages, App, Enforcement, FilterStyle, FilterType, Transition};
 
 // Partly copied from ungap/layers.s
 
@@ -20,11 +20,39 @@ pub struct Layers {
     // (Mode, max zoom, min zoom, bottom bar position)
     panel_cache_key: (Mode, bool, bool, Option<f64>),
     show_bus_routes: bool,
+    show_turn_restrictions: bool,
+    show_one_way_edits: bool,
+    show_speed_limit_edits: bool,
     pub show_crossing_time: bool,
+    /// Color cells by how much shortcutting traffic they have, instead of arbitrarily
+    pub shade_cells_by_shortcuts: bool,
+    /// Only color minor streets by cell, leaving busier interior roads faded
+    pub only_color_minor_roads: bool,
+    /// Briefly crossfade between the old and new cell colors after an edit
+    pub animate_cell_changes: bool,
+    /// Draw hatched barriers where filters separate cells
+    pub hatch_cell_seams: bool,
+    /// Tint each side of a filtered road with the color of its cell
+    pub tint_filtered_roads: bool,
+    /// While designing one neighbourhood, draw filters elsewhere faintly
+    pub faint_other_filters: bool,
+    /// Multiplies the size of all filter icons
+    pub filter_icon_scale: f64,
+    /// At or above this zoom, filter icons are drawn at their real size. People with large or
+    /// high-DPI screens may want detail to appear sooner.
+    pub filter_detail_zoom: f64,
+    /// How much the zoom changes before low-zoom filter icons are resized
+    pub filter_zoom_step: f64,
+    /// Draw filters as realistic signs or simple bars
+    pub filter_style: FilterStyle,
+    /// Segments of one OSM way shorter than this (in meters) are filtered as one street
+    pub merge_short_segments_below: f64,
 
     // For the design LTN mode
     pub autofix_bus_gates: bool,
     pub autofix_one_ways: bool,
+    /// Ask before applying an edit that changes the cells
+    pub lock_cells: bool,
 }
 
 impl Layers {
@@ -35,10 +63,25 @@ impl Layers {
             minimized: true,
             panel_cache_key: (Mode::Impact, false, false, None),
             show_bus_routes: false,
+            show_turn_restrictions: false,
+            show_one_way_edits: false,
+            show_speed_limit_edits: false,
             show_crossing_time: false,
+            shade_cells_by_shortcuts: false,
+            only_color_minor_roads: false,
+            animate_cell_changes: true,
+            hatch_cell_seams: false,
+            tint_filtered_roads: false,
+            faint_other_filters: false,
+            filter_icon_scale: 1.0,
+            filter_detail_zoom: ZoomThresholds::FILTERS.min_zoom_for_detail,
+            filter_zoom_step: ZoomThresholds::FILTERS.step_size,
+            filter_style: FilterStyle::Signs,
+            merge_short_segments_below: 10.0,
 
             autofix_bus_gates: false,
             autofix_one_ways: false,
+            lock_cells: false,
         }
     }
 
@@ -74,10 +117,57 @@ impl Layers {
                     self.show_bus_routes = self.panel.is_checked(&x);
                     self.update_panel(ctx, cs, bottom_panel);
                     return Some(Transition::Keep);
+                } else if x == "show existing turn restrictions" {
+                    self.show_turn_restrictions = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
+                } else if x == "show changed one-ways" {
+                    self.show_one_way_edits = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
+                } else if x == "show changed speed limits" {
+                    self.show_speed_limit_edits = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
                 } else if x == "show time to nearest crossing" {
                     self.show_crossing_time = self.panel.is_checked(&x);
                     self.update_panel(ctx, cs, bottom_panel);
                     return Some(Transition::Keep);
+                } else if x == "shade cells by shortcuts" {
+                    self.shade_cells_by_shortcuts = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    // The cells are drawn by the current page
+                    return Some(Transition::Recreate);
+                } else if x == "only color minor streets" {
+                    self.only_color_minor_roads = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Recreate);
+                } else if x == "hatch seams between cells" {
+                    self.hatch_cell_seams = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Recreate);
+                } else if x == "tint filtered roads by cell" {
+                    self.tint_filtered_roads = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Recreate);
+                } else if x == "animate cell changes" {
+                    self.animate_cell_changes = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
+                } else if x == "show other areas' filters faintly" {
+                    self.faint_other_filters = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Recreate);
+                } else if x == "draw filters as schematic bars" {
+                    self.filter_style = if self.panel.is_checked(&x) {
+                        FilterStyle::Schematic
+                    } else {
+                        FilterStyle::Signs
+                    };
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::ModifyState(Box::new(|_, ctx, app| {
+                        crate::redraw_all_filters(ctx, app);
+                    })));
                 } else if x == "Use bus gates when needed" {
                     self.autofix_bus_gates = self.panel.is_checked(&x);
                     self.update_panel(ctx, cs, bottom_panel);
@@ -86,6 +176,33 @@ impl Layers {
                     self.autofix_one_ways = self.panel.is_checked(&x);
                     self.update_panel(ctx, cs, bottom_panel);
                     return Some(Transition::Keep);
+                } else if x == "Lock the cell layout" {
+                    self.lock_cells = self.panel.is_checked(&x);
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
+                } else if x == "filter_icon_scale" {
+                    self.filter_icon_scale =
+                        self.panel.spinner::<RoundedF64>("filter_icon_scale").0;
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::ModifyState(Box::new(|_, ctx, app| {
+                        crate::redraw_all_filters(ctx, app);
+                    })));
+                } else if x == "filter_detail_zoom" || x == "filter_zoom_step" {
+                    self.filter_detail_zoom =
+                        self.panel.spinner::<RoundedF64>("filter_detail_zoom").0;
+                    self.filter_zoom_step = self.panel.spinner::<RoundedF64>("filter_zoom_step").0;
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::ModifyState(Box::new(|_, ctx, app| {
+                        app.session.save_settings();
+                        crate::redraw_all_filters(ctx, app);
+                    })));
+                } else if x == "merge_short_segments_below" {
+                    self.merge_short_segments_below = self
+                        .panel
+                        .spinner::<RoundedF64>("merge_short_segments_below")
+                        .0;
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
                 }
 
                 ctx.set_scale_factor(self.panel.spinner::<RoundedF64>("scale_factor").0);
@@ -111,11 +228,35 @@ impl Layers {
         None
     }
 
+    /// When filter icons switch to full detail, following the user's settings
+    pub fn filter_zoom(&self) -> ZoomThresholds {
+        ZoomThresholds {
+            min_zoom_for_detail: self.filter_detail_zoom,
+            step_size: self.filter_zoom_step,
+            ..ZoomThresholds::FILTERS
+        }
+    }
+
     pub fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.draw_without_edits(g, app);
+        if self.show_one_way_edits {
+            g.redraw(&app.per_map.draw_one_way_edits);
+        }
+        if self.show_speed_limit_edits {
+            g.redraw(&app.per_map.draw_speed_limit_edits);
+        }
+    }
+
+    /// Like `draw`, but skips the layers showing changes from the proposal, like one-way, lane
+    /// width, and speed limit edits
+    pub fn draw_without_edits(&self, g: &mut GfxCtx, app: &App) {
         self.panel.draw(g);
         if self.show_bus_routes {
             g.redraw(&app.per_map.draw_bus_routes);
         }
+        if self.show_turn_restrictions {
+            g.redraw(&app.per_map.draw_turn_restrictions);
+        }
     }
 
     pub fn show_bus_routes(
@@ -155,7 +296,7 @@ impl Layers {
                 VerticalAlignment::Above(bottom_panel.panel_rect().y1 - buffer),
             );
         }
-        self.panel = builder.build_custom(ctx);
+        self.panel = builder.keyboard_navigation().build_custom(ctx);
     }
 
     fn make_legend(&self, ctx: &mut EventCtx, cs: &ColorScheme) -> Widget {
@@ -192,6 +333,82 @@ impl Layers {
                     checkbox
                 }
             },
+            {
+                let checkbox = Toggle::checkbox(
+                    ctx,
+                    "show existing turn restrictions",
+                    None,
+                    self.show_turn_restrictions,
+                );
+                if self.show_turn_restrictions {
+                    checkbox.outline((1.0, Color::RED))
+                } else {
+                    checkbox
+                }
+            },
+            {
+                let checkbox =
+                    Toggle::checkbox(ctx, "show changed one-ways", None, self.show_one_way_edits);
+                if self.show_one_way_edits {
+                    checkbox.outline((1.0, colors::ONE_WAY_EDIT))
+                } else {
+                    checkbox
+                }
+            },
+            {
+                let checkbox = Toggle::checkbox(
+                    ctx,
+                    "show changed speed limits",
+                    None,
+                    self.show_speed_limit_edits,
+                );
+                if self.show_speed_limit_edits {
+                    checkbox.outline((1.0, colors::SPEED_LIMIT_EDIT))
+                } else {
+                    checkbox
+                }
+            },
+            if self.panel_cache_key.0 == Mode::ModifyNeighbourhood {
+                Widget::col(vec![
+                    Toggle::checkbox(
+                        ctx,
+                        "shade cells by shortcuts",
+                        None,
+                        self.shade_cells_by_shortcuts,
+                    ),
+                    Widget::row(vec![
+                        "Shortcuts per cell:".text_widget(ctx),
+                        ColorLegend::gradient_with_width(
+                            ctx,
+                            &colors::CELL_SHORTCUTS,
+                            vec!["few", "many"],
+                            150.0,
+                        ),
+                    ])
+                    .hide(!self.shade_cells_by_shortcuts),
+                    Toggle::checkbox(
+                        ctx,
+                        "only color minor streets",
+                        None,
+                        self.only_color_minor_roads,
+                    ),
+                    Toggle::checkbox(
+                        ctx,
+                        "hatch seams between cells",
+                        None,
+                        self.hatch_cell_seams,
+                    ),
+                    Toggle::checkbox(
+                        ctx,
+                        "tint filtered roads by cell",
+                        None,
+                        self.tint_filtered_roads,
+                    ),
+                    Toggle::checkbox(ctx, "animate cell changes", None, self.animate_cell_changes),
+                ])
+            } else {
+                Widget::nothing()
+            },
             if self.panel_cache_key.0 == Mode::Crossings {
                 Widget::col(vec![
                     Toggle::checkbox(
@@ -225,6 +442,60 @@ impl Layers {
                     0.1,
                 ),
             ]),
+            Toggle::checkbox(
+                ctx,
+                "draw filters as schematic bars",
+                None,
+                self.filter_style == FilterStyle::Schematic,
+            ),
+            Widget::row(vec![
+                "Adjust the size of filter icons:"
+                    .text_widget(ctx)
+                    .centered_vert(),
+                Spinner::f64_widget(
+                    ctx,
+                    "filter_icon_scale",
+                    (0.2, 2.0),
+                    self.filter_icon_scale,
+                    0.1,
+                ),
+            ]),
+            Widget::row(vec![
+                "Show full filter detail from zoom:"
+                    .text_widget(ctx)
+                    .centered_vert(),
+                Spinner::f64_widget(
+                    ctx,
+                    "filter_detail_zoom",
+                    (1.0, 15.0),
+                    self.filter_detail_zoom,
+                    0.5,
+                ),
+            ]),
+            Widget::row(vec![
+                "Resize zoomed-out filters every:"
+                    .text_widget(ctx)
+                    .centered_vert(),
+                Spinner::f64_widget(
+                    ctx,
+                    "filter_zoom_step",
+                    (0.05, 1.0),
+                    self.filter_zoom_step,
+                    0.05,
+                ),
+            ]),
+            Widget::row(vec![
+                "Filter short segments together (meters):"
+                    .text_widget(ctx)
+                    .centered_vert(),
+                Spinner::f64_widget(
+                    ctx,
+                    "merge_short_segments_below",
+                    (0.0, 50.0),
+                    self.merge_short_segments_below,
+                    1.0,
+                ),
+            ]),
         ])
         .padding(16)
     }
@@ -311,6 +582,12 @@ impl Mode {
                         .untinted()
                         .dims(30.0)
                         .into_widget(ctx),
+                    Image::from_path(
+                        FilterType::BusGate.svg_path_enforced_by(Enforcement::Physical),
+                    )
+                    .untinted()
+                    .dims(30.0)
+                    .into_widget(ctx),
                     Image::from_path(FilterType::SchoolStreet.svg_path())
                         .untinted()
                         .dims(30.0)
@@ -338,6 +615,7 @@ impl Mode {
                     None,
                     layers.autofix_one_ways,
                 ),
+                Toggle::checkbox(ctx, "Lock the cell layout", None, layers.lock_cells),
             ],
             Mode::SelectBoundary => vec![],
             Mode::FreehandBoundary => vec![],
@@ -19,6 +19,7 @@ impl LeftPanel {
             .dims_height(PanelDims::ExactPixels(
                 ctx.canvas.window_height - top_height,
             ))
+            .keyboard_navigation()
     }
 
     pub fn right_of_proposals(
@@ -36,6 +37,7 @@ impl LeftPanel {
             .dims_height(PanelDims::ExactPixels(
                 ctx.canvas.window_height - top_height,
             ))
+            .keyboard_navigation()
     }
 }
 
@@ -52,6 +54,7 @@ impl BottomPanel {
             .dims_width(PanelDims::ExactPixels(
                 ctx.canvas.window_width - left_panel_width,
             ))
+            .keyboard_navigation()
             .build(ctx)
     }
 }
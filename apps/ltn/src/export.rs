@@ -1,9 +1,10 @@
 use anyhow::Result;
+use serde::Serialize;
 
 use geom::{PolyLine, Pt2D};
 use osm2streets::Direction;
 
-use crate::{render, App, Neighbourhood};
+use crate::{render, App, Enforcement, FilterType, Neighbourhood, RoadFilter};
 
 /// Returns the path where the file was written
 pub fn write_geojson_file(app: &App) -> Result<String> {
@@ -12,6 +13,126 @@ pub fn write_geojson_file(app: &App) -> Result<String> {
     abstio::write_file(path, contents)
 }
 
+/// Writes a CSV listing every new road filter with the OSM way it's on and the tags a mapper
+/// would add. Physical filters are a barrier node at the filter's position, carrying the access
+/// tags themselves, so the rest of the way stays open. Filters without a barrier only restrict a
+/// stretch of road, so the way has to be split there first. Every row repeats the plan's details,
+/// to use in the changeset. Returns the path where the file was written.
+pub fn write_osm_tags_file(app: &App) -> Result<String> {
+    let map = &app.per_map.map;
+    let metadata = &app.edits().metadata;
+    let mut out = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut out);
+        for (r, filter) in &app.edits().roads {
+            // Existing filters came from OSM in the first place
+            if !filter.user_modified {
+                continue;
+            }
+            let road = map.get_r(*r);
+            // Distances are clamped when loading, but skip anything still off the road rather
+            // than crash
+            let gps = match road.center_pts.dist_along(filter.dist) {
+                Ok((pt, _)) => pt.to_gps(map.get_gps_bounds()),
+                Err(err) => {
+                    warn!("Skipping the filter on {} in the OSM tags: {}", r, err);
+                    continue;
+                }
+            };
+            let tags = osm_tags(filter);
+            writer.serialize(OsmTagsRow {
+                osm_way_id: road.orig_id.osm_way_id.0,
+                name: app.edits().get_road_name(road, None),
+                filter_type: format!("{:?}", filter.filter_type),
+                lon: gps.x(),
+                lat: gps.y(),
+                node_tags: tags.node.join(";"),
+                way_tags: tags.way.join(";"),
+                note: tags.note,
+                plan_title: &metadata.title,
+                plan_neighbourhood: &metadata.neighbourhood,
+                plan_author: &metadata.author,
+                plan_date: &metadata.date,
+                plan_description: &metadata.description,
+            })?;
+        }
+        writer.flush()?;
+    }
+    let path = format!("ltn_osm_tags_{}.csv", map.get_name().map);
+    abstio::write_file(path, String::from_utf8(out)?)
+}
+
+#[derive(Serialize)]
+struct OsmTagsRow<'a> {
+    osm_way_id: i64,
+    name: String,
+    filter_type: String,
+    lon: f64,
+    lat: f64,
+    node_tags: String,
+    way_tags: String,
+    note: &'static str,
+    plan_title: &'a str,
+    plan_neighbourhood: &'a str,
+    plan_author: &'a str,
+    plan_date: &'a str,
+    plan_description: &'a str,
+}
+
+struct OsmTags {
+    /// For a new node on the way at the filter's position
+    node: Vec<&'static str>,
+    /// For a short piece of the way split off around the filter, never the whole way
+    way: Vec<&'static str>,
+    note: &'static str,
+}
+
+fn osm_tags(filter: &RoadFilter) -> OsmTags {
+    let barrier_note = "Add a node to the way at this position";
+    let split_note = "Split the way either side of this position and tag only the short piece";
+    match (filter.filter_type, filter.enforcement) {
+        (FilterType::WalkCycleOnly, _) => OsmTags {
+            node: vec![
+                if filter.planters {
+                    "barrier=planter"
+                } else {
+                    "barrier=bollard"
+                },
+                "motor_vehicle=no",
+                "bicycle=yes",
+                "foot=yes",
+            ],
+            way: Vec::new(),
+            note: barrier_note,
+        },
+        (FilterType::BusGate, Enforcement::Physical) => OsmTags {
+            node: vec!["barrier=bus_trap", "motor_vehicle=no", "bus=yes", "psv=yes"],
+            way: Vec::new(),
+            note: barrier_note,
+        },
+        (FilterType::BusGate, Enforcement::Camera) => OsmTags {
+            node: Vec::new(),
+            way: vec!["motor_vehicle=no", "bus=yes", "psv=yes"],
+            note: split_note,
+        },
+        // Only entry is banned, and the filter doesn't record which way that is
+        (FilterType::NoEntry, _) => OsmTags {
+            node: Vec::new(),
+            way: vec!["motor_vehicle:backward=no"],
+            note: "Split the way either side of this position and tag only the short piece. Use motor_vehicle:forward=no instead if entry is banned in the way's direction.",
+        },
+        (FilterType::SchoolStreet, _) => OsmTags {
+            node: Vec::new(),
+            way: vec![
+                "motor_vehicle:conditional=no @ (Mo-Fr 08:00-09:30,14:30-16:00)",
+                "bicycle=yes",
+                "foot=yes",
+            ],
+            note: split_note,
+        },
+    }
+}
+
 fn geojson_string(app: &App) -> Result<String> {
     use geo::MapCoordsInPlace;
     use geojson::{Feature, FeatureCollection, GeoJson, Geometry, Value};
@@ -69,12 +190,22 @@ fn geojson_string(app: &App) -> Result<String> {
             };
             feature.set_property("type", "road filter");
             feature.set_property("filter_type", format!("{:?}", filter.filter_type));
+            if filter.filter_type == FilterType::BusGate {
+                feature.set_property("enforcement", format!("{:?}", filter.enforcement));
+            }
             feature.set_property("user_modified", filter.user_modified);
+            if let Some(ref trial_until) = filter.trial_until {
+                feature.set_property("trial_until", trial_until.clone());
+            }
+            if let Some(placed_at) = filter.placed_at {
+                // Seconds since the Unix epoch
+                feature.set_property("placed_at", placed_at);
+            }
             feature.set_property("stroke", "red");
             features.push(feature);
         }
     }
-    for (_, filter) in &app.edits().intersections {
+    for filter in app.edits().intersections.values().flatten() {
         let pl = filter.geometry(map).to_polyline();
         let mut feature = Feature {
             bbox: None,
@@ -85,6 +216,12 @@ fn geojson_string(app: &App) -> Result<String> {
         };
         feature.set_property("type", "diagonal filter");
         feature.set_property("filter_type", format!("{:?}", filter.filter_type));
+        if filter.filter_type == FilterType::BusGate {
+            feature.set_property("enforcement", format!("{:?}", filter.enforcement));
+        }
+        if let Some(placed_at) = filter.placed_at {
+            feature.set_property("placed_at", placed_at);
+        }
         feature.set_property("stroke", "red");
         features.push(feature);
     }
@@ -132,6 +269,20 @@ fn geojson_string(app: &App) -> Result<String> {
         }
     }
 
+    for (r, name) in &app.edits().road_names {
+        let mut feature = Feature {
+            bbox: None,
+            geometry: Some(map.get_r(*r).center_pts.to_geojson(None)),
+            id: None,
+            properties: None,
+            foreign_members: None,
+        };
+        feature.set_property("type", "renamed road");
+        feature.set_property("name", name.clone());
+        feature.set_property("stroke", "purple");
+        features.push(feature);
+    }
+
     // Transform to WGS84
     let gps_bounds = map.get_gps_bounds();
     for feature in &mut features {
@@ -152,10 +303,29 @@ fn geojson_string(app: &App) -> Result<String> {
         });
     }
 
+    let metadata = &app.edits().metadata;
+    let foreign_members = if metadata.is_empty() {
+        None
+    } else {
+        let mut members = serde_json::Map::new();
+        for (key, value) in [
+            ("title", &metadata.title),
+            ("author", &metadata.author),
+            ("description", &metadata.description),
+            ("date", &metadata.date),
+            ("neighbourhood", &metadata.neighbourhood),
+        ] {
+            if !value.is_empty() {
+                members.insert(key.to_string(), value.clone().into());
+            }
+        }
+        Some(members)
+    };
+
     let gj = GeoJson::FeatureCollection(FeatureCollection {
         features,
         bbox: None,
-        foreign_members: None,
+        foreign_members,
     });
 
     let x = serde_json::to_string_pretty(&gj)?;
@@ -1,16 +1,29 @@
 use std::collections::{BTreeMap, BTreeSet};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::{Angle, Distance, Line, Speed};
-use map_model::{CrossingType, EditRoad, IntersectionID, Map, RoadID, RoutingParams, TurnID};
-use widgetry::mapspace::{DrawCustomUnzoomedShapes, PerZoom};
+use geom::{Angle, Circle, Distance, Duration, Line, Polygon, Pt2D, Speed};
+use map_gui::tools::intersections_from_roads;
+use map_model::{
+    BuildingType, CrossingType, DirectedRoadID, Direction, EditRoad, IntersectionID, LaneType, Map,
+    Road, RoadID, RoutingParams, TurnID, TurnType,
+};
+use widgetry::mapspace::DrawCustomUnzoomedShapes;
 use widgetry::{Color, EventCtx, GeomBatch, RewriteColor};
 
-use crate::render::{colors, Toggle3Zoomed};
+use crate::render::{colors, Toggle3Zoomed, ZoomThresholds};
 use crate::{mut_edits, App};
 
+/// When zoomed out, filter icons are this many times the width of a line drawn at the current
+/// zoom. (The SVGs are about 78 units wide, so this matches the old `scale(0.4 * thickness)` for
+/// diagonal filters.)
+const LOW_ZOOM_ICON_WIDTH: f64 = 30.0;
+/// In schematic mode, how thick a filter's bar is, relative to its length
+const SCHEMATIC_BAR_THICKNESS: f64 = 0.2;
+/// How far along the road a row of planters reaches
+const PLANTER_DEPTH: Distance = Distance::const_meters(2.0);
+
 /// Stored in App per-map state. Before making any changes, call `before_edit`.
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Edits {
@@ -20,11 +33,19 @@ pub struct Edits {
         deserialize_with = "deserialize_btreemap"
     )]
     pub roads: BTreeMap<RoadID, RoadFilter>,
+    /// Large intersections may have more than one diagonal filter
     #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_diagonal_filters"
+    )]
+    pub intersections: BTreeMap<IntersectionID, Vec<DiagonalFilter>>,
+    /// Single movements banned with signage, like "no left turn"
+    #[serde(
+        default,
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
     )]
-    pub intersections: BTreeMap<IntersectionID, DiagonalFilter>,
+    pub banned_turns: BTreeMap<IntersectionID, Vec<BannedTurn>>,
     /// For roads with modified directions or speed limits, what's their current state?
     // TODO Misnomer; this includes speed limit changes now too. Not worth a backwards incompatible
     // change right now.
@@ -33,18 +54,29 @@ pub struct Edits {
         deserialize_with = "deserialize_btreemap"
     )]
     pub one_ways: BTreeMap<RoadID, EditRoad>,
-    /// For roads with modified speeds, what's their current state?
+    /// Roads with a different speed limit in each direction. The map's road has the slower of the
+    /// two, since it can only store one.
     #[serde(
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
     )]
-    pub speed_limits: BTreeMap<RoadID, Speed>,
+    pub speed_limits: BTreeMap<RoadID, DirectedSpeedLimit>,
     /// One road may have multiple crossings. They're sorted by increasing distance.
     #[serde(
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
     )]
     pub crossings: BTreeMap<RoadID, Vec<Crossing>>,
+    /// Names to use instead of the ones from OSM, for unnamed or mislabelled roads
+    #[serde(
+        default,
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub road_names: BTreeMap<RoadID, String>,
+    /// Describes the whole plan when it's shared or exported
+    #[serde(default)]
+    pub metadata: PlanMetadata,
 
     /// Edit history is preserved recursively
     #[serde(skip_serializing, skip_deserializing)]
@@ -57,6 +89,27 @@ pub struct RoadFilter {
     pub dist: Distance,
     pub filter_type: FilterType,
     pub user_modified: bool,
+    /// Only meaningful for bus gates
+    #[serde(default)]
+    pub enforcement: Enforcement,
+    /// Locked filters can't be removed by clicking or bulk operations
+    #[serde(default)]
+    pub locked: bool,
+    /// If this filter is a trial, when it's meant to end. This is free-form, like "6 months" or
+    /// "March 2024", and purely informational.
+    #[serde(default)]
+    pub trial_until: Option<String>,
+    /// Draw the icon at this angle, instead of one derived from the road
+    #[serde(default)]
+    pub angle_override: Option<Angle>,
+    /// When the user placed this filter, in seconds since the Unix epoch. Filters that came from
+    /// the map or were placed before this was recorded don't have it.
+    #[serde(default)]
+    pub placed_at: Option<u64>,
+    /// Built from planters instead of bollards, so it adds some greenery to the street. Only
+    /// walking/cycling filters can be planters.
+    #[serde(default)]
+    pub planters: bool,
 }
 
 impl RoadFilter {
@@ -65,7 +118,96 @@ impl RoadFilter {
             dist,
             filter_type,
             user_modified: true,
+            enforcement: Enforcement::default(),
+            locked: false,
+            trial_until: None,
+            angle_override: None,
+            placed_at: Some(now_timestamp()),
+            planters: false,
+        }
+    }
+
+    pub fn enforced_by(mut self, enforcement: Enforcement) -> Self {
+        self.enforcement = enforcement;
+        self
+    }
+
+    /// How to rotate the icon, given the angle of the road where the filter sits
+    pub fn icon_angle(&self, road_angle: Angle) -> Angle {
+        if let Some(angle) = self.angle_override {
+            angle
+        } else if self.filter_type == FilterType::NoEntry {
+            road_angle.rotate_degs(90.0)
+        } else {
+            Angle::ZERO
+        }
+    }
+
+    /// Draw this filter on a road, the same way `Edits::draw` does when zoomed in, but without
+    /// badges
+    pub fn draw_icon(
+        &self,
+        ctx: &EventCtx,
+        road: &Road,
+        icon_scale: f64,
+        style: FilterStyle,
+    ) -> GeomBatch {
+        match self.placement(road, icon_scale, style) {
+            Some(placement) => self.draw_zoomed(
+                road,
+                &filter_icon(ctx, self.filter_type, self.enforcement, style),
+                placement,
+                style,
+                RewriteColor::NoOp,
+                RewriteColor::NoOp,
+            ),
+            None => GeomBatch::new(),
+        }
+    }
+
+    /// Where to center the icon, how to rotate it, and how wide to make it when zoomed in.
+    /// Nothing if the filter is past the end of the road.
+    fn placement(
+        &self,
+        road: &Road,
+        icon_scale: f64,
+        style: FilterStyle,
+    ) -> Option<(Pt2D, Angle, f64)> {
+        let (pt, road_angle) = road.center_pts.dist_along(self.dist).ok()?;
+        let angle = match style {
+            FilterStyle::Signs => self.icon_angle(road_angle),
+            // The bar goes across the road
+            FilterStyle::Schematic => road_angle.rotate_degs(90.0),
+        };
+        Some((pt, angle, icon_scale * road.get_width().inner_meters()))
+    }
+
+    /// The icon from `filter_icon`, along with any planters or bus gate barriers, as drawn when
+    /// zoomed in. `icon_color` only applies to the icon, and `dim` to everything.
+    fn draw_zoomed(
+        &self,
+        road: &Road,
+        icon: &GeomBatch,
+        (pt, angle, width): (Pt2D, Angle, f64),
+        style: FilterStyle,
+        icon_color: RewriteColor,
+        dim: RewriteColor,
+    ) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        if self.planters && self.filter_type == FilterType::WalkCycleOnly {
+            batch.append(planter_boxes(road, self.dist).color(dim));
+        }
+        batch.append(
+            icon.clone()
+                .scale_to_fit_width(width)
+                .centered_on(pt)
+                .rotate(angle)
+                .color(icon_color),
+        );
+        if self.filter_type == FilterType::BusGate && style == FilterStyle::Signs {
+            batch.append(bus_gate_barriers(road, self.dist).color(dim));
         }
+        batch
     }
 }
 
@@ -79,6 +221,23 @@ pub enum FilterType {
 }
 
 impl FilterType {
+    pub const ALL: [FilterType; 4] = [
+        FilterType::WalkCycleOnly,
+        FilterType::NoEntry,
+        FilterType::BusGate,
+        FilterType::SchoolStreet,
+    ];
+
+    /// A short name, as used when picking a type
+    pub fn describe(self) -> &'static str {
+        match self {
+            FilterType::WalkCycleOnly => "Walking/cycling only",
+            FilterType::NoEntry => "No entry",
+            FilterType::BusGate => "Bus gate",
+            FilterType::SchoolStreet => "School street",
+        }
+    }
+
     pub fn svg_path(self) -> &'static str {
         match self {
             FilterType::NoEntry => "system/assets/tools/no_entry.svg",
@@ -96,6 +255,142 @@ impl FilterType {
             FilterType::SchoolStreet => Color::hex("#e31017"),
         }
     }
+
+    /// Like `svg_path`, but physical bus gates get their own icon
+    pub fn svg_path_enforced_by(self, enforcement: Enforcement) -> &'static str {
+        if self == FilterType::BusGate && enforcement == Enforcement::Physical {
+            "system/assets/tools/bus_gate_physical.svg"
+        } else {
+            self.svg_path()
+        }
+    }
+
+    /// Bollards, planters, and rising barriers have to be built into the road. Other filters are
+    /// just signs or cameras.
+    pub fn is_physical(self, enforcement: Enforcement) -> bool {
+        match self {
+            FilterType::WalkCycleOnly => true,
+            FilterType::BusGate => enforcement == Enforcement::Physical,
+            FilterType::NoEntry | FilterType::SchoolStreet => false,
+        }
+    }
+
+    /// Camera-enforced bus gates only fine drivers, so some still drive through. Every other filter
+    /// is assumed to keep all cars out.
+    pub fn is_leaky(self, enforcement: Enforcement) -> bool {
+        self == FilterType::BusGate && enforcement == Enforcement::Camera
+    }
+
+    /// Returns a warning if this type of filter wouldn't work well on a road. The filter can still
+    /// be placed there.
+    pub fn placement_warning(self, road: &Road, enforcement: Enforcement) -> Option<&'static str> {
+        // People walking or cycling need somewhere to pass the filter
+        if self == FilterType::WalkCycleOnly
+            && !road.lanes.iter().any(|l| {
+                matches!(
+                    l.lane_type,
+                    LaneType::Sidewalk
+                        | LaneType::Shoulder
+                        | LaneType::Footway
+                        | LaneType::SharedUse
+                        | LaneType::Biking
+                )
+            })
+        {
+            return Some("There's no walking/cycling lane here. Check that people walking and cycling can actually get past this filter.");
+        }
+        // It's usually not possible to dig into the deck of a bridge or the roof of a tunnel
+        if self.is_physical(enforcement) {
+            match road_structure(road) {
+                Some(Structure::Bridge) => {
+                    return Some(
+                        "This segment is a bridge; a physical filter may not be possible.",
+                    );
+                }
+                Some(Structure::Tunnel) => {
+                    return Some(
+                        "This segment is a tunnel; a physical filter may not be possible.",
+                    );
+                }
+                None => {}
+            }
+        }
+        None
+    }
+
+    /// Like `hide_color`, but matching `svg_path_enforced_by`
+    pub fn hide_color_enforced_by(self, enforcement: Enforcement) -> Color {
+        if self == FilterType::BusGate && enforcement == Enforcement::Physical {
+            Color::hex("#1B3A57")
+        } else {
+            self.hide_color()
+        }
+    }
+}
+
+/// How filters are drawn on the map
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterStyle {
+    /// The detailed signs, one icon per filter type
+    Signs,
+    /// A plain colored bar across the road, which reads better in printed diagrams
+    Schematic,
+}
+
+/// How a bus gate keeps other vehicles out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Enforcement {
+    /// Signs and ANPR cameras, with no physical barrier. Proposals saved before this existed only
+    /// had camera-enforced bus gates.
+    #[default]
+    Camera,
+    /// Rising bollards or a similar barrier that only lowers for buses
+    Physical,
+}
+
+/// Routing treats a camera-enforced bus gate as this much extra time, instead of impassable. Most
+/// drivers go around to avoid a fine, but some drive through anyway.
+const CAMERA_GATE_PENALTY: Duration = Duration::const_seconds(10.0 * 60.0);
+
+/// A speed limit for each direction of a road
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct DirectedSpeedLimit {
+    pub fwd: Speed,
+    pub back: Speed,
+}
+
+impl DirectedSpeedLimit {
+    pub fn get(self, dir: Direction) -> Speed {
+        match dir {
+            Direction::Fwd => self.fwd,
+            Direction::Back => self.back,
+        }
+    }
+
+    /// The speed the map's road should use
+    pub fn slowest(self) -> Speed {
+        self.fwd.min(self.back)
+    }
+}
+
+// Proposals used to store one speed limit for both directions
+impl<'de> Deserialize<'de> for DirectedSpeedLimit {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrBoth {
+            One(Speed),
+            Both { fwd: Speed, back: Speed },
+        }
+
+        Ok(match OneOrBoth::deserialize(d)? {
+            OneOrBoth::One(speed) => DirectedSpeedLimit {
+                fwd: speed,
+                back: speed,
+            },
+            OneOrBoth::Both { fwd, back } => DirectedSpeedLimit { fwd, back },
+        })
+    }
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
@@ -105,13 +400,125 @@ pub struct Crossing {
     pub user_modified: bool,
 }
 
+/// Buildings within this distance of a crossing count towards its demand
+const CROSSING_DEMAND_RADIUS: Distance = Distance::const_meters(200.0);
+
+impl Crossing {
+    /// Finds the buildings close enough to any crossing on this road to count towards its demand,
+    /// with the center and score of each. Residents count once and each amenity counts like 10
+    /// residents, since shops and schools draw people from farther away. Do this once per road,
+    /// then pass the result to `demand_per_side` for each crossing.
+    pub fn nearby_demand(map: &Map, road: &Road) -> Vec<(Pt2D, usize)> {
+        let mut bounds = road.center_pts.get_bounds();
+        bounds.add_buffer(CROSSING_DEMAND_RADIUS);
+
+        let mut nearby = Vec::new();
+        for b in map.all_buildings() {
+            let center = b.polygon.center();
+            if !bounds.contains(center) {
+                continue;
+            }
+            let residents = match b.bldg_type {
+                BuildingType::Residential { num_residents, .. } => num_residents,
+                BuildingType::ResidentialCommercial(num_residents, _) => num_residents,
+                BuildingType::Commercial(_) | BuildingType::Empty => 0,
+            };
+            nearby.push((center, residents + 10 * b.amenities.len()));
+        }
+        nearby
+    }
+
+    /// A rough estimate of how many people might want to use this crossing, based on the
+    /// buildings nearby on each side of the road. `nearby` comes from `nearby_demand` for the
+    /// same road. Returns the score for each side of the road.
+    pub fn demand_per_side(&self, road: &Road, nearby: &[(Pt2D, usize)]) -> (usize, usize) {
+        let (pt, angle) = match road.center_pts.dist_along(self.dist) {
+            Ok(pair) => pair,
+            Err(_) => return (0, 0),
+        };
+
+        let mut side1 = 0;
+        let mut side2 = 0;
+        for (center, score) in nearby {
+            if center.dist_to(pt) > CROSSING_DEMAND_RADIUS {
+                continue;
+            }
+            if angle.simple_shortest_rotation_towards(pt.angle_to(*center)) < 0.0 {
+                side1 += score;
+            } else {
+                side2 += score;
+            }
+        }
+        (side1, side2)
+    }
+
+    /// Sums `demand_per_side`
+    pub fn demand_score(&self, road: &Road, nearby: &[(Pt2D, usize)]) -> usize {
+        let (side1, side2) = self.demand_per_side(road, nearby);
+        side1 + side2
+    }
+}
+
 /// This logically changes every time an edit occurs. MapName isn't captured here.
-#[derive(Default, PartialEq)]
+#[derive(Clone, Default, PartialEq)]
 pub struct ChangeKey {
     roads: BTreeMap<RoadID, RoadFilter>,
-    intersections: BTreeMap<IntersectionID, DiagonalFilter>,
+    intersections: BTreeMap<IntersectionID, Vec<DiagonalFilter>>,
     one_ways: BTreeMap<RoadID, EditRoad>,
     crossings: BTreeMap<RoadID, Vec<Crossing>>,
+    banned_turns: BTreeMap<IntersectionID, Vec<BannedTurn>>,
+    speed_limits: BTreeMap<RoadID, DirectedSpeedLimit>,
+}
+
+/// Like `ChangeKey`, but also covers edits that don't affect routing, like road names and plan
+/// details. This changes whenever there's something that'd be lost without saving.
+#[derive(Clone, Default, PartialEq)]
+pub struct SaveKey {
+    change_key: ChangeKey,
+    road_names: BTreeMap<RoadID, String>,
+    metadata: PlanMetadata,
+}
+
+/// Free-form details about a plan as a whole. All fields are optional and may be blank.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlanMetadata {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    /// Not parsed; people write dates in all sorts of ways
+    pub date: String,
+    /// Which neighbourhood the plan is for, in the planner's own words. Neighbourhoods don't
+    /// otherwise have names, so this is how saved plans are told apart.
+    #[serde(default)]
+    pub neighbourhood: String,
+}
+
+impl PlanMetadata {
+    pub fn is_empty(&self) -> bool {
+        self == &PlanMetadata::default()
+    }
+}
+
+/// One movement through an intersection that drivers can't make, signed like "no left turn". This
+/// is lighter than a diagonal filter: only driving from `from` to `to` is banned, and everything
+/// else, including the movement in reverse, is still allowed.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct BannedTurn {
+    pub from: RoadID,
+    pub to: RoadID,
+}
+
+impl BannedTurn {
+    /// Which way a driver would turn, to pick the right sign. Movements the map doesn't have are
+    /// treated as straight.
+    pub fn turn_type(&self, map: &Map, i: IntersectionID) -> TurnType {
+        map.get_i(i)
+            .movements
+            .iter()
+            .find(|(id, _)| !id.crosswalk && id.from.road == self.from && id.to.road == self.to)
+            .map(|(_, movement)| movement.turn_type)
+            .unwrap_or(TurnType::Straight)
+    }
 }
 
 /// A diagonal filter exists in an intersection. It's defined by two roads (the order is
@@ -126,32 +533,187 @@ pub struct DiagonalFilter {
     r2: RoadID,
     i: IntersectionID,
     pub filter_type: FilterType,
-    user_modified: bool,
+    pub user_modified: bool,
+    #[serde(default)]
+    pub enforcement: Enforcement,
+    #[serde(default)]
+    pub locked: bool,
+    /// Like `RoadFilter::placed_at`
+    #[serde(default)]
+    pub placed_at: Option<u64>,
 
     group1: BTreeSet<RoadID>,
     group2: BTreeSet<RoadID>,
 }
 
 impl Edits {
-    /// Modify RoutingParams to respect these modal filters
+    /// Modify RoutingParams to respect these modal filters. Camera-enforced bus gates only fine
+    /// drivers, so some still go through; they're a high cost instead of a ban.
     pub fn update_routing_params(&self, params: &mut RoutingParams) {
-        params.avoid_roads.extend(self.roads.keys().cloned());
-        for filter in self.intersections.values() {
-            params
-                .avoid_movements_between
-                .extend(filter.avoid_movements_between_roads());
+        for (r, filter) in &self.roads {
+            if filter.filter_type.is_leaky(filter.enforcement) {
+                params.road_penalties.insert(*r, CAMERA_GATE_PENALTY);
+            } else {
+                params.avoid_roads.insert(*r);
+            }
+        }
+        for filter in self.intersections.values().flatten() {
+            let pairs = filter.avoid_movements_between_roads();
+            if filter.filter_type.is_leaky(filter.enforcement) {
+                for pair in pairs {
+                    params.movement_penalties.insert(pair, CAMERA_GATE_PENALTY);
+                }
+            } else {
+                params.avoid_movements_between.extend(pairs);
+            }
+        }
+        for ban in self.banned_turns.values().flatten() {
+            params.avoid_movements_between.insert((ban.from, ban.to));
+        }
+        // The map's road only has the slower of the two
+        for (r, limit) in &self.speed_limits {
+            for dir in [Direction::Fwd, Direction::Back] {
+                params
+                    .speed_limits
+                    .insert(DirectedRoadID { road: *r, dir }, limit.get(dir));
+            }
+        }
+    }
+
+    /// Make pedestrians wait to cross roads with crossings, using the quickest crossing on each
+    /// road.
+    pub fn update_walking_params(&self, params: &mut RoutingParams) {
+        for (r, list) in &self.crossings {
+            if let Some(delay) = list
+                .iter()
+                .map(|crossing| crate::logic::crossings::pedestrian_delay(crossing.kind))
+                .min()
+            {
+                params.crossing_penalties.insert(*r, delay);
+            }
         }
     }
 
+    /// Only checks diagonal filters. See `turn_allowed` for everything.
     pub fn allows_turn(&self, t: TurnID) -> bool {
-        if let Some(filter) = self.intersections.get(&t.parent) {
-            return filter.allows_turn(t.src.road, t.dst.road);
+        if let Some(filters) = self.intersections.get(&t.parent) {
+            return filters
+                .iter()
+                .all(|filter| filter.allows_turn(t.src.road, t.dst.road));
         }
         true
     }
 
-    /// Draw all modal filters
-    pub fn draw(&self, ctx: &EventCtx, map: &Map) -> Toggle3Zoomed {
+    /// Is there a sign banning exactly this movement? Diagonal filters aren't checked.
+    pub fn is_turn_banned(&self, i: IntersectionID, from: RoadID, to: RoadID) -> bool {
+        self.banned_turns
+            .get(&i)
+            .map(|list| list.iter().any(|ban| ban.from == from && ban.to == to))
+            .unwrap_or(false)
+    }
+
+    /// Can a car make this turn, considering all edits? This mostly matches
+    /// `update_routing_params`: roads with a filter anywhere along them can't be used at all, even
+    /// though in reality a driver could reach the part before the filter. Camera-enforced bus gates
+    /// count as blocking here, though routing only penalizes them.
+    pub fn turn_allowed(&self, t: TurnID, map: &Map) -> bool {
+        // One-way changes are applied to the map directly, so a turn against the new direction
+        // won't exist anymore
+        if map.maybe_get_t(t).is_none() {
+            return false;
+        }
+        if self.roads.contains_key(&t.src.road) || self.roads.contains_key(&t.dst.road) {
+            return false;
+        }
+        if self.is_turn_banned(t.parent, t.src.road, t.dst.road) {
+            return false;
+        }
+        self.allows_turn(t)
+    }
+
+    /// Find every filter along a bus route, using the route names from OSM. Road filters are
+    /// returned first, then diagonal filters that split two roads the route uses. Bus gates are
+    /// included too, so callers can tell apart filters that the route passes and ones that block
+    /// it.
+    pub fn filters_on_bus_route(
+        &self,
+        map: &Map,
+        route: &str,
+    ) -> (Vec<(RoadID, FilterType)>, Vec<(IntersectionID, FilterType)>) {
+        let on_route = |r: RoadID| map.get_bus_routes_on_road(r).contains(route);
+
+        let mut road_filters = Vec::new();
+        for (r, filter) in &self.roads {
+            if on_route(*r) {
+                road_filters.push((*r, filter.filter_type));
+            }
+        }
+
+        let mut diagonal_filters = Vec::new();
+        for (i, filters) in &self.intersections {
+            let route_roads: Vec<RoadID> = map
+                .get_i(*i)
+                .roads
+                .iter()
+                .cloned()
+                .filter(|r| on_route(*r))
+                .collect();
+            for filter in filters {
+                let splits_route = route_roads.iter().any(|r1| {
+                    route_roads
+                        .iter()
+                        .any(|r2| r1 != r2 && !filter.allows_turn(*r1, *r2))
+                });
+                if splits_route {
+                    diagonal_filters.push((*i, filter.filter_type));
+                }
+            }
+        }
+
+        (road_filters, diagonal_filters)
+    }
+
+    /// Draw all modal filters. `icon_scale` shrinks or grows every icon, relative to the width
+    /// of the road (when zoomed in) or a fixed size on screen (when zoomed out).
+    /// `zoom` controls when icons switch to full detail; see `Layers::filter_zoom`.
+    pub fn draw(
+        &self,
+        ctx: &EventCtx,
+        map: &Map,
+        icon_scale: f64,
+        zoom: ZoomThresholds,
+        style: FilterStyle,
+    ) -> Toggle3Zoomed {
+        self.draw_with_dimming(ctx, map, icon_scale, zoom, style, None)
+    }
+
+    /// Like `draw`, but every icon and badge is drawn with a fixed transparency, for showing
+    /// filters as faint context.
+    pub fn draw_dimmed(
+        &self,
+        ctx: &EventCtx,
+        map: &Map,
+        icon_scale: f64,
+        zoom: ZoomThresholds,
+        style: FilterStyle,
+        alpha: f32,
+    ) -> Toggle3Zoomed {
+        self.draw_with_dimming(ctx, map, icon_scale, zoom, style, Some(alpha))
+    }
+
+    fn draw_with_dimming(
+        &self,
+        ctx: &EventCtx,
+        map: &Map,
+        icon_scale: f64,
+        zoom: ZoomThresholds,
+        style: FilterStyle,
+        dim: Option<f32>,
+    ) -> Toggle3Zoomed {
+        let dim_rewrite = match dim {
+            Some(alpha) => RewriteColor::ChangeAlpha(alpha),
+            None => RewriteColor::NoOp,
+        };
         let mut batch = GeomBatch::new();
         let mut low_zoom = DrawCustomUnzoomedShapes::builder();
 
@@ -162,93 +724,352 @@ impl Edits {
             FilterType::BusGate,
             FilterType::SchoolStreet,
         ] {
-            icons.insert(ft, GeomBatch::load_svg(ctx, ft.svg_path()));
+            for enforcement in [Enforcement::Camera, Enforcement::Physical] {
+                icons.insert(
+                    (ft, enforcement),
+                    filter_icon(ctx, ft, enforcement, style).color(dim_rewrite),
+                );
+            }
         }
+        let lock = GeomBatch::load_svg(ctx, "system/assets/tools/locked.svg").color(dim_rewrite);
+        let calendar =
+            GeomBatch::load_svg(ctx, "system/assets/tools/calendar.svg").color(dim_rewrite);
 
         for (r, filter) in &self.roads {
-            let icon = &icons[&filter.filter_type];
-            let rewrite_color = if filter.user_modified {
+            let icon = &icons[&(filter.filter_type, filter.enforcement)];
+            // When dimming, everything already has the same transparency
+            let rewrite_color = if filter.user_modified || dim.is_some() {
                 RewriteColor::NoOp
             } else {
                 RewriteColor::ChangeAlpha(0.7)
             };
 
             let road = map.get_r(*r);
-            if let Ok((pt, road_angle)) = road.center_pts.dist_along(filter.dist) {
-                let angle = if filter.filter_type == FilterType::NoEntry {
-                    road_angle.rotate_degs(90.0)
-                } else {
-                    Angle::ZERO
-                };
-
-                batch.append(
-                    icon.clone()
-                        .scale_to_fit_width(road.get_width().inner_meters())
-                        .centered_on(pt)
-                        .rotate(angle)
-                        .color(rewrite_color),
-                );
+            if let Some((pt, angle, width)) = filter.placement(road, icon_scale, style) {
+                batch.append(filter.draw_zoomed(
+                    road,
+                    icon,
+                    (pt, angle, width),
+                    style,
+                    rewrite_color,
+                    dim_rewrite,
+                ));
+                if filter.locked {
+                    batch.append(lock_badge(&lock, pt, width));
+                }
+                if filter.trial_until.is_some() {
+                    batch.append(trial_badge(&calendar, pt, width));
+                }
 
                 // TODO Memory intensive
                 let icon = icon.clone();
+                let lock = filter.locked.then(|| lock.clone());
+                let calendar = filter.trial_until.is_some().then(|| calendar.clone());
+                let planters = filter.planters && filter.filter_type == FilterType::WalkCycleOnly;
                 // TODO They can shrink a bit past their map size
                 low_zoom.add_custom(Box::new(move |batch, thickness| {
+                    let width = icon_scale * LOW_ZOOM_ICON_WIDTH * thickness;
+                    // The road is too thin to show the bed, so put some greenery behind the icon
+                    if planters {
+                        batch.append(
+                            GeomBatch::from(vec![(
+                                colors::PLANTING,
+                                Circle::new(pt, Distance::meters(0.7 * width)).to_polygon(),
+                            )])
+                            .color(dim_rewrite),
+                        );
+                    }
                     batch.append(
                         icon.clone()
-                            .scale_to_fit_width(30.0 * thickness)
+                            .scale_to_fit_width(width)
                             .centered_on(pt)
                             .rotate(angle)
                             .color(rewrite_color),
                     );
+                    if let Some(ref lock) = lock {
+                        batch.append(lock_badge(lock, pt, width));
+                    }
+                    if let Some(ref calendar) = calendar {
+                        batch.append(trial_badge(calendar, pt, width));
+                    }
                 }));
             }
         }
 
-        for (_, filter) in &self.intersections {
-            let icon = &icons[&filter.filter_type];
-            let rewrite_color = if filter.user_modified {
+        for filter in self.intersections.values().flatten() {
+            let icon = &icons[&(filter.filter_type, filter.enforcement)];
+            // When dimming, everything already has the same transparency
+            let rewrite_color = if filter.user_modified || dim.is_some() {
                 RewriteColor::NoOp
             } else {
                 RewriteColor::ChangeAlpha(0.7)
             };
 
-            let line = filter.geometry(map);
-            let angle = if filter.filter_type == FilterType::NoEntry {
-                line.angle()
+            let angle = if style == FilterStyle::Schematic {
+                filter.geometry(map).angle()
+            } else if filter.filter_type == FilterType::NoEntry {
+                filter.geometry(map).angle()
             } else {
                 Angle::ZERO
             };
-            let pt = line.middle().unwrap();
-
+            let (pt, width) = match style {
+                FilterStyle::Signs => filter.icon_placement(map, icon_scale),
+                // The bar spans the whole diagonal, without worrying about fitting inside the
+                // intersection like an icon must
+                FilterStyle::Schematic => {
+                    let line = filter.geometry(map);
+                    (
+                        line.middle().unwrap_or_else(|_| line.pt1()),
+                        line.length().inner_meters(),
+                    )
+                }
+            };
             batch.append(
                 icon.clone()
-                    .scale_to_fit_width(line.length().inner_meters())
+                    .scale_to_fit_width(width)
                     .centered_on(pt)
                     .rotate(angle)
                     .color(rewrite_color),
             );
+            if filter.locked {
+                batch.append(lock_badge(&lock, pt, width));
+            }
 
             let icon = icon.clone();
+            let lock = filter.locked.then(|| lock.clone());
             low_zoom.add_custom(Box::new(move |batch, thickness| {
-                // TODO Why is this magic value different than the one above?
+                let width = icon_scale * LOW_ZOOM_ICON_WIDTH * thickness;
                 batch.append(
                     icon.clone()
-                        .scale(0.4 * thickness)
+                        .scale_to_fit_width(width)
                         .centered_on(pt)
                         .rotate(angle)
                         .color(rewrite_color),
                 );
+                if let Some(ref lock) = lock {
+                    batch.append(lock_badge(lock, pt, width));
+                }
             }));
         }
 
-        let min_zoom_for_detail = 5.0;
-        let step_size = 0.1;
+        for (i, list) in &self.banned_turns {
+            for ban in list {
+                let sign = crate::render::draw_banned_turn_sign(
+                    map,
+                    *i,
+                    ban.from,
+                    ban.turn_type(map, *i),
+                    icon_scale,
+                )
+                .color(dim_rewrite);
+                let pt = sign.get_bounds().center();
+                batch.append(sign.clone());
+
+                low_zoom.add_custom(Box::new(move |batch, thickness| {
+                    batch.append(
+                        sign.clone()
+                            .scale_to_fit_width(icon_scale * LOW_ZOOM_ICON_WIDTH * thickness)
+                            .centered_on(pt),
+                    );
+                }));
+            }
+        }
+
         // TODO Ideally we get rid of Toggle3Zoomed and make DrawCustomUnzoomedShapes handle this
         // medium-zoom case.
-        Toggle3Zoomed::new(
-            batch.build(ctx),
-            low_zoom.build(PerZoom::new(min_zoom_for_detail, step_size)),
-        )
+        Toggle3Zoomed::new(batch.build(ctx), low_zoom.build(zoom.per_zoom()), zoom)
+    }
+
+    /// The name of a road, respecting any override
+    pub fn get_road_name(&self, road: &Road, lang: Option<&String>) -> String {
+        match self.road_names.get(&road.id) {
+            Some(name) => name.clone(),
+            None => road.get_name(lang),
+        }
+    }
+
+    /// Junctions where the plan needs signs or works, limited to some area, like a
+    /// neighbourhood's interior and perimeter `roads`. That's both ends of every road there with a
+    /// new filter, plus every junction between those roads with a new diagonal filter or banned
+    /// turn. Filters that already existed are skipped.
+    pub fn intersections_touched(
+        &self,
+        map: &Map,
+        roads: &BTreeSet<RoadID>,
+    ) -> BTreeSet<IntersectionID> {
+        let mut result: BTreeSet<IntersectionID> = intersections_from_roads(roads, map)
+            .into_iter()
+            .filter(|i| {
+                // Banned turns are only ever placed by the user
+                self.banned_turns.contains_key(i)
+                    || self
+                        .intersections
+                        .get(i)
+                        .map(|list| list.iter().any(|filter| filter.user_modified))
+                        .unwrap_or(false)
+            })
+            .collect();
+        for r in roads {
+            if self.roads.get(r).map(|f| f.user_modified).unwrap_or(false) {
+                let road = map.get_r(*r);
+                result.insert(road.src_i);
+                result.insert(road.dst_i);
+            }
+        }
+        result
+    }
+
+    /// How many filters are placed, counting each diagonal filter separately
+    pub fn num_filters(&self) -> usize {
+        self.roads.len()
+            + self
+                .intersections
+                .values()
+                .map(|list| list.len())
+                .sum::<usize>()
+    }
+
+    /// How much new planting the plan adds, in square meters. Each planter filter placed by the
+    /// user fills the width of its road for a short distance.
+    pub fn planting_area_m2(&self, map: &Map) -> f64 {
+        self.roads
+            .iter()
+            .filter(|(_, filter)| {
+                filter.planters
+                    && filter.user_modified
+                    && filter.filter_type == FilterType::WalkCycleOnly
+            })
+            .map(|(r, _)| map.get_r(*r).get_width().inner_meters() * PLANTER_DEPTH.inner_meters())
+            .sum()
+    }
+
+    /// Every crossing in the plan, with where it is on the map. Crossings on the same road are in
+    /// order along it. A crossing that somehow lies off the end of its road is skipped; call
+    /// `clamp_distances` after loading to avoid that.
+    pub fn all_crossings(&self, map: &Map) -> Vec<(RoadID, Distance, CrossingType, Pt2D)> {
+        let mut result = Vec::new();
+        for (r, list) in &self.crossings {
+            let road = map.get_r(*r);
+            for crossing in list {
+                if let Ok((pt, _)) = road.center_pts.dist_along(crossing.dist) {
+                    result.push((*r, crossing.dist, crossing.kind, pt));
+                }
+            }
+        }
+        result
+    }
+
+    /// The speed limit along one direction of a road, respecting per-direction edits
+    pub fn speed_limit(&self, map: &Map, dr: DirectedRoadID) -> Speed {
+        match self.speed_limits.get(&dr.road) {
+            Some(limit) => limit.get(dr.dir),
+            None => map.get_r(dr.road).speed_limit,
+        }
+    }
+
+    /// Are any of the diagonal filters at this intersection locked?
+    pub fn is_intersection_locked(&self, i: IntersectionID) -> bool {
+        self.intersections
+            .get(&i)
+            .map(|list| list.iter().any(|f| f.locked))
+            .unwrap_or(false)
+    }
+
+    /// If a road's geometry changed since these edits were made, a filter or crossing might point
+    /// past the end of the shorter road. Clamp them back onto the road. Returns a description of
+    /// each one moved.
+    pub fn clamp_distances(&mut self, map: &Map) -> Vec<String> {
+        let mut moved = Vec::new();
+        for (r, filter) in &mut self.roads {
+            let len = map.get_r(*r).length();
+            if filter.dist > len {
+                info!(
+                    "Filter on {} was {} along, but the road is only {} long now",
+                    r, filter.dist, len
+                );
+                moved.push(format!(
+                    "A filter on {} was moved to the end of the road, because the road got shorter",
+                    r
+                ));
+                filter.dist = len;
+            }
+        }
+        for (r, list) in &mut self.crossings {
+            let len = map.get_r(*r).length();
+            for crossing in list {
+                if crossing.dist > len {
+                    info!(
+                        "Crossing on {} was {} along, but the road is only {} long now",
+                        r, crossing.dist, len
+                    );
+                    moved.push(format!(
+                        "A crossing on {} was moved to the end of the road, because the road got shorter",
+                        r
+                    ));
+                    crossing.dist = len;
+                }
+            }
+        }
+        moved
+    }
+
+    /// Remove filters and crossings that came from the map, leaving only the ones the user placed
+    /// or changed
+    pub fn retain_user_modified(&mut self) {
+        self.roads.retain(|_, filter| filter.user_modified);
+        for list in self.intersections.values_mut() {
+            list.retain(|filter| filter.user_modified);
+        }
+        self.intersections.retain(|_, list| !list.is_empty());
+        for list in self.crossings.values_mut() {
+            list.retain(|crossing| crossing.user_modified);
+        }
+        self.crossings.retain(|_, list| !list.is_empty());
+    }
+
+    /// Diagonal filters remember how they split up the roads at an intersection. If the map was
+    /// regenerated and the intersection has different roads now, that split is meaningless, so
+    /// drop the filter. Returns a description of each one removed.
+    pub fn remove_stale_diagonal_filters(&mut self, map: &Map) -> Vec<String> {
+        let mut removed = Vec::new();
+        for (i, list) in &mut self.intersections {
+            let roads: BTreeSet<RoadID> = map.get_i(*i).roads.iter().cloned().collect();
+            list.retain(|filter| {
+                let groups: BTreeSet<RoadID> =
+                    filter.group1.union(&filter.group2).cloned().collect();
+                let ok = groups == roads
+                    && filter.group1.contains(&filter.r1)
+                    && filter.group1.contains(&filter.r2);
+                if !ok {
+                    warn!(
+                        "Dropping diagonal filter at {}; it splits {:?} and {:?}, but the roads there are now {:?}",
+                        i, filter.group1, filter.group2, roads
+                    );
+                    removed.push(format!(
+                        "A diagonal filter at {} was removed, because the roads there changed",
+                        i
+                    ));
+                }
+                ok
+            });
+        }
+        self.intersections.retain(|_, list| !list.is_empty());
+
+        for (i, list) in &mut self.banned_turns {
+            let roads = &map.get_i(*i).roads;
+            list.retain(|ban| {
+                let ok = roads.contains(&ban.from) && roads.contains(&ban.to);
+                if !ok {
+                    removed.push(format!(
+                        "A banned turn at {} was removed, because the roads there changed",
+                        i
+                    ));
+                }
+                ok
+            });
+        }
+        self.banned_turns.retain(|_, list| !list.is_empty());
+        removed
     }
 
     pub fn get_change_key(&self) -> ChangeKey {
@@ -257,13 +1078,59 @@ impl Edits {
             intersections: self.intersections.clone(),
             one_ways: self.one_ways.clone(),
             crossings: self.crossings.clone(),
+            banned_turns: self.banned_turns.clone(),
+            speed_limits: self.speed_limits.clone(),
+        }
+    }
+
+    /// A copy of everything covered by the `ChangeKey`, without road names, plan details, or edit
+    /// history. Useful for trying out variations that get routed.
+    pub fn routing_copy(&self) -> Edits {
+        let key = self.get_change_key();
+        Edits {
+            roads: key.roads,
+            intersections: key.intersections,
+            one_ways: key.one_ways,
+            crossings: key.crossings,
+            banned_turns: key.banned_turns,
+            speed_limits: key.speed_limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_save_key(&self) -> SaveKey {
+        SaveKey {
+            change_key: self.get_change_key(),
+            road_names: self.road_names.clone(),
+            metadata: self.metadata.clone(),
         }
     }
+
+    /// How many steps of undo history lead up to these edits
+    pub fn history_len(&self) -> usize {
+        let mut count = 0;
+        let mut version = self;
+        while let Some(ref prev) = *version.previous_version {
+            count += 1;
+            version = prev;
+        }
+        count
+    }
+
+    /// These edits as they were `steps` undos ago, if the history goes back that far
+    pub fn version_before(&self, steps: usize) -> Option<&Edits> {
+        let mut version = self;
+        for _ in 0..steps {
+            version = version.previous_version.as_ref().as_ref()?;
+        }
+        Some(version)
+    }
 }
 
 impl DiagonalFilter {
-    /// The caller must call this in a `before_edit` / `redraw_all_filters` "transaction."
-    pub fn cycle_through_alternatives(app: &mut App, i: IntersectionID) {
+    /// The caller must call this in a `before_edit` / `redraw_all_filters` "transaction." Returns
+    /// false without changing anything if a locked filter is in the way.
+    pub fn cycle_through_alternatives(app: &mut App, i: IntersectionID) -> bool {
         let map = &app.per_map.map;
         let mut roads = map.get_i(i).roads.clone();
         // Don't consider non-driveable roads for the 4-way calculation even
@@ -274,18 +1141,30 @@ impl DiagonalFilter {
             let alt1 = DiagonalFilter::new(app, i, roads[0], roads[1]);
             let alt2 = DiagonalFilter::new(app, i, roads[1], roads[2]);
 
-            match app.edits().intersections.get(&i) {
-                Some(prev) => {
+            if app.edits().is_intersection_locked(i) {
+                return false;
+            }
+            match app
+                .edits()
+                .intersections
+                .get(&i)
+                .map(|list| list.as_slice())
+            {
+                Some([prev]) => {
                     if alt1.approx_eq(prev) {
-                        mut_edits!(app).intersections.insert(i, alt2);
+                        mut_edits!(app).intersections.insert(i, vec![alt2]);
                     } else if alt2.approx_eq(prev) {
                         mut_edits!(app).intersections.remove(&i);
                     } else {
                         unreachable!()
                     }
                 }
+                // When there are multiple filters, cycle back to having none
+                Some(_) => {
+                    mut_edits!(app).intersections.remove(&i);
+                }
                 None => {
-                    mut_edits!(app).intersections.insert(i, alt1);
+                    mut_edits!(app).intersections.insert(i, vec![alt1]);
                 }
             }
         } else if roads.len() > 1 {
@@ -301,7 +1180,13 @@ impl DiagonalFilter {
             // TODO I triggered this case somewhere in Kennington when drawing free-hand. Look for
             // the case and test this case more carefully. Maybe do the filtering earlier.
             if roads.is_empty() {
-                return;
+                return true;
+            }
+            if roads
+                .iter()
+                .any(|r| app.edits().roads.get(r).map(|f| f.locked).unwrap_or(false))
+            {
+                return false;
             }
 
             let mut add_filter_to = None;
@@ -320,11 +1205,48 @@ impl DiagonalFilter {
                 } else {
                     road.length()
                 };
+                mut_edits!(app).roads.insert(
+                    r,
+                    RoadFilter::new_by_user(dist, app.session.filter_type)
+                        .enforced_by(app.session.bus_gate_enforcement),
+                );
+            }
+        }
+        true
+    }
+
+    /// Keeps any diagonal filters at the intersection and adds another one, if there's one left
+    /// to add. Elsewhere, behaves like `cycle_through_alternatives`. The caller must call this in
+    /// a `before_edit` / `redraw_all_filters` "transaction." Returns false if a locked filter is
+    /// in the way.
+    pub fn add_another_alternative(app: &mut App, i: IntersectionID) -> bool {
+        let map = &app.per_map.map;
+        let mut roads = map.get_i(i).roads.clone();
+        roads.retain(|r| crate::is_driveable(map.get_r(*r), map));
+        if roads.len() != 4 {
+            return Self::cycle_through_alternatives(app, i);
+        }
+
+        let existing = app
+            .edits()
+            .intersections
+            .get(&i)
+            .cloned()
+            .unwrap_or_default();
+        for alt in [
+            DiagonalFilter::new(app, i, roads[0], roads[1]),
+            DiagonalFilter::new(app, i, roads[1], roads[2]),
+        ] {
+            if !existing.iter().any(|prev| alt.approx_eq(prev)) {
                 mut_edits!(app)
-                    .roads
-                    .insert(r, RoadFilter::new_by_user(dist, app.session.filter_type));
+                    .intersections
+                    .entry(i)
+                    .or_insert_with(Vec::new)
+                    .push(alt);
+                return true;
             }
         }
+        true
     }
 
     fn new(app: &App, i: IntersectionID, r1: RoadID, r2: RoadID) -> DiagonalFilter {
@@ -352,10 +1274,13 @@ impl DiagonalFilter {
             r2,
             i,
             filter_type: app.session.filter_type,
+            enforcement: app.session.bus_gate_enforcement,
             group1,
             group2: roads.into_iter().collect(),
             // We don't detect existing diagonal filters right now
             user_modified: true,
+            locked: false,
+            placed_at: Some(now_timestamp()),
         }
     }
 
@@ -369,7 +1294,8 @@ impl DiagonalFilter {
         let pl2 = r2.center_pts.maybe_reverse(r2.src_i == self.i);
 
         // The other combinations of left/right here would produce points or a line across just one
-        // road
+        // road. This is purely about geometry -- r1 and r2 are ordered around the intersection, so
+        // it doesn't depend on the side of the road people drive on.
         let pt1 = pl1.must_shift_right(r1.get_half_width()).last_pt();
         let pt2 = pl2.must_shift_left(r2.get_half_width()).last_pt();
         match Line::new(pt1, pt2) {
@@ -383,11 +1309,48 @@ impl DiagonalFilter {
         }
     }
 
+    /// Where to center the icon and how wide to draw it. Normally it sits in the middle of the
+    /// filter line, sized to match, but at tight junctions that spills over the adjacent roads.
+    /// Then the icon moves to the roomiest spot in the intersection and shrinks to fit.
+    pub fn icon_placement(&self, map: &Map, icon_scale: f64) -> (Pt2D, f64) {
+        let line = self.geometry(map);
+        let desired_width = icon_scale * line.length().inner_meters();
+        let polygon = &map.get_i(self.i).polygon;
+        let boundary = polygon.get_outer_ring().as_polyline();
+
+        // The icon is about square, so its corners stick out sqrt(2) / 2 of its width from the
+        // center
+        let max_width = |pt: Pt2D| {
+            if polygon.contains_pt(pt) {
+                std::f64::consts::SQRT_2 * pt.dist_to(boundary.project_pt(pt)).inner_meters()
+            } else {
+                0.0
+            }
+        };
+
+        let mut pt = line.middle().unwrap_or_else(|_| line.pt1());
+        let mut room = max_width(pt);
+        if room < desired_width {
+            let center = polygon.polylabel();
+            let center_room = max_width(center);
+            if center_room > room {
+                pt = center;
+                room = center_room;
+            }
+        }
+        // If the intersection polygon is broken somehow, don't make the icon vanish
+        if room <= 0.0 {
+            return (pt, desired_width);
+        }
+        (pt, desired_width.min(room))
+    }
+
     pub fn allows_turn(&self, from: RoadID, to: RoadID) -> bool {
         self.group1.contains(&from) == self.group1.contains(&to)
     }
 
-    fn avoid_movements_between_roads(&self) -> Vec<(RoadID, RoadID)> {
+    /// Every (from, to) pair of roads that can't be driven between through this filter
+    pub fn avoid_movements_between_roads(&self) -> Vec<(RoadID, RoadID)> {
         let mut pairs = Vec::new();
         for from in &self.group1 {
             for to in &self.group2 {
@@ -398,7 +1361,7 @@ impl DiagonalFilter {
         pairs
     }
 
-    fn approx_eq(&self, other: &DiagonalFilter) -> bool {
+    pub fn approx_eq(&self, other: &DiagonalFilter) -> bool {
         // Careful. At a 4-way intersection, the same filter can be expressed as a different pair of two
         // roads. The (r1, r2) ordering is also arbitrary. cycle_through_alternatives is
         // consistent, though.
@@ -408,3 +1371,244 @@ impl DiagonalFilter {
             == (other.r1, other.r2, other.i, &other.group1, &other.group2)
     }
 }
+
+enum Structure {
+    Bridge,
+    Tunnel,
+}
+
+/// Is the road on a bridge or in a tunnel? A `layer` tag alone doesn't say -- streets passing
+/// under a bridge are often just `layer=-1` -- so it only counts for covered roads.
+fn road_structure(road: &Road) -> Option<Structure> {
+    let tagged = |key: &str| road.osm_tags.get(key).map(|v| v != "no").unwrap_or(false);
+    if tagged("bridge") {
+        Some(Structure::Bridge)
+    } else if tagged("tunnel") {
+        Some(Structure::Tunnel)
+    } else if tagged("covered")
+        && road
+            .osm_tags
+            .get("layer")
+            .and_then(|x| x.parse::<isize>().ok())
+            .map(|layer| layer < 0)
+            .unwrap_or(false)
+    {
+        Some(Structure::Tunnel)
+    } else {
+        None
+    }
+}
+
+/// On a multi-lane road, one bus gate icon in the middle doesn't say which lanes are closed. Draw
+/// a barrier across each general traffic lane and mark the path left clear for buses: any bus
+/// lanes, or if there are none, the kerbside lane in each direction. Ordinary two-lane streets get
+/// nothing extra.
+fn bus_gate_barriers(road: &Road, dist: Distance) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let moving_lanes = road
+        .lanes
+        .iter()
+        .filter(|l| matches!(l.lane_type, LaneType::Driving | LaneType::Bus))
+        .count();
+    if moving_lanes < 3 {
+        return batch;
+    }
+
+    // Find the center of every lane, measured from the left side of the road
+    let mut lane_centers = Vec::new();
+    let mut left_edge = Distance::ZERO;
+    for lane in &road.lanes {
+        lane_centers.push(left_edge + lane.width / 2.0);
+        left_edge += lane.width;
+    }
+    let half_road = road.get_width() / 2.0;
+
+    let any_bus_lanes = road.lanes.iter().any(|l| l.lane_type == LaneType::Bus);
+    let mut clear_for_buses: Vec<bool> = road
+        .lanes
+        .iter()
+        .map(|l| l.lane_type == LaneType::Bus)
+        .collect();
+    if !any_bus_lanes {
+        for dir in [Direction::Fwd, Direction::Back] {
+            if let Some(idx) = (0..road.lanes.len())
+                .filter(|idx| {
+                    road.lanes[*idx].lane_type == LaneType::Driving && road.lanes[*idx].dir == dir
+                })
+                .max_by_key(|idx| (lane_centers[*idx] - half_road).abs())
+            {
+                clear_for_buses[idx] = true;
+            }
+        }
+    }
+
+    for (idx, lane) in road.lanes.iter().enumerate() {
+        if !matches!(lane.lane_type, LaneType::Driving | LaneType::Bus) {
+            continue;
+        }
+        let (pt, angle) = match road
+            .shift_from_left_side(lane_centers[idx])
+            .and_then(|pl| pl.dist_along(dist.min(pl.length())))
+        {
+            Ok(pair) => pair,
+            Err(_) => continue,
+        };
+        let across = angle.rotate_degs(90.0);
+        // Leave a little space between neighbouring lanes
+        let half_width = 0.4 * lane.width;
+        if let Ok(line) = Line::new(
+            pt.project_away(half_width, across.opposite()),
+            pt.project_away(half_width, across),
+        ) {
+            if clear_for_buses[idx] {
+                batch.push(
+                    colors::BUS_ROUTE.alpha(0.8),
+                    line.make_polygons(Distance::meters(0.5)),
+                );
+            } else {
+                batch.push(
+                    colors::BUS_GATE_BARRIER,
+                    line.make_polygons(Distance::meters(1.5)),
+                );
+            }
+        }
+    }
+    batch
+}
+
+/// A bed of greenery filling the road around a planter filter
+fn planter_boxes(road: &Road, dist: Distance) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let start = (dist - PLANTER_DEPTH / 2.0).max(Distance::ZERO);
+    let end = (dist + PLANTER_DEPTH / 2.0).min(road.length());
+    if let Ok(slice) = road.center_pts.maybe_exact_slice(start, end) {
+        batch.push(colors::PLANTING, slice.make_polygons(road.get_width()));
+    }
+    batch
+}
+
+/// Seconds since the Unix epoch, according to the system or browser clock
+fn now_timestamp() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+/// The icon for one kind of filter, before scaling and rotating
+fn filter_icon(
+    ctx: &EventCtx,
+    filter_type: FilterType,
+    enforcement: Enforcement,
+    style: FilterStyle,
+) -> GeomBatch {
+    match style {
+        FilterStyle::Signs => {
+            GeomBatch::load_svg(ctx, filter_type.svg_path_enforced_by(enforcement))
+        }
+        // A unit-length bar, so it scales and rotates just like the SVGs
+        FilterStyle::Schematic => GeomBatch::from(vec![(
+            filter_type.hide_color_enforced_by(enforcement),
+            Polygon::rectangle(1.0, SCHEMATIC_BAR_THICKNESS),
+        )]),
+    }
+}
+
+/// A small lock in the top-right corner of a filter icon `width` wide
+fn lock_badge(lock: &GeomBatch, pt: Pt2D, width: f64) -> GeomBatch {
+    lock.clone()
+        .scale_to_fit_width(0.4 * width)
+        .centered_on(pt.offset(0.5 * width, -0.5 * width))
+}
+
+/// A small calendar in the top-left corner of a filter icon, marking a trial
+fn trial_badge(calendar: &GeomBatch, pt: Pt2D, width: f64) -> GeomBatch {
+    calendar
+        .clone()
+        .scale_to_fit_width(0.4 * width)
+        .centered_on(pt.offset(-0.5 * width, -0.5 * width))
+}
+
+// Proposals saved before intersections could have multiple filters stored just one
+fn deserialize_diagonal_filters<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<BTreeMap<IntersectionID, Vec<DiagonalFilter>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(DiagonalFilter),
+        Many(Vec<DiagonalFilter>),
+    }
+
+    let map: BTreeMap<IntersectionID, OneOrMany> = deserialize_btreemap(d)?;
+    Ok(map
+        .into_iter()
+        .map(|(i, filters)| match filters {
+            OneOrMany::One(filter) => (i, vec![filter]),
+            OneOrMany::Many(list) => (i, list),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_directed_speed_limit() {
+        let slow = Speed::meters_per_second(5.0);
+        let fast = Speed::meters_per_second(10.0);
+
+        // The old format has one speed for both directions
+        let old = serde_json::to_string(&slow).unwrap();
+        let limit: DirectedSpeedLimit = serde_json::from_str(&old).unwrap();
+        assert_eq!(
+            limit,
+            DirectedSpeedLimit {
+                fwd: slow,
+                back: slow
+            }
+        );
+
+        let both = DirectedSpeedLimit {
+            fwd: fast,
+            back: slow,
+        };
+        let new = serde_json::to_string(&both).unwrap();
+        let limit: DirectedSpeedLimit = serde_json::from_str(&new).unwrap();
+        assert_eq!(limit, both);
+        assert_eq!(limit.slowest(), slow);
+    }
+
+    #[test]
+    fn test_deserialize_diagonal_filters() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[serde(deserialize_with = "deserialize_diagonal_filters")]
+            intersections: BTreeMap<IntersectionID, Vec<DiagonalFilter>>,
+        }
+
+        let filter = |r1, r2| DiagonalFilter {
+            r1: RoadID(r1),
+            r2: RoadID(r2),
+            i: IntersectionID(0),
+            filter_type: FilterType::WalkCycleOnly,
+            user_modified: true,
+            enforcement: Enforcement::Camera,
+            locked: false,
+            placed_at: None,
+            group1: [RoadID(r1), RoadID(r2)].into_iter().collect(),
+            group2: BTreeSet::new(),
+        };
+
+        // Old proposals stored one filter per intersection
+        let old = serde_json::json!({
+            "intersections": [[IntersectionID(0), filter(1, 2)]],
+        });
+        let parsed: Wrapper = serde_json::from_value(old).unwrap();
+        assert!(parsed.intersections[&IntersectionID(0)] == vec![filter(1, 2)]);
+
+        let new = serde_json::json!({
+            "intersections": [[IntersectionID(0), [filter(1, 2), filter(3, 4)]]],
+        });
+        let parsed: Wrapper = serde_json::from_value(new).unwrap();
+        assert!(parsed.intersections[&IntersectionID(0)] == vec![filter(1, 2), filter(3, 4)]);
+    }
+}
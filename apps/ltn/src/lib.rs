@@ -3,14 +3,19 @@
 use structopt::StructOpt;
 
 use abstio::MapName;
-use map_model::{Map, PathConstraints, Road};
-use widgetry::tools::FutureLoader;
+use map_gui::tools::DrawSimpleRoadLabels;
+use map_model::{osm, Map, PathConstraints, Road};
+use widgetry::tools::{FutureLoader, URLManager};
 use widgetry::{EventCtx, Settings, State};
 
 pub use app::{App, PerMap, Session, Transition};
-pub use filters::{Crossing, DiagonalFilter, Edits, FilterType, RoadFilter};
+pub use filters::{
+    BannedTurn, Crossing, DiagonalFilter, DirectedSpeedLimit, Edits, Enforcement, FilterStyle,
+    FilterType, PlanMetadata, RoadFilter,
+};
 pub use logic::NeighbourhoodID;
-pub use neighbourhood::{Cell, DistanceInterval, Neighbourhood};
+pub use neighbourhood::{Cell, CellReachability, DistanceInterval, Neighbourhood};
+pub use save::validate_all_proposals;
 
 #[macro_use]
 extern crate anyhow;
@@ -90,6 +95,16 @@ fn run(mut settings: Settings) {
                     .layers
                     .event(ctx, &app.cs, components::Mode::PickArea, None);
 
+                // A proposal embedded in the URL takes priority
+                if let Some(bytes) = URLManager::get_url_fragment()
+                    .and_then(|fragment| crate::save::proposal_from_url_fragment(&fragment))
+                {
+                    let popup_state =
+                        crate::save::Proposal::load_from_bytes(ctx, app, "from the URL", bytes)
+                            .or_else(|| crate::save::describe_shared_proposal(ctx, app));
+                    return setup_initial_states(ctx, app, args.consultation.as_ref(), popup_state);
+                }
+
                 if let Some(ref name) = args.proposal {
                     // Remote edits require another intermediate state to load
                     if let Some(id) = name.strip_prefix("remote/") {
@@ -168,9 +183,8 @@ fn setup_initial_states(
         app.per_map.consultation = Some(*neighbourhood);
         app.per_map.consultation_id = Some(consultation.to_string());
 
-        // TODO Maybe center the camera, ignoring any saved values
-
-        states.push(pages::DesignLTN::new_state(
+        // Frame the neighbourhood, ignoring any saved camera
+        states.push(pages::DesignLTN::enter(
             ctx,
             app,
             app.per_map.consultation.unwrap(),
@@ -203,7 +217,8 @@ fn load_remote(
         inner_progress_rx,
         "Downloading proposal",
         Box::new(move |ctx, app, result| {
-            let popup_state = crate::save::Proposal::load_from_bytes(ctx, app, &id, result);
+            let popup_state = crate::save::Proposal::load_from_bytes(ctx, app, &id, result)
+                .or_else(|| crate::save::describe_shared_proposal(ctx, app));
             Transition::Clear(setup_initial_states(
                 ctx,
                 app,
@@ -229,7 +244,48 @@ pub fn run_wasm(root_dom_id: String, assets_base_url: String, assets_are_gzipped
 }
 
 pub fn redraw_all_filters(ctx: &EventCtx, app: &mut App) {
-    app.per_map.draw_all_filters = app.edits().draw(ctx, &app.per_map.map);
+    app.per_map.draw_all_filters = app
+        .edits()
+        .draw(
+            ctx,
+            &app.per_map.map,
+            app.session.layers.filter_icon_scale,
+            app.session.layers.filter_zoom(),
+            app.session.layers.filter_style,
+        )
+        .with_summary(render::render_filter_summary(
+            ctx,
+            &app.per_map.map,
+            app.partitioning(),
+            app.edits(),
+        ));
+    app.per_map.draw_one_way_edits =
+        render::render_one_way_edits(ctx, &app.per_map.map, app.edits());
+    app.per_map.draw_unedited_roads =
+        render::render_unedited_roads(ctx, &app.per_map.map, &app.cs, app.edits());
+    app.per_map.draw_speed_limit_edits =
+        render::render_speed_limit_edits(ctx, &app.per_map.map, app.edits(), &app.opts.units);
+}
+
+/// Only the per-neighbourhood summary of filters depends on the boundaries, so call this after
+/// changing them, instead of `redraw_all_filters`
+pub fn redraw_filter_summary(ctx: &EventCtx, app: &mut App) {
+    let summary =
+        render::render_filter_summary(ctx, &app.per_map.map, app.partitioning(), app.edits());
+    app.per_map.draw_all_filters.replace_summary(summary);
+}
+
+/// Recalculate road labels after changing `Edits::road_names`
+pub fn relabel_roads(ctx: &mut EventCtx, app: &mut App) {
+    app.per_map.draw_major_road_labels = DrawSimpleRoadLabels::new_with_name_overrides(
+        ctx,
+        app,
+        render::colors::MAIN_ROAD_LABEL,
+        Box::new(|r| r.get_rank() != osm::RoadRank::Local && !r.is_light_rail()),
+        app.edits().road_names.clone(),
+    );
+    // Lazily recalculated when needed
+    app.per_map.draw_all_local_road_labels = None;
 }
 
 fn is_private(road: &Road) -> bool {
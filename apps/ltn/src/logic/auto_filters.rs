@@ -1,13 +1,16 @@
 //! Experiments to make a neighbourhood be low-traffic by automatically placing filters to prevent
 //! all shortcuts.
 
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 
 use abstutil::Timer;
-use map_model::RoadID;
+use geom::Distance;
+use map_model::{IntersectionID, Map, RoadID};
 use widgetry::{Choice, EventCtx};
 
-use crate::{mut_edits, redraw_all_filters, App, Neighbourhood, RoadFilter};
+use crate::{mut_edits, redraw_all_filters, App, Edits, Neighbourhood, RoadFilter};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum AutoFilterHeuristic {
@@ -65,7 +68,7 @@ impl AutoFilterHeuristic {
 
         // TODO If we already have no shortcuts, stop
 
-        app.per_map.proposals.before_edit();
+        app.per_map.proposals.start_edit_group();
 
         match self {
             AutoFilterHeuristic::Greedy => greedy(app, neighbourhood),
@@ -74,7 +77,7 @@ impl AutoFilterHeuristic {
             AutoFilterHeuristic::OnlyOneBorder => only_one_border(app, neighbourhood),
         }
 
-        let empty = app.per_map.proposals.cancel_empty_edit();
+        let empty = app.per_map.proposals.finish_edit_group();
         redraw_all_filters(ctx, app);
         if empty {
             bail!("No new filters created");
@@ -191,13 +194,15 @@ fn only_one_border(app: &mut App, neighbourhood: &Neighbourhood) {
                     if road.src_i == *i {
                         mut_edits!(app).roads.insert(
                             road.id,
-                            RoadFilter::new_by_user(0.1 * road.length(), app.session.filter_type),
+                            RoadFilter::new_by_user(0.1 * road.length(), app.session.filter_type)
+                                .enforced_by(app.session.bus_gate_enforcement),
                         );
                         break;
                     } else if road.dst_i == *i {
                         mut_edits!(app).roads.insert(
                             road.id,
-                            RoadFilter::new_by_user(0.9 * road.length(), app.session.filter_type),
+                            RoadFilter::new_by_user(0.9 * road.length(), app.session.filter_type)
+                                .enforced_by(app.session.bus_gate_enforcement),
                         );
                         break;
                     }
@@ -207,6 +212,67 @@ fn only_one_border(app: &mut App, neighbourhood: &Neighbourhood) {
     }
 }
 
+/// The inverse of the usual workflow: instead of placing filters and seeing what cells result, say
+/// which cell each interior road should belong to, and get back the filters (as a road and a
+/// distance along it) needed to separate them. Roads missing from `desired` are all lumped into one
+/// more cell. Roads that already have a filter are assumed to be cut already.
+///
+/// At every junction where roads from different cells meet, the cell with the most roads there
+/// keeps driving through, and the rest are cut off right next to the junction. That's the fewest
+/// filters possible at each junction, though not necessarily the fewest overall.
+pub fn filters_to_realize_cells(
+    map: &Map,
+    neighbourhood: &Neighbourhood,
+    edits: &Edits,
+    desired: &BTreeMap<RoadID, usize>,
+) -> Vec<(RoadID, Distance)> {
+    let mut cut_ends: BTreeMap<RoadID, Vec<IntersectionID>> = BTreeMap::new();
+    for i in &neighbourhood.interior_intersections {
+        let mut roads_per_cell: BTreeMap<Option<usize>, Vec<RoadID>> = BTreeMap::new();
+        for r in &map.get_i(*i).roads {
+            if neighbourhood.interior_roads.contains(r) && !edits.roads.contains_key(r) {
+                roads_per_cell
+                    .entry(desired.get(r).cloned())
+                    .or_insert_with(Vec::new)
+                    .push(*r);
+            }
+        }
+        if roads_per_cell.len() < 2 {
+            continue;
+        }
+
+        let keep = roads_per_cell
+            .iter()
+            .max_by_key(|(_, roads)| roads.len())
+            .map(|(cell, _)| *cell)
+            .unwrap();
+        for (cell, roads) in roads_per_cell {
+            if cell != keep {
+                for r in roads {
+                    cut_ends.entry(r).or_insert_with(Vec::new).push(*i);
+                }
+            }
+        }
+    }
+
+    cut_ends
+        .into_iter()
+        .map(|(r, ends)| {
+            let road = map.get_r(r);
+            let pct = if ends.len() > 1 {
+                // Both ends need cutting. One filter in the middle still stops anybody driving
+                // between them.
+                0.5
+            } else if road.src_i == ends[0] {
+                0.1
+            } else {
+                0.9
+            };
+            (r, pct * road.length())
+        })
+        .collect()
+}
+
 // If successful, returns a Neighbourhood and leaves the new filter in place. If it disconncts a
 // cell, reverts the change and returns None
 fn try_to_filter_road(
@@ -217,7 +283,8 @@ fn try_to_filter_road(
     let road = app.per_map.map.get_r(r);
     mut_edits!(app).roads.insert(
         r,
-        RoadFilter::new_by_user(road.length() / 2.0, app.session.filter_type),
+        RoadFilter::new_by_user(road.length() / 2.0, app.session.filter_type)
+            .enforced_by(app.session.bus_gate_enforcement),
     );
     let new_neighbourhood = Neighbourhood::new(app, neighbourhood.id);
     if new_neighbourhood.cells.iter().any(|c| c.is_disconnected()) {
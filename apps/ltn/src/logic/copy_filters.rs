@@ -0,0 +1,122 @@
+//! Reuse a treatment that worked in one neighbourhood somewhere else, by copying each filter to
+//! the road in roughly the same relative position.
+
+use std::collections::BTreeSet;
+
+use geom::{Distance, Pt2D};
+use map_model::RoadID;
+
+use crate::{mut_edits, App, FilterType, Neighbourhood, RoadFilter};
+
+/// Copy road filters from the interior of `from` to the interior of `to`. Positions are matched
+/// relative to each neighbourhood's bounding box, preferring roads of the same rank. The caller
+/// must call this in a `before_edit` / `redraw_all_filters` "transaction." Returns the number of
+/// filters placed and a description of every problem.
+pub fn copy_filters(
+    app: &mut App,
+    from: &Neighbourhood,
+    to: &Neighbourhood,
+) -> (usize, Vec<String>) {
+    let map = &app.per_map.map;
+    let from_bounds = from.boundary_polygon.get_bounds();
+    let to_bounds = to.boundary_polygon.get_bounds();
+    // Don't match a filter to a road too far away from where it should be
+    let max_dist = Distance::meters(0.25 * to_bounds.width().max(to_bounds.height()));
+
+    let mut placed = Vec::new();
+    let mut problems = Vec::new();
+    let mut used: BTreeSet<RoadID> = BTreeSet::new();
+
+    for (r, filter) in &app.edits().roads {
+        if !from.interior_roads.contains(r) {
+            continue;
+        }
+        let src_road = map.get_r(*r);
+        let name = app
+            .edits()
+            .get_road_name(src_road, app.opts.language.as_ref());
+        let src_pt = match src_road.center_pts.dist_along(filter.dist) {
+            Ok((pt, _)) => pt,
+            Err(_) => continue,
+        };
+
+        let target_pt = Pt2D::new(
+            to_bounds.min_x
+                + to_bounds.width() * (src_pt.x() - from_bounds.min_x) / from_bounds.width(),
+            to_bounds.min_y
+                + to_bounds.height() * (src_pt.y() - from_bounds.min_y) / from_bounds.height(),
+        );
+
+        // Prefer roads of the same rank, then the closest
+        let best = to
+            .interior_roads
+            .iter()
+            .filter_map(|candidate| {
+                if used.contains(candidate) || app.edits().roads.contains_key(candidate) {
+                    return None;
+                }
+                let road = map.get_r(*candidate);
+                if !crate::is_driveable(road, map)
+                    || road.oneway_for_driving().is_some()
+                    || road.is_deadend_for_driving(map)
+                {
+                    return None;
+                }
+                let pt = road.center_pts.project_pt(target_pt);
+                let dist = pt.dist_to(target_pt);
+                if dist > max_dist {
+                    return None;
+                }
+                Some((road.get_rank() != src_road.get_rank(), dist, *candidate, pt))
+            })
+            .min_by_key(|(different_rank, dist, _, _)| (*different_rank, *dist));
+
+        let (_, _, target, pt) = match best {
+            Some(x) => x,
+            None => {
+                problems.push(format!("No matching road for the filter on {}", name));
+                continue;
+            }
+        };
+        let target_road = map.get_r(target);
+        let dist = match target_road.center_pts.dist_along_of_point(pt) {
+            Some((dist, _)) => dist,
+            None => {
+                problems.push(format!("No matching road for the filter on {}", name));
+                continue;
+            }
+        };
+
+        let mut filter_type = filter.filter_type;
+        if filter_type != FilterType::BusGate && !map.get_bus_routes_on_road(target).is_empty() {
+            filter_type = FilterType::BusGate;
+            problems.push(format!(
+                "Used a bus gate instead of copying the filter on {}, because buses use {}",
+                name,
+                app.edits()
+                    .get_road_name(target_road, app.opts.language.as_ref())
+            ));
+        }
+        used.insert(target);
+        placed.push((
+            target,
+            RoadFilter::new_by_user(dist, filter_type).enforced_by(filter.enforcement),
+        ));
+    }
+
+    for (i, filters) in &app.edits().intersections {
+        if from.interior_intersections.contains(i) {
+            problems.push(format!(
+                "{} diagonal filter(s) at {} weren't copied",
+                filters.len(),
+                i
+            ));
+        }
+    }
+
+    let num_placed = placed.len();
+    for (r, filter) in placed {
+        mut_edits!(app).roads.insert(r, filter);
+    }
+    (num_placed, problems)
+}
@@ -1,4 +1,5 @@
-use map_model::Map;
+use geom::Duration;
+use map_model::{CrossingType, Map};
 
 use crate::{Crossing, Edits};
 
@@ -17,3 +18,14 @@ pub fn populate_existing_crossings(map: &Map, edits: &mut Edits) {
         }
     }
 }
+
+/// How long a pedestrian spends waiting at and then walking over a crossing. These are rough
+/// guesses; the real values depend on signal timing, the width of the road, and traffic.
+pub fn pedestrian_delay(kind: CrossingType) -> Duration {
+    match kind {
+        // Most of this is waiting for the pedestrian phase
+        CrossingType::Signalized => Duration::const_seconds(45.0),
+        // Traffic should give way, so it's mostly just the time to cross
+        CrossingType::Unsignalized => Duration::const_seconds(10.0),
+    }
+}
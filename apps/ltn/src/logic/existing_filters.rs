@@ -2,7 +2,7 @@ use abstutil::{Tags, Timer};
 use geom::Distance;
 use map_model::{osm, Map, Road};
 
-use crate::{Edits, FilterType, RoadFilter};
+use crate::{Edits, Enforcement, FilterType, RoadFilter};
 
 /// Detect roads that're modelled in OSM as cycleways, but really are regular roads with modal
 /// filters. Transform them into normal roads, and instead use this tool's explicit representation
@@ -46,6 +46,12 @@ pub fn transform_existing_filters(map: &mut Map, timer: &mut Timer) -> Edits {
                         FilterType::BusGate
                     },
                     user_modified: false,
+                    enforcement: Enforcement::default(),
+                    locked: false,
+                    trial_until: None,
+                    angle_override: None,
+                    placed_at: None,
+                    planters: false,
                 },
             );
         }
@@ -66,6 +72,12 @@ pub fn transform_existing_filters(map: &mut Map, timer: &mut Timer) -> Edits {
                         FilterType::BusGate
                     },
                     user_modified: false,
+                    enforcement: Enforcement::default(),
+                    locked: false,
+                    trial_until: None,
+                    angle_override: None,
+                    placed_at: None,
+                    planters: false,
                 },
             );
         }
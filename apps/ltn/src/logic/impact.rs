@@ -1,15 +1,20 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use abstio::MapName;
-use abstutil::Timer;
+use abstutil::{prettyprint_usize, Timer};
 use geom::{Duration, Time};
 use map_gui::tools::compare_counts::CompareCounts;
-use map_model::{PathConstraints, PathRequest, PathV2, Pathfinder, RoadID};
+use map_model::{
+    EditRoad, LaneID, Map, PathConstraints, PathRequest, PathV2, Pathfinder, RoadID, RoutingParams,
+};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sim::{Sim, SimOptions};
 use synthpop::{Scenario, TrafficCounts, TripEndpoint, TripMode};
 use widgetry::EventCtx;
 
 use crate::filters::ChangeKey;
-use crate::App;
+use crate::{App, Edits};
 
 // TODO Configurable main road penalty, like in the pathfinding tool
 // TODO Share structure or pieces with Ungap's predict mode
@@ -23,17 +28,31 @@ use crate::App;
 pub struct Impact {
     pub map: MapName,
     pub filters: Filters,
+    /// The scenario the trips came from
+    pub scenario: Option<Scenario>,
 
     // Handles all modes
     // TODO Maybe try to use this app-wide
     pathfinder_before_changes: Pathfinder,
+    // Only cars, with the edits as of the ChangeKey. Kept until the edits change, since building
+    // it is slow.
+    pathfinder_rush_hour: Option<(ChangeKey, Pathfinder)>,
+    // Durations of driving trips in the morning peak, from simulating it. Also slow, so the
+    // results after changes are kept until the edits change. One-way changes are real map edits,
+    // so they affect the results before the other changes too.
+    simulated_rush_hour_before: Option<(BTreeMap<RoadID, EditRoad>, Vec<Duration>)>,
+    simulated_rush_hour_after: Option<(ChangeKey, Vec<Duration>)>,
 
     all_trips: Vec<PathRequest>,
+    // Driving trips departing during the morning peak
+    rush_hour_trips: Vec<PathRequest>,
     // A subset of all_trips, and the number of times somebody takes the same trip
     filtered_trips: Vec<(PathRequest, usize)>,
 
     pub compare_counts: CompareCounts,
     pub change_key: ChangeKey,
+    /// Instead of the before/after comparison, only show roads that gain traffic
+    pub show_displaced_traffic: bool,
 }
 
 #[derive(PartialEq)]
@@ -49,6 +68,7 @@ impl Impact {
     pub fn empty(ctx: &EventCtx) -> Self {
         Self {
             map: MapName::blank(),
+            scenario: None,
             filters: Filters {
                 modes: vec![TripMode::Drive].into_iter().collect(),
                 include_borders: true,
@@ -56,12 +76,17 @@ impl Impact {
             },
 
             pathfinder_before_changes: Pathfinder::empty(),
+            pathfinder_rush_hour: None,
+            simulated_rush_hour_before: None,
+            simulated_rush_hour_after: None,
 
             all_trips: Vec::new(),
+            rush_hour_trips: Vec::new(),
             filtered_trips: Vec::new(),
 
             compare_counts: CompareCounts::empty(ctx),
             change_key: ChangeKey::default(),
+            show_displaced_traffic: false,
         }
     }
 
@@ -83,13 +108,26 @@ impl Impact {
 
         impact.map = app.per_map.map.get_name().clone();
         impact.change_key = app.edits().get_change_key();
-        impact.all_trips = timer
+        let trips: Vec<(Time, PathRequest)> = timer
             .parallelize("analyze trips", scenario.all_trips().collect(), |trip| {
                 TripEndpoint::path_req(trip.origin, trip.destination, trip.mode, map)
+                    .map(|req| (trip.depart, req))
             })
             .into_iter()
             .flatten()
             .collect();
+        let (peak_start, peak_end) = morning_peak();
+        impact.rush_hour_trips = trips
+            .iter()
+            .filter(|(depart, req)| {
+                *depart >= peak_start
+                    && *depart < peak_end
+                    && req.constraints == PathConstraints::Car
+            })
+            .map(|(_, req)| req.clone())
+            .collect();
+        impact.all_trips = trips.into_iter().map(|(_, req)| req).collect();
+        impact.scenario = Some(scenario);
         impact.trips_changed(ctx, app, timer);
         impact.compare_counts.autoselect_layer();
         impact
@@ -194,6 +232,25 @@ impl Impact {
         counts
     }
 
+    /// Where does traffic go after filtering? Returns every road carrying more trips after the
+    /// changes than before, with the number of extra trips, busiest first.
+    pub fn displaced_traffic(&self) -> Vec<(RoadID, usize)> {
+        let before = &self.compare_counts.counts_a.per_road;
+        let mut result: Vec<(RoadID, usize)> = self
+            .compare_counts
+            .counts_b
+            .per_road
+            .borrow()
+            .iter()
+            .filter_map(|(r, after)| {
+                let extra = after.saturating_sub(before.get(*r));
+                (extra > 0).then(|| (*r, extra))
+            })
+            .collect();
+        result.sort_by_key(|(_, extra)| std::cmp::Reverse(*extra));
+        result
+    }
+
     /// Returns routes that start or stop crossing the given road. Returns paths (before filters,
     /// after)
     pub fn find_changed_routes(
@@ -225,6 +282,233 @@ impl Impact {
         }
         changed
     }
+
+    /// Routes every driving trip departing in the morning peak before and after the current
+    /// edits, and summarizes the change. The trip times are also simulated both ways, to include
+    /// congestion.
+    pub fn rush_hour_report(
+        &mut self,
+        map: &Map,
+        edits: &Edits,
+        params_before_changes: &RoutingParams,
+        timer: &mut Timer,
+    ) -> RushHourReport {
+        let change_key = edits.get_change_key();
+        if self
+            .pathfinder_rush_hour
+            .as_ref()
+            .map(|(key, _)| key != &change_key)
+            .unwrap_or(true)
+        {
+            let mut params = map.routing_params().clone();
+            edits.update_routing_params(&mut params);
+            let pathfinder = Pathfinder::new_ch(map, params, vec![PathConstraints::Car], timer);
+            self.pathfinder_rush_hour = Some((change_key, pathfinder));
+        }
+        let pathfinder_after = &self.pathfinder_rush_hour.as_ref().unwrap().1;
+
+        let mut report = RushHourReport {
+            num_trips: 0,
+            total_before: Duration::ZERO,
+            total_after: Duration::ZERO,
+            num_rerouted: 0,
+            num_impossible: 0,
+            worst: Vec::new(),
+            simulated_before: Vec::new(),
+            simulated_after: Vec::new(),
+        };
+        let trips = PathRequest::deduplicate(map, self.rush_hour_trips.clone());
+        timer.start_iter("route rush hour trips", trips.len());
+        for (req, count) in trips {
+            timer.next();
+            let path1 = match self.pathfinder_before_changes.pathfind_v2(req.clone(), map) {
+                Some(path) => path,
+                None => continue,
+            };
+            report.num_trips += count;
+            let path2 = match pathfinder_after.pathfind_v2(req.clone(), map) {
+                Some(path) => path,
+                None => {
+                    report.num_impossible += count;
+                    continue;
+                }
+            };
+
+            let before = path1.estimate_duration(map, None, None);
+            // Skip spurious changes where the cost matches
+            let after = if path1.get_cost() == path2.get_cost() {
+                before
+            } else {
+                report.num_rerouted += count;
+                path2
+                    .estimate_duration_with_speeds(map, None, None, |dr| edits.speed_limit(map, dr))
+            };
+            report.total_before += (count as f64) * before;
+            report.total_after += (count as f64) * after;
+            if after > before {
+                report.worst.push((req, before, after));
+            }
+        }
+        report
+            .worst
+            .sort_by_key(|(_, before, after)| *before - *after);
+        report.worst.truncate(5);
+
+        if self
+            .simulated_rush_hour_before
+            .as_ref()
+            .map(|(one_ways, _)| one_ways != &edits.one_ways)
+            .unwrap_or(true)
+        {
+            timer.start("simulate rush hour before changes");
+            let durations = self.simulate_rush_hour(map, params_before_changes.clone(), timer);
+            timer.stop("simulate rush hour before changes");
+            self.simulated_rush_hour_before = Some((edits.one_ways.clone(), durations));
+        }
+        if self
+            .simulated_rush_hour_after
+            .as_ref()
+            .map(|(key, _)| key != &change_key)
+            .unwrap_or(true)
+        {
+            let mut params = map.routing_params().clone();
+            edits.update_routing_params(&mut params);
+            timer.start("simulate rush hour after changes");
+            let durations = self.simulate_rush_hour(map, params, timer);
+            timer.stop("simulate rush hour after changes");
+            self.simulated_rush_hour_after = Some((change_key, durations));
+        }
+        report.simulated_before = self.simulated_rush_hour_before.as_ref().unwrap().1.clone();
+        report.simulated_after = self.simulated_rush_hour_after.as_ref().unwrap().1.clone();
+        report
+    }
+
+    /// Simulates everyone's trips in the morning peak with the given routing params, returning
+    /// the durations of driving trips that depart and finish during it.
+    fn simulate_rush_hour(
+        &self,
+        map: &Map,
+        params: RoutingParams,
+        timer: &mut Timer,
+    ) -> Vec<Duration> {
+        let (peak_start, peak_end) = morning_peak();
+        let mut scenario = match self.scenario.clone() {
+            Some(scenario) => scenario,
+            None => return Vec::new(),
+        };
+        for person in &mut scenario.people {
+            person
+                .trips
+                .retain(|trip| trip.depart >= peak_start && trip.depart < peak_end);
+        }
+        scenario.people.retain(|person| !person.trips.is_empty());
+
+        // The filters are expressed as routing params, not edits to the map, so the sim needs a
+        // copy of the map with cars routing around them
+        timer.start("copy map");
+        let mut map = map.clone();
+        timer.stop("copy map");
+        // This app doesn't otherwise keep the map's own pathfinder in sync with one-way edits
+        map.recalculate_pathfinding_after_edits(timer);
+        map.hack_override_routing_params_for_cars(params, timer);
+
+        let mut sim = Sim::new(&map, SimOptions::new("ltn_rush_hour"));
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        sim.instantiate(&scenario, &map, &mut rng, timer);
+        sim.timed_step(&map, peak_end - Time::START_OF_DAY, &mut None, timer);
+
+        sim.get_analytics()
+            .finished_trips
+            .iter()
+            .filter_map(|(finished, _, mode, duration)| {
+                let duration = (*duration)?;
+                (*mode == TripMode::Drive && *finished - duration >= peak_start).then(|| duration)
+            })
+            .collect()
+    }
+}
+
+pub struct RushHourReport {
+    pub num_trips: usize,
+    /// Only over trips possible both before and after
+    pub total_before: Duration,
+    pub total_after: Duration,
+    pub num_rerouted: usize,
+    /// Trips that can't be made by car anymore
+    pub num_impossible: usize,
+    /// The trips with the largest increase in time, with the duration before and after
+    pub worst: Vec<(PathRequest, Duration, Duration)>,
+    /// Simulated durations of driving trips in the morning peak, before and after. Unlike the
+    /// numbers above, these include congestion.
+    pub simulated_before: Vec<Duration>,
+    pub simulated_after: Vec<Duration>,
+}
+
+impl RushHourReport {
+    pub fn describe(&self, app: &App) -> Vec<String> {
+        let (start, end) = morning_peak();
+        if self.num_trips == 0 {
+            return vec![format!(
+                "No driving trips depart between {} and {}",
+                start.ampm_tostring(),
+                end.ampm_tostring()
+            )];
+        }
+        let n = self.num_trips as f64;
+        let routed = (self.num_trips - self.num_impossible) as f64;
+        let mut lines = vec![
+            format!(
+                "{} driving trips depart between {} and {}",
+                prettyprint_usize(self.num_trips),
+                start.ampm_tostring(),
+                end.ampm_tostring()
+            ),
+            format!(
+                "Average trip time: {} before, {} after",
+                self.total_before / routed.max(1.0),
+                self.total_after / routed.max(1.0)
+            ),
+            format!(
+                "{}% of trips rerouted",
+                ((self.num_rerouted as f64) / n * 100.0).round()
+            ),
+        ];
+        if self.num_impossible > 0 {
+            lines.push(format!(
+                "{} trips can't be made by car anymore",
+                prettyprint_usize(self.num_impossible)
+            ));
+        }
+        if !self.worst.is_empty() {
+            lines.push(String::new());
+            lines.push("Worst-affected trips:".to_string());
+            let map = &app.per_map.map;
+            for (req, before, after) in &self.worst {
+                let name = |l: LaneID| map.get_parent(l).get_name(app.opts.language.as_ref());
+                lines.push(format!(
+                    "- {} to {}: {} before, {} after",
+                    name(req.start.lane()),
+                    name(req.end.lane()),
+                    before,
+                    after
+                ));
+            }
+        }
+        lines.push(String::new());
+        lines.push(
+            "Times above assume free-flowing traffic. The histogram simulates congestion, but nobody choosing not to drive."
+                .to_string(),
+        );
+        lines
+    }
+}
+
+/// The departure window used for rush hour testing
+pub fn morning_peak() -> (Time, Time) {
+    (
+        Time::START_OF_DAY + Duration::hours(7),
+        Time::START_OF_DAY + Duration::hours(10),
+    )
 }
 
 // TODO Fixed, and sadly not const
@@ -1,32 +1,150 @@
-use map_model::{EditRoad, MapEdits, RoadID};
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use geom::{Distance, Speed};
+use map_gui::render::DrawMap;
+use map_model::{Direction, DrivingSide, EditRoad, LaneSpec, LaneType, MapEdits, RoadID};
 use widgetry::EventCtx;
 
 use crate::{mut_edits, App};
 
 pub fn modify_road(ctx: &mut EventCtx, app: &mut App, r: RoadID, edits: MapEdits) {
-    ctx.loading_screen("apply edits", |_, timer| {
-        app.per_map.map.must_apply_edits(edits, timer);
-        // We don't need to regenerate_unzoomed_layer for one-ways or speed limits; no widths or
-        // styling has changed
+    modify_roads(ctx, app, vec![r], edits);
+}
+
+/// Like `modify_road`, but the map edits can change many roads at once. It's all one edit to the
+/// proposal, so one undo reverts everything.
+pub fn modify_roads(ctx: &mut EventCtx, app: &mut App, roads: Vec<RoadID>, edits: MapEdits) {
+    apply_and_redraw(ctx, app, edits);
+
+    app.per_map.proposals.before_edit();
+
+    for r in roads {
+        let r_edit = app.per_map.map.get_r_edit(r);
+        // Was the road originally like this? Use the original OSM tags to decide.
+        // TODO This'll break in the face of newer osm2streets transformations. But it's the
+        // same problem as EditRoad::get_orig_from_osm -- figure out a bigger solution later.
+        if r_edit
+            == EditRoad::get_orig_from_osm(app.per_map.map.get_r(r), app.per_map.map.get_config())
+        {
+            mut_edits!(app).one_ways.remove(&r);
+        } else {
+            mut_edits!(app).one_ways.insert(r, r_edit);
+        }
+    }
 
+    // We don't need to call redraw_all_filters; no icons have changed, just the one-way layer
+    app.per_map.draw_one_way_edits =
+        crate::render::render_one_way_edits(ctx, &app.per_map.map, app.edits());
+    app.per_map.draw_unedited_roads =
+        crate::render::render_unedited_roads(ctx, &app.per_map.map, &app.cs, app.edits());
+}
+
+/// Apply map edits. For one-ways and speed limits, we don't need to regenerate the unzoomed layer,
+/// since no styling changes. But narrowing or widening lanes changes the road's shape, so redraw
+/// just in that case.
+pub fn apply_and_redraw(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
+    ctx.loading_screen("apply edits", |ctx, timer| {
+        let widths_before: Vec<Distance> = app
+            .per_map
+            .map
+            .all_roads()
+            .iter()
+            .map(|r| r.get_width())
+            .collect();
+        let effects = app.per_map.map.must_apply_edits(edits, timer);
         // See the argument in filters/existing.rs about not recalculating the pathfinder.
         // We always create it from-scratch when needed.
+
+        let map = &app.per_map.map;
+        if effects
+            .changed_roads
+            .iter()
+            .all(|r| map.get_r(*r).get_width() == widths_before[r.0])
+        {
+            return;
+        }
+        app.per_map
+            .draw_map
+            .draw_all_unzoomed_roads_and_intersections =
+            DrawMap::regenerate_unzoomed_layer(ctx, map, &app.cs, &app.opts, timer);
+        for r in effects.changed_roads {
+            app.per_map.draw_map.recreate_road(map.get_r(r), map);
+        }
+        for i in effects.changed_intersections {
+            app.per_map.draw_map.recreate_intersection(i, map);
+        }
     });
+}
 
-    app.per_map.proposals.before_edit();
+/// Change the width of one lane by some amount. Returns false, leaving the lane alone, if the
+/// result would be outside a sensible range for traffic lanes.
+pub fn change_lane_width(lanes_ltr: &mut [LaneSpec], idx: usize, delta: Distance) -> bool {
+    let width = lanes_ltr[idx].width + delta;
+    if width < MIN_TRAFFIC_LANE_WIDTH || width > MAX_TRAFFIC_LANE_WIDTH {
+        return false;
+    }
+    lanes_ltr[idx].width = width;
+    true
+}
 
-    let r_edit = app.per_map.map.get_r_edit(r);
-    // Was the road originally like this? Use the original OSM tags to decide.
-    // TODO This'll break in the face of newer osm2streets transformations. But it's the
-    // same problem as EditRoad::get_orig_from_osm -- figure out a bigger solution later.
-    if r_edit == EditRoad::get_orig_from_osm(app.per_map.map.get_r(r), app.per_map.map.get_config())
-    {
-        mut_edits!(app).one_ways.remove(&r);
-    } else {
-        mut_edits!(app).one_ways.insert(r, r_edit);
+/// Narrower than this, and even a car can't fit through
+pub const MIN_TRAFFIC_LANE_WIDTH: Distance = Distance::const_meters(2.5);
+pub const MAX_TRAFFIC_LANE_WIDTH: Distance = Distance::const_meters(4.5);
+
+/// Cycle a road between two-way and each one-way direction. Unlike
+/// `LaneSpec::toggle_road_direction`, only general driving lanes decide the direction. Bus lanes
+/// keep their position and follow the carriageway they're on whenever its direction flips, so a
+/// contraflow bus lane stays contraflow. Bike lanes, sidewalks, parking, buffers, and anything
+/// else keep exactly the position and direction they had.
+pub fn toggle_traffic_direction(lanes_ltr: &mut Vec<LaneSpec>, driving_side: DrivingSide) {
+    let traffic: Vec<usize> = lanes_ltr
+        .iter()
+        .enumerate()
+        .filter(|(_, spec)| spec.lt == LaneType::Driving)
+        .map(|(idx, _)| idx)
+        .collect();
+    // A single lane can't become two-way without adding another, so let osm2streets handle it
+    if traffic.len() < 2 {
+        LaneSpec::toggle_road_direction(lanes_ltr, driving_side);
+        return;
     }
 
-    // We don't need to call redraw_all_filters; no icons have changed
+    let bus: Vec<usize> = lanes_ltr
+        .iter()
+        .enumerate()
+        .filter(|(_, spec)| spec.lt == LaneType::Bus)
+        .map(|(idx, _)| idx)
+        .collect();
+
+    match LaneSpec::oneway_for_driving(lanes_ltr) {
+        None => {
+            // Bus lanes on the side that used to run backwards become contraflow
+            for idx in traffic {
+                lanes_ltr[idx].dir = Direction::Fwd;
+            }
+        }
+        Some(Direction::Fwd) => {
+            for idx in traffic.into_iter().chain(bus) {
+                lanes_ltr[idx].dir = lanes_ltr[idx].dir.opposite();
+            }
+        }
+        Some(Direction::Back) => {
+            // Split the lanes down the middle, each half flowing the usual way for its side. Bus
+            // lanes join the half they're on.
+            let (left, right) = if driving_side == DrivingSide::Right {
+                (Direction::Back, Direction::Fwd)
+            } else {
+                (Direction::Fwd, Direction::Back)
+            };
+            let split = traffic[traffic.len() / 2];
+            for idx in traffic.into_iter().chain(bus) {
+                lanes_ltr[idx].dir = if idx < split { left } else { right };
+            }
+        }
+    }
 }
 
 pub fn undo_proposal(ctx: &mut EventCtx, app: &mut App) {
@@ -66,11 +184,204 @@ pub fn undo_proposal(ctx: &mut EventCtx, app: &mut App) {
             }
         }
 
-        ctx.loading_screen("apply edits", |_, timer| {
-            app.per_map.map.must_apply_edits(edits, timer);
-        });
+        apply_and_redraw(ctx, app, edits);
     }
 
+    let names_changed = prev.road_names != app.edits().road_names;
     mut_edits!(app) = prev;
     crate::redraw_all_filters(ctx, app);
+    if names_changed {
+        crate::relabel_roads(ctx, app);
+    }
+}
+
+#[derive(Deserialize)]
+struct SpeedLimitRow {
+    osm_way_id: i64,
+    #[serde(default)]
+    mph: Option<f64>,
+    #[serde(default)]
+    kph: Option<f64>,
+}
+
+/// Set speed limits from a CSV file with an `osm_way_id` column and either an `mph` or `kph`
+/// column. Every road from a matching OSM way gets that limit in both directions. Returns a
+/// description of every row that didn't match anything in this map.
+pub fn import_speed_limits(ctx: &mut EventCtx, app: &mut App, path: String) -> Result<Vec<String>> {
+    let bytes = abstio::slurp_file(path)?;
+    let mut roads_per_way: BTreeMap<i64, Vec<RoadID>> = BTreeMap::new();
+    for road in app.per_map.map.all_roads() {
+        roads_per_way
+            .entry(road.orig_id.osm_way_id.0)
+            .or_insert_with(Vec::new)
+            .push(road.id);
+    }
+
+    let mut limits = Vec::new();
+    let mut unmatched = Vec::new();
+    for (idx, rec) in csv::Reader::from_reader(&bytes[..])
+        .deserialize::<SpeedLimitRow>()
+        .enumerate()
+    {
+        let row = rec?;
+        // The header is line 1
+        let line = idx + 2;
+        let speed = match (row.mph, row.kph) {
+            (Some(mph), _) => Speed::miles_per_hour(mph),
+            (None, Some(kph)) => Speed::km_per_hour(kph),
+            (None, None) => {
+                unmatched.push(format!("Line {line}: no speed limit given"));
+                continue;
+            }
+        };
+        match roads_per_way.get(&row.osm_way_id) {
+            Some(roads) => {
+                for r in roads {
+                    limits.push((*r, speed));
+                }
+            }
+            None => {
+                unmatched.push(format!(
+                    "Line {line}: way {} isn't in this map",
+                    row.osm_way_id
+                ));
+            }
+        }
+    }
+
+    let map = &app.per_map.map;
+    // Skip roads that already have the limit, so re-importing the same file is a no-op
+    limits.retain(|(r, speed)| {
+        map.get_r(*r).speed_limit != *speed || app.edits().speed_limits.contains_key(r)
+    });
+    if limits.is_empty() {
+        return Ok(unmatched);
+    }
+
+    let mut edits = map.get_edits().clone();
+    for (r, speed) in &limits {
+        let speed = *speed;
+        edits.commands.push(map.edit_road_cmd(*r, |new| {
+            new.speed_limit = speed;
+        }));
+    }
+    let roads: Vec<RoadID> = limits.iter().map(|(r, _)| *r).collect();
+    // This calls before_edit
+    modify_roads(ctx, app, roads.clone(), edits);
+    // The official limit is the same in both directions
+    for r in roads {
+        mut_edits!(app).speed_limits.remove(&r);
+    }
+    app.per_map.draw_speed_limit_edits = crate::render::render_speed_limit_edits(
+        ctx,
+        &app.per_map.map,
+        app.edits(),
+        &app.opts.units,
+    );
+
+    Ok(unmatched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane(lt: LaneType, dir: Direction) -> LaneSpec {
+        LaneSpec {
+            lt,
+            dir,
+            width: Distance::meters(3.0),
+            allowed_turns: Default::default(),
+        }
+    }
+
+    fn dirs(lanes_ltr: &[LaneSpec]) -> Vec<(LaneType, Direction)> {
+        lanes_ltr.iter().map(|spec| (spec.lt, spec.dir)).collect()
+    }
+
+    #[test]
+    fn test_toggle_traffic_direction() {
+        use Direction::{Back, Fwd};
+        use LaneType::{Biking, Driving, Sidewalk};
+
+        let mut lanes_ltr = vec![
+            lane(Sidewalk, Back),
+            lane(Biking, Back),
+            lane(Driving, Back),
+            lane(Driving, Fwd),
+            lane(Biking, Fwd),
+            lane(Sidewalk, Fwd),
+        ];
+
+        // Two-way becomes one-way forwards, leaving the bike lanes alone
+        toggle_traffic_direction(&mut lanes_ltr, DrivingSide::Right);
+        assert_eq!(
+            dirs(&lanes_ltr),
+            vec![
+                (Sidewalk, Back),
+                (Biking, Back),
+                (Driving, Fwd),
+                (Driving, Fwd),
+                (Biking, Fwd),
+                (Sidewalk, Fwd),
+            ]
+        );
+
+        toggle_traffic_direction(&mut lanes_ltr, DrivingSide::Right);
+        assert_eq!(
+            dirs(&lanes_ltr),
+            vec![
+                (Sidewalk, Back),
+                (Biking, Back),
+                (Driving, Back),
+                (Driving, Back),
+                (Biking, Fwd),
+                (Sidewalk, Fwd),
+            ]
+        );
+
+        // Back to two-way, splitting only the driving lanes
+        toggle_traffic_direction(&mut lanes_ltr, DrivingSide::Right);
+        assert_eq!(
+            dirs(&lanes_ltr),
+            vec![
+                (Sidewalk, Back),
+                (Biking, Back),
+                (Driving, Back),
+                (Driving, Fwd),
+                (Biking, Fwd),
+                (Sidewalk, Fwd),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toggle_traffic_direction_left_handed() {
+        use Direction::{Back, Fwd};
+        use LaneType::{Bus, Driving};
+
+        let mut lanes_ltr = vec![
+            lane(Bus, Back),
+            lane(Driving, Back),
+            lane(Driving, Back),
+            lane(Bus, Back),
+        ];
+        toggle_traffic_direction(&mut lanes_ltr, DrivingSide::Left);
+        assert_eq!(
+            dirs(&lanes_ltr),
+            vec![(Bus, Fwd), (Driving, Fwd), (Driving, Back), (Bus, Back)]
+        );
+
+        // Back to one-way, keeping the bus lanes with their carriageway
+        toggle_traffic_direction(&mut lanes_ltr, DrivingSide::Left);
+        assert_eq!(
+            dirs(&lanes_ltr),
+            vec![(Bus, Fwd), (Driving, Fwd), (Driving, Fwd), (Bus, Back)]
+        );
+        toggle_traffic_direction(&mut lanes_ltr, DrivingSide::Left);
+        assert_eq!(
+            dirs(&lanes_ltr),
+            vec![(Bus, Back), (Driving, Back), (Driving, Back), (Bus, Fwd)]
+        );
+    }
 }
@@ -1,4 +1,5 @@
 mod auto_filters;
+mod copy_filters;
 pub mod crossings;
 mod existing_filters;
 pub mod impact;
@@ -6,7 +7,8 @@ pub mod map_edits;
 mod partition;
 mod shortcuts;
 
-pub use auto_filters::AutoFilterHeuristic;
+pub use auto_filters::{filters_to_realize_cells, AutoFilterHeuristic};
+pub use copy_filters::copy_filters;
 pub use crossings::populate_existing_crossings;
 pub use existing_filters::transform_existing_filters;
 pub use impact::Impact;
@@ -436,6 +436,25 @@ impl Partitioning {
         Ok(blocks)
     }
 
+    /// Find the block just outside a neighbourhood, on the other side of one of its perimeter
+    /// roads. Adding it to the neighbourhood makes that road part of the interior.
+    pub fn block_across_road(&self, id: NeighbourhoodID, r: RoadID) -> Option<BlockID> {
+        let road_side = self
+            .neighbourhood_block(id)
+            .perimeter
+            .roads
+            .iter()
+            .find(|side| side.road == r)?;
+        let other_side = road_side.other_side();
+        self.all_single_blocks()
+            .into_iter()
+            .find(|(block_id, block)| {
+                self.block_to_neighbourhood[block_id] != id
+                    && block.perimeter.roads.contains(&other_side)
+            })
+            .map(|(block_id, _)| block_id)
+    }
+
     /// We want to add target_block to new_owner, but we can't. Find the blocks we may need to add
     /// first.
     pub fn find_intermediate_blocks(
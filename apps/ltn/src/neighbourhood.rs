@@ -1,13 +1,17 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use maplit::btreeset;
 
-use geom::{ArrowCap, Distance, PolyLine, Polygon};
-use map_model::{osm, Direction, IntersectionID, Map, RoadID};
-use widgetry::{Drawable, EventCtx, GeomBatch};
+use abstutil::Timer;
+use geom::{ArrowCap, Circle, Distance, PolyLine, Polygon};
+use map_gui::ID;
+use map_model::{
+    osm, AmenityType, DirectedRoadID, Direction, IntersectionID, Map, PathConstraints, RoadID,
+};
+use widgetry::{Drawable, EventCtx};
 
 use crate::logic::{CustomBoundary, Partitioning, Shortcuts};
-use crate::{is_private, App, Edits, NeighbourhoodID};
+use crate::{is_private, App, Edits, FilterType, NeighbourhoodID};
 
 // Once constructed, a Neighbourhood is immutable
 pub struct Neighbourhood {
@@ -27,6 +31,16 @@ pub struct Neighbourhood {
     pub shortcuts: Shortcuts,
 }
 
+/// How a driver can get from one cell to another
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CellReachability {
+    /// Without leaving the neighbourhood. Only true from a cell to itself, by definition.
+    Internal,
+    /// By leaving the neighbourhood and re-entering it somewhere else
+    External,
+    Unreachable,
+}
+
 /// A partitioning of the interior of a neighbourhood based on driving connectivity
 pub struct Cell {
     /// Most roads are fully in one cell. Roads with modal filters on them are sometimes split
@@ -204,19 +218,349 @@ impl Neighbourhood {
 
         // TODO The timer could be nice for large areas. But plumbing through one everywhere is
         // tedious, and would hit a nested start_iter bug anyway.
-        self.shortcuts = Shortcuts::new(map, edits, self, &mut abstutil::Timer::throwaway());
+        self.shortcuts = Shortcuts::new(map, edits, self, &mut Timer::throwaway());
     }
 
-    pub fn fade_irrelevant(&self, ctx: &EventCtx, app: &App) -> Drawable {
-        let fade_area = Polygon::with_holes(
-            app.per_map
-                .map
-                .get_boundary_polygon()
-                .get_outer_ring()
-                .clone(),
-            vec![self.boundary_polygon.clone().into_outer_ring()],
+    /// Interior roads that a driver can't pass straight through with these edits
+    pub fn dead_ends(&self, map: &Map, edits: &Edits) -> BTreeSet<RoadID> {
+        find_dead_ends(map, &self.interior_roads, &self.borders, edits)
+    }
+
+    /// Partition the interior as buses see it: bus gates don't divide anything. Comparing this with
+    /// `cells` shows where public transport can still pass through.
+    pub fn cells_for_buses(&self, map: &Map, edits: &Edits) -> Vec<Cell> {
+        // Only the filters matter here, so don't clone the edit history
+        let mut bus_edits = Edits::default();
+        for (r, filter) in &edits.roads {
+            if filter.filter_type != FilterType::BusGate {
+                bus_edits.roads.insert(*r, filter.clone());
+            }
+        }
+        for (i, filters) in &edits.intersections {
+            let filters: Vec<_> = filters
+                .iter()
+                .filter(|f| f.filter_type != FilterType::BusGate)
+                .cloned()
+                .collect();
+            if !filters.is_empty() {
+                bus_edits.intersections.insert(*i, filters);
+            }
+        }
+        find_cells(map, &self.interior_roads, &self.borders, &bus_edits)
+    }
+
+    /// For `redundant_filters` and `rank_filters`: a copy of the edits to remove filters from one
+    /// at a time, along with the interior roads and intersections that have filters. The copy
+    /// keeps everything that affects routing, so the baseline shortcuts stay comparable.
+    fn filters_to_try(&self, edits: &Edits) -> (Edits, Vec<RoadID>, Vec<IntersectionID>) {
+        let roads: Vec<RoadID> = self
+            .interior_roads
+            .iter()
+            .filter(|r| edits.roads.contains_key(r))
+            .cloned()
+            .collect();
+        let intersections: Vec<IntersectionID> = self
+            .interior_intersections
+            .iter()
+            .filter(|i| edits.intersections.contains_key(i))
+            .cloned()
+            .collect();
+        (edits.routing_copy(), roads, intersections)
+    }
+
+    /// Find filters in this neighbourhood that don't change any driving route through it. All of
+    /// the diagonal filters at one intersection count as one. Filters are removed one at a time,
+    /// and stay removed if the cells and the shortcuts stay exactly the same, so the whole set
+    /// returned can be removed together without changing anything -- maybe the roads are already
+    /// unreachable, or other filters do the job. Only routes entering, leaving, or crossing the
+    /// neighbourhood are compared.
+    pub fn redundant_filters(&self, map: &Map, edits: &Edits, timer: &mut Timer) -> Vec<ID> {
+        let (mut trial, roads, intersections) = self.filters_to_try(edits);
+        let cell_roads = |cells: &[Cell]| -> BTreeSet<BTreeSet<RoadID>> {
+            cells
+                .iter()
+                .map(|cell| cell.roads.keys().cloned().collect())
+                .collect()
+        };
+        let orig_cells = cell_roads(&self.cells);
+        let unchanged = |trial: &Edits| {
+            let cells = find_cells(map, &self.interior_roads, &self.borders, trial);
+            if cell_roads(&cells) != orig_cells {
+                return false;
+            }
+            let shortcuts = Shortcuts::new(map, trial, self, &mut Timer::throwaway());
+            shortcuts.paths.len() == self.shortcuts.paths.len()
+                && self.interior_roads.iter().all(|road| {
+                    shortcuts.count_per_road.get(*road) == self.shortcuts.count_per_road.get(*road)
+                })
+        };
+
+        let mut redundant = Vec::new();
+        timer.start_iter("check each filter", roads.len() + intersections.len());
+        for r in roads {
+            timer.next();
+            let filter = trial.roads.remove(&r).unwrap();
+            if unchanged(&trial) {
+                redundant.push(ID::Road(r));
+            } else {
+                trial.roads.insert(r, filter);
+            }
+        }
+        for i in intersections {
+            timer.next();
+            let filters = trial.intersections.remove(&i).unwrap();
+            if unchanged(&trial) {
+                redundant.push(ID::Intersection(i));
+            } else {
+                trial.intersections.insert(i, filters);
+            }
+        }
+        redundant
+    }
+
+    /// How many more shortcuts would there be without each filter in this neighbourhood? All of
+    /// the diagonal filters at one intersection count as one. The most effective filters come
+    /// first.
+    pub fn rank_filters(&self, map: &Map, edits: &Edits, timer: &mut Timer) -> Vec<(ID, usize)> {
+        let (mut trial, roads, intersections) = self.filters_to_try(edits);
+        let extra_shortcuts = |trial: &Edits| {
+            Shortcuts::new(map, trial, self, &mut Timer::throwaway())
+                .paths
+                .len()
+                .saturating_sub(self.shortcuts.paths.len())
+        };
+
+        let mut ranking = Vec::new();
+        timer.start_iter(
+            "try removing each filter",
+            roads.len() + intersections.len(),
         );
-        GeomBatch::from(vec![(app.cs.fade_map_dark, fade_area)]).upload(ctx)
+        for r in roads {
+            timer.next();
+            let filter = trial.roads.remove(&r).unwrap();
+            ranking.push((ID::Road(r), extra_shortcuts(&trial)));
+            trial.roads.insert(r, filter);
+        }
+        for i in intersections {
+            timer.next();
+            let filters = trial.intersections.remove(&i).unwrap();
+            ranking.push((ID::Intersection(i), extra_shortcuts(&trial)));
+            trial.intersections.insert(i, filters);
+        }
+        ranking.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        ranking
+    }
+
+    /// What fraction of the interior road length is in a cell with at most one entrance? Traffic
+    /// can't pass through those cells.
+    pub fn fraction_calmed(&self) -> f64 {
+        let mut calmed = Distance::ZERO;
+        let mut total = Distance::ZERO;
+        for cell in &self.cells {
+            let length = cell.roads.values().fold(Distance::ZERO, |sum, interval| {
+                sum + interval.end - interval.start
+            });
+            total += length;
+            if cell.borders.len() <= 1 {
+                calmed += length;
+            }
+        }
+        if total == Distance::ZERO {
+            return 0.0;
+        }
+        calmed / total
+    }
+
+    /// How many separate areas can people walk between using only interior roads? Filters don't
+    /// stop anybody on foot, so compared to the number of cells, this shows how the neighbourhood
+    /// stays permeable for walking while through-traffic is restricted.
+    pub fn walking_areas(&self, map: &Map) -> usize {
+        let mut unvisited: BTreeSet<RoadID> = self
+            .interior_roads
+            .iter()
+            .filter(|r| PathConstraints::Pedestrian.can_use_road(map.get_r(**r), map))
+            .cloned()
+            .collect();
+        let mut areas = 0;
+        while let Some(start) = unvisited.iter().next().cloned() {
+            unvisited.remove(&start);
+            areas += 1;
+            // Flood out through every intersection, ignoring filters
+            let mut queue = vec![start];
+            while let Some(r) = queue.pop() {
+                let road = map.get_r(r);
+                for i in [road.src_i, road.dst_i] {
+                    for next in &map.get_i(i).roads {
+                        if unvisited.remove(next) {
+                            queue.push(*next);
+                        }
+                    }
+                }
+            }
+        }
+        areas
+    }
+
+    /// A polygon tightly enclosing every filter and turn restriction in the neighbourhood, for
+    /// zooming to fit the plan's interventions. None if there aren't any.
+    pub fn filters_hull(&self, map: &Map, edits: &Edits) -> Option<Polygon> {
+        let mut pts = Vec::new();
+        for r in &self.interior_roads {
+            if let Some(filter) = edits.roads.get(r) {
+                if let Ok((pt, _)) = map.get_r(*r).center_pts.dist_along(filter.dist) {
+                    pts.push(pt);
+                }
+            }
+        }
+        for i in &self.interior_intersections {
+            for filter in edits.intersections.get(i).into_iter().flatten() {
+                let line = filter.geometry(map);
+                pts.push(line.pt1());
+                pts.push(line.pt2());
+            }
+        }
+        // Bans often sit on the boundary, so include any involving an interior road
+        for (i, bans) in &edits.banned_turns {
+            if bans.iter().any(|ban| {
+                self.interior_roads.contains(&ban.from) || self.interior_roads.contains(&ban.to)
+            }) {
+                pts.push(map.get_i(*i).polygon.center());
+            }
+        }
+        if pts.is_empty() {
+            return None;
+        }
+        // A small circle around each point means one or two filters still produce an area
+        Polygon::convex_hull(
+            pts.into_iter()
+                .map(|pt| Circle::new(pt, Distance::meters(5.0)).to_polygon())
+                .collect(),
+        )
+        .ok()
+    }
+
+    /// Returns the indices of cells with a school fronting one of their roads. Drop-off traffic
+    /// makes these worth looking at first.
+    pub fn cells_with_schools(&self, map: &Map) -> BTreeSet<usize> {
+        let mut result = BTreeSet::new();
+        for b in map.all_buildings() {
+            if !b
+                .amenities
+                .iter()
+                .any(|a| AmenityType::categorize(&a.amenity_type) == Some(AmenityType::School))
+            {
+                continue;
+            }
+            let r = b.sidewalk_pos.lane().road;
+            if let Some(idx) = self
+                .cells
+                .iter()
+                .position(|cell| cell.roads.contains_key(&r))
+            {
+                result.insert(idx);
+            }
+        }
+        result
+    }
+
+    /// For every pair of cells, can a driver get from the first (the row) to the second (the
+    /// column)? Cells are never connected internally, but a well-designed neighbourhood should
+    /// let drivers reach every cell by the perimeter.
+    pub fn cell_reachability(&self, map: &Map, edits: &Edits) -> Vec<Vec<CellReachability>> {
+        (0..self.cells.len())
+            .map(|from| {
+                let reached = self.cells_reachable_from(map, edits, from);
+                (0..self.cells.len())
+                    .map(|to| {
+                        if from == to {
+                            CellReachability::Internal
+                        } else if reached.contains(&to) {
+                            CellReachability::External
+                        } else {
+                            CellReachability::Unreachable
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Search for routes that leave one cell and stay off the interior of the neighbourhood until
+    /// they enter another. Returns the indices of every cell reached. One-ways, filters, and turn
+    /// restrictions all apply, like `Edits::turn_allowed`.
+    fn cells_reachable_from(&self, map: &Map, edits: &Edits, from: usize) -> BTreeSet<usize> {
+        // A road split by a filter is only partly in a cell, so a driver there can only head for
+        // (or come from) the end of the road in the cell
+        let towards_end_in_cell = |idx: usize, dr: DirectedRoadID| {
+            self.cells[idx]
+                .roads
+                .get(&dr.road)
+                .map(|interval| match dr.dir {
+                    Direction::Fwd => interval.end == map.get_r(dr.road).length(),
+                    Direction::Back => interval.start == Distance::ZERO,
+                })
+                .unwrap_or(false)
+        };
+        let from_end_in_cell = |idx: usize, dr: DirectedRoadID| {
+            towards_end_in_cell(
+                idx,
+                DirectedRoadID {
+                    road: dr.road,
+                    dir: dr.dir.opposite(),
+                },
+            )
+        };
+
+        let mut queue = Vec::new();
+        for r in self.cells[from].roads.keys() {
+            for dr in r.both_directions() {
+                if towards_end_in_cell(from, dr) {
+                    queue.push(dr);
+                }
+            }
+        }
+        let mut visited: HashSet<DirectedRoadID> = queue.iter().cloned().collect();
+        let mut reached = BTreeSet::new();
+
+        while let Some(current) = queue.pop() {
+            for l in current.lanes(PathConstraints::Car, map) {
+                for (turn, next_lane) in map.get_next_turns_and_lanes_for(l, PathConstraints::Car) {
+                    let next = next_lane.get_directed_parent();
+                    if visited.contains(&next) {
+                        continue;
+                    }
+                    // Filters along roads are checked below, since a cell can hold just part of a
+                    // filtered road
+                    if edits.is_turn_banned(turn.id.parent, current.road, next.road)
+                        || !edits.allows_turn(turn.id)
+                    {
+                        continue;
+                    }
+                    visited.insert(next);
+
+                    // Don't cut through the interior, but note any cell entered
+                    if self.interior_roads.contains(&next.road) {
+                        for idx in 0..self.cells.len() {
+                            if idx != from && from_end_in_cell(idx, next) {
+                                reached.insert(idx);
+                            }
+                        }
+                        continue;
+                    }
+                    if edits.roads.contains_key(&next.road)
+                        || !crate::is_driveable(map.get_r(next.road), map)
+                    {
+                        continue;
+                    }
+                    queue.push(next);
+                }
+            }
+        }
+        reached
+    }
+
+    pub fn fade_irrelevant(&self, ctx: &EventCtx, app: &App) -> Drawable {
+        crate::render::fade_outside(ctx, app, vec![self.boundary_polygon.clone()])
     }
 }
 
@@ -336,8 +680,11 @@ fn floodfill(
 
             for next in &map.get_i(i).roads {
                 let next_road = map.get_r(*next);
-                if let Some(filter) = edits.intersections.get(&i) {
-                    if !filter.allows_turn(current.id, *next) {
+                if let Some(filters) = edits.intersections.get(&i) {
+                    if !filters
+                        .iter()
+                        .all(|filter| filter.allows_turn(current.id, *next))
+                    {
                         continue;
                     }
                 }
@@ -391,3 +738,67 @@ fn floodfill(
         borders: cell_borders,
     }
 }
+
+/// Find the driveable interior roads that a car can't pass straight through, because at least one
+/// end leads nowhere. A road with a filter on it always counts, since each side is a stub. A road
+/// whose only onward connection is another dead-end counts too, so this peels away whole
+/// cul-de-sac trees, not just their tips.
+fn find_dead_ends(
+    map: &Map,
+    interior_roads: &BTreeSet<RoadID>,
+    borders: &BTreeSet<IntersectionID>,
+    edits: &Edits,
+) -> BTreeSet<RoadID> {
+    let driveable: BTreeSet<RoadID> = interior_roads
+        .iter()
+        .filter(|r| crate::is_driveable(map.get_r(**r), map))
+        .cloned()
+        .collect();
+    let mut through: BTreeSet<RoadID> = driveable
+        .iter()
+        .filter(|r| !edits.roads.contains_key(r))
+        .cloned()
+        .collect();
+
+    loop {
+        let stuck: Vec<RoadID> = through
+            .iter()
+            .filter(|r| !can_pass_through(map, **r, &through, borders, edits))
+            .cloned()
+            .collect();
+        if stuck.is_empty() {
+            break;
+        }
+        for r in stuck {
+            through.remove(&r);
+        }
+    }
+
+    &driveable - &through
+}
+
+/// Can a driver continue past both ends of this road, onto the perimeter or another road still
+/// considered passable?
+fn can_pass_through(
+    map: &Map,
+    r: RoadID,
+    through: &BTreeSet<RoadID>,
+    borders: &BTreeSet<IntersectionID>,
+    edits: &Edits,
+) -> bool {
+    let road = map.get_r(r);
+    [road.src_i, road.dst_i].into_iter().all(|i| {
+        if borders.contains(&i) {
+            return true;
+        }
+        map.get_i(i).roads.iter().any(|next| {
+            *next != r
+                && through.contains(next)
+                && edits
+                    .intersections
+                    .get(&i)
+                    .map(|filters| filters.iter().all(|f| f.allows_turn(r, *next)))
+                    .unwrap_or(true)
+        })
+    })
+}
@@ -27,6 +27,7 @@ impl About {
                 .text("ltn.abstreet.org")
                 .build_def(ctx),
         ]))
+        .keyboard_navigation()
         .build(ctx);
         <dyn SimpleState<_>>::new_state(panel, Box::new(About))
     }
@@ -1,16 +1,22 @@
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
 
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
 use abstutil::PriorityQueueItem;
-use geom::{Circle, Duration};
-use map_model::{osm, CrossingType, RoadID};
-use widgetry::mapspace::{DrawCustomUnzoomedShapes, ObjectID, PerZoom, World, WorldOutcome};
+use geom::{Angle, Circle, Distance, Duration, PolyLine, Pt2D};
+use map_model::{osm, AmenityType, CrossingType, Map, RoadID};
+use synthpop::make::ScenarioGenerator;
+use synthpop::{Scenario, TripMode};
+use widgetry::mapspace::{DrawCustomUnzoomedShapes, ObjectID, World, WorldOutcome};
+use widgetry::tools::{FileLoader, PopupMsg};
 use widgetry::{
     lctrl, Color, ControlState, Drawable, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel,
     RewriteColor, State, Text, TextExt, Widget,
 };
 
 use crate::components::{AppwidePanel, BottomPanel, Mode};
-use crate::render::{colors, Toggle3Zoomed};
+use crate::render::{colors, Toggle3Zoomed, ZoomThresholds};
 use crate::{logic, mut_edits, App, Crossing, Transition};
 
 pub struct Crossings {
@@ -21,12 +27,15 @@ pub struct Crossings {
     draw_crossings: Toggle3Zoomed,
     draw_nearest_crossing: Option<Drawable>,
     time_to_nearest_crossing: BTreeMap<RoadID, Duration>,
+    /// Only filled in after the travel demand model is loaded
+    draw_desire_lines: Option<Drawable>,
+    show_desire_lines: bool,
 }
 
 impl Crossings {
     pub fn new_state(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
         let appwide_panel = AppwidePanel::new(ctx, app, Mode::Crossings);
-        let contents = make_bottom_panel(ctx, app);
+        let contents = make_bottom_panel(ctx, app, app.per_map.walking_desire_lines.is_some());
         let bottom_panel = BottomPanel::new(ctx, &appwide_panel, contents);
 
         // Just force the layers panel to align above the bottom panel
@@ -42,6 +51,12 @@ impl Crossings {
             draw_crossings: Toggle3Zoomed::empty(ctx),
             draw_nearest_crossing: None,
             time_to_nearest_crossing: BTreeMap::new(),
+            draw_desire_lines: app
+                .per_map
+                .walking_desire_lines
+                .as_ref()
+                .map(|lines| draw_desire_lines(ctx, app, lines)),
+            show_desire_lines: true,
         };
         state.update(ctx, app);
         Box::new(state)
@@ -57,7 +72,7 @@ impl Crossings {
     fn update(&mut self, ctx: &mut EventCtx, app: &App) {
         self.draw_porosity = draw_porosity(ctx, app);
         self.draw_crossings = draw_crossings(ctx, app);
-        let contents = make_bottom_panel(ctx, app);
+        let contents = make_bottom_panel(ctx, app, self.desire_lines_shown());
         self.bottom_panel = BottomPanel::new(ctx, &self.appwide_panel, contents);
         self.draw_nearest_crossing = None;
         self.time_to_nearest_crossing.clear();
@@ -70,6 +85,10 @@ impl Crossings {
 
         self.world = make_world(ctx, app, &self.time_to_nearest_crossing);
     }
+
+    fn desire_lines_shown(&self) -> bool {
+        self.draw_desire_lines.is_some() && self.show_desire_lines
+    }
 }
 
 impl State<App> for Crossings {
@@ -103,18 +122,32 @@ impl State<App> for Crossings {
             match x.as_ref() {
                 "signalized crossing" => {
                     app.session.crossing_type = CrossingType::Signalized;
-                    let contents = make_bottom_panel(ctx, app);
+                    let contents = make_bottom_panel(ctx, app, self.desire_lines_shown());
                     self.bottom_panel = BottomPanel::new(ctx, &self.appwide_panel, contents);
                 }
                 "unsignalized crossing" => {
                     app.session.crossing_type = CrossingType::Unsignalized;
-                    let contents = make_bottom_panel(ctx, app);
+                    let contents = make_bottom_panel(ctx, app, self.desire_lines_shown());
+                    self.bottom_panel = BottomPanel::new(ctx, &self.appwide_panel, contents);
+                }
+                "desire lines" => {
+                    if self.draw_desire_lines.is_none() {
+                        return load_desire_lines(ctx, app);
+                    }
+                    self.show_desire_lines = !self.show_desire_lines;
+                    let contents = make_bottom_panel(ctx, app, self.desire_lines_shown());
                     self.bottom_panel = BottomPanel::new(ctx, &self.appwide_panel, contents);
                 }
                 "undo" => {
                     logic::map_edits::undo_proposal(ctx, app);
                     self.update(ctx, app);
                 }
+                "roads near schools" => {
+                    return Transition::Push(school_report(ctx, app));
+                }
+                "crossing demand" => {
+                    return Transition::Push(demand_report(ctx, app));
+                }
                 _ => unreachable!(),
             }
         }
@@ -158,6 +191,9 @@ impl State<App> for Crossings {
         self.bottom_panel.draw(g);
         app.session.layers.draw(g, app);
         g.redraw(&self.draw_porosity);
+        if self.desire_lines_shown() {
+            g.redraw(self.draw_desire_lines.as_ref().unwrap());
+        }
         app.per_map.draw_major_road_labels.draw(g);
         app.per_map.draw_poi_icons.draw(g);
         if let Some(ref draw) = self.draw_nearest_crossing {
@@ -177,6 +213,9 @@ fn help() -> Vec<&'static str> {
     vec![
         "This shows crossings over main roads.",
         "The number of crossings determines the \"porosity\" of areas",
+        "",
+        "Show pedestrian desire lines to see where people walking in the travel demand model want to cross main roads.",
+        "Each line goes straight from the start to the end of one trip, and dots mark where it crosses.",
     ]
 }
 
@@ -210,16 +249,15 @@ fn draw_crossings(ctx: &EventCtx, app: &App) -> Toggle3Zoomed {
                 };
 
                 let icon = &icons[&crossing.kind];
-                if let Ok((pt, angle)) = road.center_pts.dist_along(crossing.dist) {
-                    let angle = angle.rotate_degs(90.0);
+                if let Ok((pt, road_angle)) = road.center_pts.dist_along(crossing.dist) {
+                    // Zoomed in, draw the markings on the road itself
                     batch.append(
-                        icon.clone()
-                            .scale_to_fit_width(road.get_width().inner_meters())
-                            .centered_on(pt)
-                            .rotate_around_batch_center(angle)
+                        crossing_markings(crossing.kind, road.get_width(), pt, road_angle)
                             .color(rewrite_color),
                     );
 
+                    let angle = road_angle.rotate_degs(90.0);
+
                     // TODO Memory intensive
                     let icon = icon.clone();
                     // TODO They can shrink a bit past their map size
@@ -237,14 +275,88 @@ fn draw_crossings(ctx: &EventCtx, app: &App) -> Toggle3Zoomed {
         }
     }
 
-    let min_zoom_for_detail = 5.0;
-    let step_size = 0.1;
+    let zoom = ZoomThresholds::CROSSINGS;
     // TODO Ideally we get rid of Toggle3Zoomed and make DrawCustomUnzoomedShapes handle this
     // medium-zoom case.
-    Toggle3Zoomed::new(
-        batch.build(ctx),
-        low_zoom.build(PerZoom::new(min_zoom_for_detail, step_size)),
-    )
+    Toggle3Zoomed::new(batch.build(ctx), low_zoom.build(zoom.per_zoom()), zoom)
+}
+
+/// How far along the road a crossing's markings extend
+const CROSSING_LENGTH: Distance = Distance::const_meters(3.0);
+
+/// Approximate the real markings for each type of crossing: zebra stripes with amber beacons for
+/// an unsignalized crossing, and dashed stop lines with signal heads for a signalized one.
+fn crossing_markings(
+    kind: CrossingType,
+    road_width: Distance,
+    pt: Pt2D,
+    angle: Angle,
+) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let half_width = road_width / 2.0;
+    let across = angle.rotate_degs(90.0);
+    // A line across the whole road, offset along the road from the crossing's center
+    let line_across = |along: Distance| {
+        let center = pt.project_away(along, angle);
+        PolyLine::must_new(vec![
+            center.project_away(half_width, across.opposite()),
+            center.project_away(half_width, across),
+        ])
+    };
+
+    match kind {
+        CrossingType::Unsignalized => {
+            // Stripes run along the road, spaced out across it
+            let stripe_width = Distance::meters(0.5);
+            let mut offset = stripe_width - half_width;
+            while offset < half_width {
+                let center = pt.project_away(offset, across);
+                batch.push(
+                    Color::WHITE,
+                    PolyLine::must_new(vec![
+                        center.project_away(CROSSING_LENGTH / 2.0, angle.opposite()),
+                        center.project_away(CROSSING_LENGTH / 2.0, angle),
+                    ])
+                    .make_polygons(stripe_width),
+                );
+                offset += 2.0 * stripe_width;
+            }
+            for side in [across, across.opposite()] {
+                batch.push(
+                    Color::ORANGE,
+                    Circle::new(
+                        pt.project_away(half_width + Distance::meters(0.5), side),
+                        Distance::meters(0.4),
+                    )
+                    .to_polygon(),
+                );
+            }
+        }
+        CrossingType::Signalized => {
+            for along in [CROSSING_LENGTH / 2.0, -CROSSING_LENGTH / 2.0] {
+                batch.extend(
+                    Color::WHITE,
+                    line_across(along).dashed_lines(
+                        Distance::meters(0.3),
+                        Distance::meters(0.5),
+                        Distance::meters(0.3),
+                    ),
+                );
+            }
+            for side in [across, across.opposite()] {
+                let head = pt.project_away(half_width + Distance::meters(0.5), side);
+                batch.push(
+                    Color::BLACK,
+                    Circle::new(head, Distance::meters(0.6)).to_polygon(),
+                );
+                batch.push(
+                    Color::GREEN,
+                    Circle::new(head, Distance::meters(0.3)).to_polygon(),
+                );
+            }
+        }
+    }
+    batch
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -268,7 +380,9 @@ fn make_world(
         let road = app.per_map.map.get_r(r);
 
         if let Some(list) = app.edits().crossings.get(&r) {
+            let nearby = Crossing::nearby_demand(&app.per_map.map, road);
             for (idx, crossing) in list.into_iter().enumerate() {
+                let (side1, side2) = crossing.demand_per_side(road, &nearby);
                 world
                     .add(Obj::Crossing(r, idx))
                     // The circles change size based on zoom, but for interaction, just use a fixed
@@ -283,6 +397,11 @@ fn make_world(
                     .drawn_in_master_batch()
                     .hover_color(colors::HOVER)
                     .zorder(1)
+                    .tooltip(Text::from_multiline(vec![
+                        Line(format!("Estimated pedestrian demand: {}", side1 + side2)),
+                        Line(format!("({side1} from one side, {side2} from the other)"))
+                            .secondary(),
+                    ]))
                     .clickable()
                     .build(ctx);
             }
@@ -297,7 +416,7 @@ fn make_world(
             .clickable()
             .maybe_tooltip(if let Some(time) = time_to_nearest_crossing.get(&r) {
                 Some(Text::from(Line(format!(
-                    "{time} walking to the nearest crossing"
+                    "{time} to walk to and use the nearest crossing"
                 ))))
             } else {
                 None
@@ -334,7 +453,7 @@ fn draw_porosity(ctx: &EventCtx, app: &App) -> Drawable {
     ctx.upload(batch)
 }
 
-fn make_bottom_panel(ctx: &mut EventCtx, app: &App) -> Widget {
+fn make_bottom_panel(ctx: &mut EventCtx, app: &App, desire_lines_shown: bool) -> Widget {
     let icon = |ct: CrossingType, key: Key, name: &str| {
         let hide_color = Color::hex("#FDDA06");
 
@@ -385,44 +504,329 @@ fn make_bottom_panel(ctx: &mut EventCtx, app: &App) -> Widget {
                 .text_widget(ctx)
                 .centered_vert(),
         ]),
+        Widget::vertical_separator(ctx),
+        ctx.style()
+            .btn_outline
+            .text("Roads near schools missing crossings")
+            .build_widget(ctx, "roads near schools")
+            .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text("Rank crossings by demand")
+            .build_widget(ctx, "crossing demand")
+            .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text(if desire_lines_shown {
+                "Hide pedestrian desire lines"
+            } else {
+                "Show pedestrian desire lines"
+            })
+            .build_widget(ctx, "desire lines")
+            .centered_vert(),
     ])
 }
 
+/// Remember the walking trips from the travel demand model. Reuse the one impact prediction
+/// already loaded, or otherwise load it the same way.
+fn load_desire_lines(ctx: &mut EventCtx, app: &mut App) -> Transition {
+    let map_name = app.per_map.map.get_name().clone();
+    if app.per_map.impact.map == map_name {
+        if let Some(ref scenario) = app.per_map.impact.scenario {
+            app.per_map.walking_desire_lines =
+                Some(walking_desire_lines(&app.per_map.map, scenario));
+            return Transition::Recreate;
+        }
+    }
+
+    let scenario_name = Scenario::default_scenario_for_map(&map_name);
+    if scenario_name != "home_to_work" {
+        return Transition::Push(FileLoader::<App, Scenario>::new_state(
+            ctx,
+            abstio::path_scenario(&map_name, &scenario_name),
+            Box::new(|ctx, app, _, maybe_scenario| match maybe_scenario {
+                Ok(scenario) => {
+                    app.per_map.walking_desire_lines =
+                        Some(walking_desire_lines(&app.per_map.map, &scenario));
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![format!("Couldn't load the travel demand model: {}", err)],
+                )),
+            }),
+        ));
+    }
+    ctx.loading_screen("synthesize travel demand model", |_, timer| {
+        // Like impact prediction, ignore any edits when estimating mode split
+        app.per_map.map.keep_pathfinder_despite_edits();
+        let scenario = ScenarioGenerator::proletariat_robot(
+            &app.per_map.map,
+            &mut XorShiftRng::seed_from_u64(42),
+            timer,
+        );
+        app.per_map.walking_desire_lines = Some(walking_desire_lines(&app.per_map.map, &scenario));
+    });
+    Transition::Recreate
+}
+
+fn walking_desire_lines(map: &Map, scenario: &Scenario) -> Vec<(Pt2D, Pt2D)> {
+    scenario
+        .all_trips()
+        .filter(|trip| trip.mode == TripMode::Walk)
+        .map(|trip| (trip.origin.pt(map), trip.destination.pt(map)))
+        .collect()
+}
+
+/// Draw the desire lines that cross a main road, with a dot where each one crosses. Lines are
+/// faint, so busy desire paths stand out where many overlap.
+fn draw_desire_lines(ctx: &EventCtx, app: &App, lines: &[(Pt2D, Pt2D)]) -> Drawable {
+    let map = &app.per_map.map;
+    let main_roads: Vec<(PolyLine, geom::Bounds)> = main_roads(app)
+        .into_iter()
+        .map(|r| {
+            let pl = map.get_r(r).center_pts.clone();
+            let bounds = pl.get_bounds();
+            (pl, bounds)
+        })
+        .collect();
+
+    let mut batch = GeomBatch::new();
+    for (pt1, pt2) in lines {
+        let line = match geom::Line::new(*pt1, *pt2) {
+            Ok(line) => line,
+            Err(_) => continue,
+        };
+        let line_bounds = line.to_polyline().get_bounds();
+        let mut crosses = false;
+        for (road, road_bounds) in &main_roads {
+            if road_bounds.max_x < line_bounds.min_x
+                || road_bounds.min_x > line_bounds.max_x
+                || road_bounds.max_y < line_bounds.min_y
+                || road_bounds.min_y > line_bounds.max_y
+            {
+                continue;
+            }
+            // A curvy road may cross the same desire line more than once
+            for pt in road.lines().filter_map(|l| l.intersection(&line)) {
+                crosses = true;
+                batch.push(
+                    colors::DESIRE_LINE.alpha(0.3),
+                    Circle::new(pt, Distance::meters(3.0)).to_polygon(),
+                );
+            }
+        }
+        if crosses {
+            batch.push(
+                colors::DESIRE_LINE.alpha(0.1),
+                line.make_polygons(Distance::meters(2.0)),
+            );
+        }
+    }
+    ctx.upload(batch)
+}
+
+/// Main roads within this distance of a school should probably have a crossing
+const SCHOOL_RADIUS: Distance = Distance::const_meters(200.0);
+
+/// Find main roads near a school without any crossing, ranked by how much traffic they have. If
+/// the impact prediction has been calculated, use its traffic counts. Otherwise, fall back to
+/// road classification.
+fn roads_near_schools_missing_crossings(app: &App) -> Vec<(RoadID, usize)> {
+    let map = &app.per_map.map;
+    let schools: Vec<_> = map
+        .all_buildings()
+        .iter()
+        .filter(|b| {
+            b.amenities
+                .iter()
+                .any(|a| AmenityType::categorize(&a.amenity_type) == Some(AmenityType::School))
+        })
+        .map(|b| b.polygon.center())
+        .collect();
+
+    let have_counts = &app.per_map.impact.map == map.get_name();
+    let mut results = Vec::new();
+    for r in main_roads(app) {
+        if app.edits().crossings.contains_key(&r) {
+            continue;
+        }
+        let road = map.get_r(r);
+        if !schools
+            .iter()
+            .any(|pt| road.center_pts.project_pt(*pt).dist_to(*pt) <= SCHOOL_RADIUS)
+        {
+            continue;
+        }
+        let traffic = if have_counts {
+            app.per_map.impact.compare_counts.counts_b.per_road.get(r)
+        } else {
+            match road.get_rank() {
+                osm::RoadRank::Highway => 3,
+                osm::RoadRank::Arterial => 2,
+                osm::RoadRank::Local => 1,
+            }
+        };
+        results.push((r, traffic));
+    }
+    results.sort_by_key(|(_, traffic)| std::cmp::Reverse(*traffic));
+    results
+}
+
+fn school_report(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+    let results = roads_near_schools_missing_crossings(app);
+    if results.is_empty() {
+        return PopupMsg::new_state(
+            ctx,
+            "Roads near schools missing crossings",
+            vec![format!(
+                "Every main road within {} of a school has a crossing",
+                SCHOOL_RADIUS
+            )],
+        );
+    }
+
+    let have_counts = &app.per_map.impact.map == app.per_map.map.get_name();
+    let mut lines = vec![
+        format!(
+            "These main roads are within {} of a school, but have no crossing.",
+            SCHOOL_RADIUS
+        ),
+        if have_counts {
+            "They're ranked by predicted traffic after your changes.".to_string()
+        } else {
+            "They're ranked by road classification. Calculate the impact prediction to rank by traffic instead.".to_string()
+        },
+        String::new(),
+    ];
+    // Many segments may share the same name, so just list the worst few
+    for (idx, (r, traffic)) in results.iter().take(20).enumerate() {
+        let name = app
+            .per_map
+            .map
+            .get_r(*r)
+            .get_name(app.opts.language.as_ref());
+        if have_counts {
+            lines.push(format!(
+                "{}. {} ({} trips)",
+                idx + 1,
+                name,
+                abstutil::prettyprint_usize(*traffic)
+            ));
+        } else {
+            lines.push(format!("{}. {}", idx + 1, name));
+        }
+    }
+    if results.len() > 20 {
+        lines.push(format!("... and {} more", results.len() - 20));
+    }
+    PopupMsg::new_state(ctx, "Roads near schools missing crossings", lines)
+}
+
+fn demand_report(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+    let map = &app.per_map.map;
+    let mut results = Vec::new();
+    for r in main_roads(app) {
+        if let Some(list) = app.edits().crossings.get(&r) {
+            let road = map.get_r(r);
+            let nearby = Crossing::nearby_demand(map, road);
+            for crossing in list {
+                results.push((crossing.demand_score(road, &nearby), r, crossing.kind));
+            }
+        }
+    }
+    if results.is_empty() {
+        return PopupMsg::new_state(
+            ctx,
+            "Crossing demand",
+            vec!["There aren't any crossings yet"],
+        );
+    }
+    results.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+    let mut lines = vec![
+        "A rough score, from residents and amenities near each crossing.".to_string(),
+        "Each amenity counts like 10 residents.".to_string(),
+        String::new(),
+    ];
+    for (idx, (score, r, kind)) in results.iter().take(20).enumerate() {
+        lines.push(format!(
+            "{}. {} crossing on {}: {}",
+            idx + 1,
+            match kind {
+                CrossingType::Signalized => "Signalized",
+                CrossingType::Unsignalized => "Unsignalized",
+            },
+            map.get_r(*r).get_name(app.opts.language.as_ref()),
+            abstutil::prettyprint_usize(*score)
+        ));
+    }
+    if results.len() > 20 {
+        lines.push(format!("... and {} more", results.len() - 20));
+    }
+    PopupMsg::new_state(ctx, "Crossing demand", lines)
+}
+
 fn draw_nearest_crossing(ctx: &EventCtx, app: &App) -> (Drawable, BTreeMap<RoadID, Duration>) {
-    // Consider the undirected graph of main roads. Floodfill from each crossing and count the
-    // walking time to the nearest crossing, at road segment granularity.
+    // Consider the undirected graph of main roads, plus each crossing as its own node. Floodfill
+    // from every crossing and count the walking time to the nearest crossing, at road segment
+    // granularity. Going between a crossing and its road costs the time needed to actually use
+    // the crossing, so a signalized crossing costs more than a zebra.
     //
     // Note this is weird -- the nearest crossing might not be in the direction someone wants to
     // go!
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    enum Node {
+        Crossing(RoadID, usize),
+        Road(RoadID),
+    }
+
     let main_roads = main_roads(app);
 
-    let mut queue: BinaryHeap<PriorityQueueItem<Duration, RoadID>> = BinaryHeap::new();
+    let mut queue: BinaryHeap<PriorityQueueItem<Duration, Node>> = BinaryHeap::new();
 
     for r in &main_roads {
-        if app.edits().crossings.contains_key(r) {
-            queue.push(PriorityQueueItem {
-                cost: Duration::ZERO,
-                value: *r,
-            });
+        if let Some(list) = app.edits().crossings.get(r) {
+            for idx in 0..list.len() {
+                queue.push(PriorityQueueItem {
+                    cost: Duration::ZERO,
+                    value: Node::Crossing(*r, idx),
+                });
+            }
         }
     }
 
+    let mut visited: BTreeSet<Node> = BTreeSet::new();
     let mut cost_per_node: BTreeMap<RoadID, Duration> = BTreeMap::new();
     while let Some(current) = queue.pop() {
-        if cost_per_node.contains_key(&current.value) {
+        if !visited.insert(current.value) {
             continue;
         }
-        cost_per_node.insert(current.value, current.cost);
 
-        // Walk to all main roads connected at either endpoint
-        for next in app.per_map.map.get_next_roads(current.value) {
-            if main_roads.contains(&next) {
-                let cost = app.per_map.map.get_r(next).length() / map_model::MAX_WALKING_SPEED;
+        match current.value {
+            Node::Crossing(r, idx) => {
+                let kind = app.edits().crossings[&r][idx].kind;
                 queue.push(PriorityQueueItem {
-                    cost: current.cost + cost,
-                    value: next,
+                    cost: current.cost + logic::crossings::pedestrian_delay(kind),
+                    value: Node::Road(r),
                 });
             }
+            Node::Road(r) => {
+                cost_per_node.insert(r, current.cost);
+
+                // Walk to all main roads connected at either endpoint
+                for next in app.per_map.map.get_next_roads(r) {
+                    if main_roads.contains(&next) {
+                        let cost =
+                            app.per_map.map.get_r(next).length() / map_model::MAX_WALKING_SPEED;
+                        queue.push(PriorityQueueItem {
+                            cost: current.cost + cost,
+                            value: Node::Road(next),
+                        });
+                    }
+                }
+            }
         }
     }
 
@@ -30,6 +30,7 @@ impl CustomizeBoundary {
                 ctx.style().btn_solid_primary.text("Save").build_def(ctx),
             ]))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .keyboard_navigation()
             .build(ctx),
             edit: EditPolygon::new(ctx, points, false),
         })
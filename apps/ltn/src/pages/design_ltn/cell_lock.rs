@@ -0,0 +1,132 @@
+use std::collections::BTreeSet;
+
+use map_model::RoadID;
+use widgetry::tools::ChooseSomething;
+use widgetry::{Choice, EventCtx};
+
+use super::EditMode;
+use crate::filters::ChangeKey;
+use crate::{logic, App, Cell, Neighbourhood, NeighbourhoodID, Transition};
+
+/// A neighbourhood's cells, as of the last time they were checked against the lock
+pub struct CheckedCells {
+    neighbourhood: NeighbourhoodID,
+    layout: BTreeSet<BTreeSet<RoadID>>,
+    history_len: usize,
+    change_key: ChangeKey,
+}
+
+/// Call this after anything that might've edited the neighbourhood, including edits made from a
+/// popup before a `Transition::Recreate`. If the cell layout is locked and the edits since the
+/// last check changed it, returns a prompt to keep them or undo all of them. Undoing, switching
+/// proposals, or moving to another neighbourhood just updates what's remembered.
+pub fn check_cell_lock(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    neighbourhood: &Neighbourhood,
+) -> Option<Transition> {
+    let now = CheckedCells {
+        neighbourhood: neighbourhood.id,
+        layout: cell_layout(&neighbourhood.cells),
+        history_len: app.edits().history_len(),
+        change_key: app.edits().get_change_key(),
+    };
+    let before = std::mem::replace(&mut app.per_map.checked_cells, Some(now))?;
+    let now = app.per_map.checked_cells.as_ref().unwrap();
+
+    if !app.session.layers.lock_cells
+        || before.neighbourhood != now.neighbourhood
+        || before.layout == now.layout
+        || now.history_len <= before.history_len
+    {
+        return None;
+    }
+    // Only new edits made on top of the checked ones count
+    let steps = now.history_len - before.history_len;
+    if app
+        .edits()
+        .version_before(steps)
+        .map(|edits| edits.get_change_key())
+        != Some(before.change_key)
+    {
+        return None;
+    }
+
+    Some(Transition::Push(ChooseSomething::new_state(
+        ctx,
+        "The cell layout is locked, but this change alters the cells",
+        Choice::strings(vec!["Undo the change", "Keep it anyway"]),
+        Box::new(move |choice, ctx, app| {
+            if choice == "Keep it anyway" {
+                return Transition::Pop;
+            }
+            // One change may span a few steps of history, like placing a filter and then fixing
+            // one-ways around it
+            for _ in 0..steps {
+                logic::map_edits::undo_proposal(ctx, app);
+            }
+            if let EditMode::ConnectCells(ref mut picked) = app.session.edit_mode {
+                *picked = None;
+            }
+            Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+        }),
+    )))
+}
+
+/// Which roads belong to each cell, ignoring where exactly filters split roads. Moving a filter
+/// along its road doesn't change this.
+fn cell_layout(cells: &[Cell]) -> BTreeSet<BTreeSet<RoadID>> {
+    cells
+        .iter()
+        .map(|cell| cell.roads.keys().cloned().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use geom::Distance;
+
+    use super::*;
+    use crate::DistanceInterval;
+
+    fn cell(roads: &[(usize, f64, f64)]) -> Cell {
+        Cell {
+            roads: roads
+                .iter()
+                .map(|(r, start, end)| {
+                    (
+                        RoadID(*r),
+                        DistanceInterval {
+                            start: Distance::meters(*start),
+                            end: Distance::meters(*end),
+                        },
+                    )
+                })
+                .collect(),
+            borders: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_cell_layout() {
+        // Road 1 is split by a filter 40m along
+        let before = cell_layout(&[
+            cell(&[(0, 0.0, 50.0), (1, 0.0, 40.0)]),
+            cell(&[(1, 40.0, 100.0), (2, 0.0, 30.0)]),
+        ]);
+
+        // Sliding the filter along road 1 doesn't change anything
+        let moved = cell_layout(&[
+            cell(&[(1, 60.0, 100.0), (2, 0.0, 30.0)]),
+            cell(&[(0, 0.0, 50.0), (1, 0.0, 60.0)]),
+        ]);
+        assert_eq!(before, moved);
+
+        // Moving it to road 2 does
+        let other_road = cell_layout(&[
+            cell(&[(0, 0.0, 50.0), (1, 0.0, 100.0), (2, 0.0, 10.0)]),
+            cell(&[(2, 10.0, 30.0)]),
+        ]);
+        assert_ne!(before, other_road);
+    }
+}
@@ -0,0 +1,228 @@
+use abstutil::Counter;
+use map_model::{IntersectionID, RoadID};
+use widgetry::mapspace::{World, WorldOutcome};
+use widgetry::tools::ChooseSomething;
+use widgetry::{Choice, EventCtx, Text};
+
+use super::{road_name, EditMode, EditOutcome, Obj};
+use crate::render::colors;
+use crate::{mut_edits, redraw_all_filters, App, Neighbourhood, Transition};
+
+/// One object per cell, identified by any road fully inside it. `picked` is a road in the first
+/// cell the user clicked, if any.
+pub fn make_world(
+    ctx: &mut EventCtx,
+    app: &App,
+    neighbourhood: &Neighbourhood,
+    picked: Option<RoadID>,
+) -> World<Obj> {
+    let map = &app.per_map.map;
+    let mut world = World::new();
+
+    let cells_per_road = count_cells_per_road(neighbourhood);
+    let picked = picked.and_then(|r| find_cell(neighbourhood, &cells_per_road, r));
+
+    for (idx, cell) in neighbourhood.cells.iter().enumerate() {
+        let roads: Vec<RoadID> = cell
+            .roads
+            .keys()
+            .filter(|r| cells_per_road.get(**r) == 1)
+            .cloned()
+            .collect();
+        if roads.is_empty() {
+            continue;
+        }
+
+        let obj = world.add(Obj::Road(roads[0])).hitboxes(
+            roads
+                .iter()
+                .map(|r| map.get_r(*r).get_thick_polygon())
+                .collect(),
+        );
+        let obj = if picked == Some(idx) {
+            obj.draw_color(colors::PICKED_CELL)
+        } else {
+            obj.drawn_in_master_batch()
+        };
+        obj.hover_color(colors::HOVER)
+            .tooltip(Text::from(match picked {
+                None => "Click to pick the first cell",
+                Some(x) if x == idx => "Click to unpick this cell",
+                Some(_) => "Click to merge with the picked cell",
+            }))
+            .clickable()
+            .build(ctx);
+    }
+
+    world.initialize_hover(ctx);
+    world
+}
+
+pub fn handle_world_outcome(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    outcome: WorldOutcome<Obj>,
+    neighbourhood: &Neighbourhood,
+) -> EditOutcome {
+    let r = if let WorldOutcome::ClickedObject(Obj::Road(r)) = outcome {
+        r
+    } else {
+        return EditOutcome::Nothing;
+    };
+    let cells_per_road = count_cells_per_road(neighbourhood);
+    let idx = find_cell(neighbourhood, &cells_per_road, r).unwrap();
+    // The cells may have changed since the first pick, so only trust it if its road is still
+    // fully inside one cell
+    let picked = if let EditMode::ConnectCells(picked) = app.session.edit_mode {
+        picked.and_then(|r| find_cell(neighbourhood, &cells_per_road, r))
+    } else {
+        unreachable!()
+    };
+
+    match picked {
+        None => {
+            app.session.edit_mode = EditMode::ConnectCells(Some(r));
+            EditOutcome::UpdatePanelAndWorld
+        }
+        Some(first) if first == idx => {
+            app.session.edit_mode = EditMode::ConnectCells(None);
+            EditOutcome::UpdatePanelAndWorld
+        }
+        Some(first) => confirm_merge(ctx, app, neighbourhood, first, idx),
+    }
+}
+
+// Roads split by a filter belong to two cells, so clicking them would be ambiguous
+fn count_cells_per_road(neighbourhood: &Neighbourhood) -> Counter<RoadID> {
+    let mut cells_per_road = Counter::new();
+    for cell in &neighbourhood.cells {
+        for r in cell.roads.keys() {
+            cells_per_road.inc(*r);
+        }
+    }
+    cells_per_road
+}
+
+/// The index of the only cell containing `r`, if there's exactly one
+fn find_cell(
+    neighbourhood: &Neighbourhood,
+    cells_per_road: &Counter<RoadID>,
+    r: RoadID,
+) -> Option<usize> {
+    if cells_per_road.get(r) != 1 {
+        return None;
+    }
+    neighbourhood
+        .cells
+        .iter()
+        .position(|cell| cell.roads.contains_key(&r))
+}
+
+#[derive(Clone, Copy)]
+enum Separator {
+    Road(RoadID),
+    /// The index into the intersection's list of diagonal filters
+    Diagonal(IntersectionID, usize),
+}
+
+/// Find every filter sitting directly between two cells. Removing any one of them merges the
+/// cells, unless something else (like a one-way) also keeps them apart.
+fn separating_filters(
+    app: &App,
+    neighbourhood: &Neighbourhood,
+    cell1: usize,
+    cell2: usize,
+) -> Vec<(Separator, bool)> {
+    let map = &app.per_map.map;
+    let cell1 = &neighbourhood.cells[cell1];
+    let cell2 = &neighbourhood.cells[cell2];
+    let mut result = Vec::new();
+
+    // A filter splits its road between the cells on either side
+    for (r, filter) in &app.edits().roads {
+        if cell1.roads.contains_key(r) && cell2.roads.contains_key(r) {
+            result.push((Separator::Road(*r), filter.locked));
+        }
+    }
+
+    for (i, filters) in &app.edits().intersections {
+        let roads = &map.get_i(*i).roads;
+        for (idx, filter) in filters.iter().enumerate() {
+            let blocks = roads.iter().any(|r1| {
+                cell1.roads.contains_key(r1)
+                    && roads
+                        .iter()
+                        .any(|r2| cell2.roads.contains_key(r2) && !filter.allows_turn(*r1, *r2))
+            });
+            if blocks {
+                result.push((Separator::Diagonal(*i, idx), filter.locked));
+            }
+        }
+    }
+
+    result
+}
+
+fn confirm_merge(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    neighbourhood: &Neighbourhood,
+    cell1: usize,
+    cell2: usize,
+) -> EditOutcome {
+    let filters = separating_filters(app, neighbourhood, cell1, cell2);
+    let separator = match filters.iter().find(|(_, locked)| !locked) {
+        Some((separator, _)) => *separator,
+        None => {
+            return EditOutcome::error(
+                ctx,
+                if filters.is_empty() {
+                    "These cells aren't next to each other, or no single filter separates them"
+                } else {
+                    "The filter between these cells is locked. Unlock it first."
+                },
+            );
+        }
+    };
+
+    let title = match separator {
+        Separator::Road(r) => format!(
+            "Remove the filter on {} to merge these cells?",
+            road_name(app, app.per_map.map.get_r(r))
+        ),
+        Separator::Diagonal(_, _) => {
+            "Remove the diagonal filter between these cells to merge them?".to_string()
+        }
+    };
+    EditOutcome::Transition(Transition::Push(ChooseSomething::new_state(
+        ctx,
+        title,
+        vec![
+            Choice::new("Remove the filter", true),
+            Choice::new("Keep it", false),
+        ],
+        Box::new(move |remove, ctx, app| {
+            if !remove {
+                return Transition::Pop;
+            }
+            app.per_map.proposals.before_edit();
+            let edits = &mut mut_edits!(app);
+            match separator {
+                Separator::Road(r) => {
+                    edits.roads.remove(&r);
+                }
+                Separator::Diagonal(i, idx) => {
+                    let filters = edits.intersections.get_mut(&i).unwrap();
+                    filters.remove(idx);
+                    if filters.is_empty() {
+                        edits.intersections.remove(&i);
+                    }
+                }
+            }
+            redraw_all_filters(ctx, app);
+            // The cells are about to be recalculated, so the old pick means nothing
+            app.session.edit_mode = EditMode::ConnectCells(None);
+            Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+        }),
+    )))
+}
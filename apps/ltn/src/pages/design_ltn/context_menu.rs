@@ -0,0 +1,266 @@
+use geom::Speed;
+use map_model::{EditCmd, EditRoad, IntersectionID, RoadID, TurnType};
+use widgetry::tools::{ChooseSomething, PopupMsg};
+use widgetry::{Choice, EventCtx, State, Transition};
+
+use super::{filters, one_ways, road_name, speed_limits, EditOutcome};
+use crate::{
+    is_driveable, logic, mut_edits, redraw_all_filters, App, BannedTurn, Crossing,
+    DirectedSpeedLimit, Neighbourhood, NeighbourhoodID,
+};
+
+/// Right-clicking a road offers every kind of edit for it, regardless of the current edit mode.
+/// Intersections can be closed off entirely.
+pub fn event(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood) -> EditOutcome {
+    if !ctx.normal_right_click() {
+        return EditOutcome::Nothing;
+    }
+    let cursor_pt = match ctx.canvas.get_cursor_in_map_space() {
+        Some(pt) => pt,
+        None => return EditOutcome::Nothing,
+    };
+    let map = &app.per_map.map;
+    if let Some(i) = neighbourhood
+        .interior_intersections
+        .iter()
+        .find(|i| map.get_i(**i).polygon.contains_pt(cursor_pt))
+    {
+        return intersection_menu(ctx, app, neighbourhood.id, *i);
+    }
+    let r = match neighbourhood
+        .interior_roads
+        .iter()
+        .chain(neighbourhood.perimeter_roads.iter())
+        .find(|r| map.get_r(**r).get_thick_polygon().contains_pt(cursor_pt))
+    {
+        Some(r) => *r,
+        None => return EditOutcome::Nothing,
+    };
+    let road = map.get_r(r);
+    let pt_on_line = road.center_pts.project_pt(cursor_pt);
+    let (dist, _) = road.center_pts.dist_along_of_point(pt_on_line).unwrap();
+
+    let interior = neighbourhood.interior_roads.contains(&r) && is_driveable(road, map);
+    let mut choices = Vec::new();
+    if interior {
+        choices.push(Choice::string(if app.edits().roads.contains_key(&r) {
+            "Remove the filter"
+        } else {
+            "Place a filter here"
+        }));
+    }
+    choices.push(Choice::string("Add a crossing here"));
+    let calm_speed = calm_speed_limit(app);
+    let speed_label = format!(
+        "Set speed limit to {}",
+        calm_speed.to_string(&app.opts.units)
+    );
+    choices.push(Choice::string(&speed_label));
+    if interior {
+        choices.push(Choice::string(if road.oneway_for_driving().is_some() {
+            "Reverse direction"
+        } else {
+            "Make one-way"
+        }));
+    }
+    if app.edits().roads.contains_key(&r)
+        || app.edits().one_ways.contains_key(&r)
+        || app.edits().crossings.contains_key(&r)
+        || app.edits().speed_limits.contains_key(&r)
+        || app.edits().road_names.contains_key(&r)
+    {
+        choices.push(Choice::string("Revert all changes to this road"));
+    }
+
+    EditOutcome::Transition(Transition::Push(ChooseSomething::new_state(
+        ctx,
+        road_name(app, road),
+        choices,
+        Box::new(move |choice, ctx, app| {
+            let outcome = match choice.as_str() {
+                "Place a filter here" | "Remove the filter" => {
+                    filters::toggle_filter(ctx, app, r, dist)
+                }
+                "Add a crossing here" => {
+                    app.per_map.proposals.before_edit();
+                    let list = mut_edits!(app).crossings.entry(r).or_insert_with(Vec::new);
+                    list.push(Crossing {
+                        kind: app.session.crossing_type,
+                        dist,
+                        user_modified: true,
+                    });
+                    list.sort_by_key(|c| c.dist);
+                    EditOutcome::UpdateAll
+                }
+                x if x == speed_label => {
+                    speed_limits::set_speed_limit(
+                        ctx,
+                        app,
+                        r,
+                        DirectedSpeedLimit {
+                            fwd: calm_speed,
+                            back: calm_speed,
+                        },
+                    );
+                    EditOutcome::UpdateAll
+                }
+                "Reverse direction" | "Make one-way" => one_ways::toggle_direction(ctx, app, r),
+                "Revert all changes to this road" => revert_road(ctx, app, r),
+                _ => unreachable!(),
+            };
+            match outcome {
+                EditOutcome::Nothing => Transition::Pop,
+                EditOutcome::UpdatePanelAndWorld | EditOutcome::UpdateAll => {
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }
+                EditOutcome::Transition(t) => {
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate, t])
+                }
+            }
+        }),
+    )))
+}
+
+/// The usual limit for calmed streets, in round numbers for the user's units
+fn calm_speed_limit(app: &App) -> Speed {
+    if app.opts.units.metric {
+        Speed::km_per_hour(30.0)
+    } else {
+        Speed::miles_per_hour(20.0)
+    }
+}
+
+/// Right-clicking an intersection offers to close it off entirely
+fn intersection_menu(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: NeighbourhoodID,
+    i: IntersectionID,
+) -> EditOutcome {
+    let map = &app.per_map.map;
+    let mut choices = vec![
+        Choice::string("Full closure"),
+        Choice::string("Ban one turn"),
+    ];
+    if app.edits().banned_turns.contains_key(&i) {
+        choices.push(Choice::string("Allow all turns again"));
+    }
+    EditOutcome::Transition(Transition::Push(ChooseSomething::new_state(
+        ctx,
+        map.get_i(i).name(app.opts.language.as_ref(), map),
+        choices,
+        Box::new(move |choice, ctx, app| {
+            if choice == "Ban one turn" {
+                return Transition::Replace(choose_turn_to_ban(ctx, app, i));
+            }
+            if choice == "Allow all turns again" {
+                app.per_map.proposals.before_edit();
+                mut_edits!(app).banned_turns.remove(&i);
+                redraw_all_filters(ctx, app);
+                return Transition::Multi(vec![Transition::Pop, Transition::Recreate]);
+            }
+            let neighbourhood = Neighbourhood::new(app, id);
+            match filters::close_all_arms(ctx, app, &neighbourhood, i) {
+                EditOutcome::Transition(t) => {
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate, t])
+                }
+                _ => Transition::Multi(vec![Transition::Pop, Transition::Recreate]),
+            }
+        }),
+    )))
+}
+
+/// List every driving movement through the intersection that isn't banned yet
+fn choose_turn_to_ban(ctx: &mut EventCtx, app: &App, i: IntersectionID) -> Box<dyn State<App>> {
+    let map = &app.per_map.map;
+    let mut choices = Vec::new();
+    for (movement, info) in &map.get_i(i).movements {
+        let (from, to) = (movement.from.road, movement.to.road);
+        if movement.crosswalk
+            || !is_driveable(map.get_r(from), map)
+            || !is_driveable(map.get_r(to), map)
+            || app.edits().is_turn_banned(i, from, to)
+        {
+            continue;
+        }
+        let sign = match info.turn_type {
+            TurnType::Left => "no left turn",
+            TurnType::Right => "no right turn",
+            TurnType::UTurn => "no U-turn",
+            _ => "no straight ahead",
+        };
+        let label = format!(
+            "From {} to {} ({})",
+            road_name(app, map.get_r(from)),
+            road_name(app, map.get_r(to)),
+            sign
+        );
+        // Roads split into several lanes can yield the same movement twice
+        if choices
+            .iter()
+            .all(|c: &Choice<BannedTurn>| c.label != label)
+        {
+            choices.push(Choice::new(label, BannedTurn { from, to }));
+        }
+    }
+    if choices.is_empty() {
+        return PopupMsg::new_state(
+            ctx,
+            "Nothing to ban",
+            vec!["Every movement through here is already banned"],
+        );
+    }
+
+    ChooseSomething::new_state(
+        ctx,
+        "Which movement should be banned?",
+        choices,
+        Box::new(move |ban, ctx, app| {
+            app.per_map.proposals.before_edit();
+            mut_edits!(app)
+                .banned_turns
+                .entry(i)
+                .or_insert_with(Vec::new)
+                .push(ban);
+            redraw_all_filters(ctx, app);
+            Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+        }),
+    )
+}
+
+/// Undo the road's direction, speed limit, and name changes, and remove its filter and crossings.
+/// Locked filters stay.
+fn revert_road(ctx: &mut EventCtx, app: &mut App, r: RoadID) -> EditOutcome {
+    if app.edits().one_ways.contains_key(&r) {
+        let map = &app.per_map.map;
+        let mut edits = map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeRoad {
+            r,
+            old: map.get_r_edit(r),
+            new: EditRoad::get_orig_from_osm(map.get_r(r), map.get_config()),
+        });
+        // This calls before_edit
+        logic::map_edits::modify_road(ctx, app, r, edits);
+    } else {
+        app.per_map.proposals.before_edit();
+    }
+
+    let edits = &mut mut_edits!(app);
+    edits.speed_limits.remove(&r);
+    edits.crossings.remove(&r);
+    edits.road_names.remove(&r);
+    let locked = edits.roads.get(&r).map(|f| f.locked).unwrap_or(false);
+    if !locked {
+        edits.roads.remove(&r);
+    }
+    app.per_map.proposals.cancel_empty_edit();
+    redraw_all_filters(ctx, app);
+
+    if locked {
+        return EditOutcome::error(
+            ctx,
+            "The filter on this road is locked, so it wasn't removed. Press L while hovering on it to unlock it.",
+        );
+    }
+    EditOutcome::UpdateAll
+}
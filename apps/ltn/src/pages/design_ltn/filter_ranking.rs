@@ -0,0 +1,129 @@
+use geom::{Circle, Distance, Pt2D};
+use map_gui::ID;
+use widgetry::{
+    DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, State, TextExt, VerticalAlignment, Warper, Widget,
+};
+
+use crate::render::colors;
+use crate::{App, Neighbourhood, NeighbourhoodID, Transition};
+
+/// Lists the filters in a neighbourhood by how many shortcuts each one stops. Clicking a filter
+/// jumps to it.
+pub struct FilterRanking {
+    panel: Panel,
+    locations: Vec<Pt2D>,
+    warper: Option<Warper>,
+    highlight: Drawable,
+}
+
+impl FilterRanking {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Box<dyn State<App>> {
+        let map = &app.per_map.map;
+        let ranking = ctx.loading_screen("rank filters", |_, timer| {
+            let neighbourhood = Neighbourhood::new(app, id);
+            neighbourhood.rank_filters(map, app.edits(), timer)
+        });
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Which filters stop the most traffic?")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Each filter is scored by how many more shortcuts there'd be without it."
+                .text_widget(ctx),
+        ];
+        if ranking.is_empty() {
+            col.push("There are no filters in this neighbourhood yet".text_widget(ctx));
+        }
+        let mut locations = Vec::new();
+        for (idx, (id, extra_shortcuts)) in ranking.into_iter().enumerate() {
+            let (name, pt) = match id {
+                ID::Road(r) => {
+                    let road = map.get_r(r);
+                    let pt = road
+                        .center_pts
+                        .dist_along(app.edits().roads[&r].dist)
+                        .map(|(pt, _)| pt)
+                        .unwrap_or_else(|_| road.center_pts.middle());
+                    (super::road_name(app, road), pt)
+                }
+                ID::Intersection(i) => {
+                    let intersection = map.get_i(i);
+                    (
+                        format!(
+                            "diagonal filter at {}",
+                            intersection.name(app.opts.language.as_ref(), map)
+                        ),
+                        intersection.polygon.center(),
+                    )
+                }
+                _ => unreachable!(),
+            };
+            let label = match extra_shortcuts {
+                0 => format!("{}. {}: stops no shortcuts", idx + 1, name),
+                1 => format!("{}. {}: stops 1 shortcut", idx + 1, name),
+                n => format!("{}. {}: stops {} shortcuts", idx + 1, name, n),
+            };
+            col.push(
+                ctx.style()
+                    .btn_plain
+                    .text(label)
+                    .build_widget(ctx, format!("filter {}", idx)),
+            );
+            locations.push(pt);
+        }
+
+        Box::new(Self {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+                .keyboard_navigation()
+                .build(ctx),
+            locations,
+            warper: None,
+            highlight: Drawable::empty(ctx),
+        })
+    }
+}
+
+impl State<App> for FilterRanking {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        if let Some(ref warper) = self.warper {
+            if warper.event(ctx) {
+                return Transition::Keep;
+            }
+            self.warper = None;
+        }
+
+        ctx.canvas_movement();
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            if x == "close" {
+                return Transition::Pop;
+            }
+            let idx = x["filter ".len()..].parse::<usize>().unwrap();
+            let pt = self.locations[idx];
+            self.warper = Some(Warper::new(ctx, pt, Some(ctx.canvas.cam_zoom.max(4.0))));
+            self.highlight = GeomBatch::from(vec![(
+                colors::HOVER,
+                Circle::new(pt, Distance::meters(15.0)).to_polygon(),
+            )])
+            .upload(ctx);
+        }
+        if ctx.input.pressed(Key::Escape) {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.highlight);
+        self.panel.draw(g);
+    }
+}
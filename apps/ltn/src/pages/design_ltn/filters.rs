@@ -1,13 +1,21 @@
+use std::collections::BTreeSet;
+
+use geom::{Angle, Distance};
+use map_model::{IntersectionID, Map, RoadID};
 use widgetry::mapspace::{World, WorldOutcome};
-use widgetry::tools::open_browser;
-use widgetry::{lctrl, EventCtx, Key, Text, Transition};
+use widgetry::tools::{open_browser, PopupMsg, PromptInput};
+use widgetry::{lctrl, Drawable, EventCtx, GeomBatch, Key, RewriteColor, Text, Transition};
 
-use super::{modals, road_name, EditOutcome, Obj};
-use crate::render::colors;
+use super::freehand_filters::{add_road_filter, resolve_skipped_roads};
+use super::{modals, road_name, DesignLTN, EditOutcome, Obj};
+use crate::render::{self, colors};
 use crate::{
-    mut_edits, redraw_all_filters, App, DiagonalFilter, FilterType, Neighbourhood, RoadFilter,
+    mut_edits, mut_partitioning, redraw_all_filters, App, DiagonalFilter, FilterType,
+    Neighbourhood, RoadFilter,
 };
 
+const LOCKED_MSG: &str = "This filter is locked. Press L while hovering on it to unlock it.";
+
 /// Creates clickable objects for managing filters on roads and intersections. Everything is
 /// invisible; the caller is responsible for drawing things.
 pub fn make_world(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood) -> World<Obj> {
@@ -27,25 +35,65 @@ pub fn make_world(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood)
                 road_name(app, road)
             )))
             .hotkey(lctrl(Key::D), "debug")
+            .hotkey(Key::N, "rename")
+            .hotkey(Key::L, "lock")
+            .hotkey(Key::T, "trial")
+            .hotkey(Key::A, "rotate")
+            .hotkey(Key::G, "planters")
             .clickable()
             .build(ctx);
     }
 
-    for i in &neighbourhood.interior_intersections {
+    for r in &neighbourhood.perimeter_roads {
+        if neighbourhood.interior_roads.contains(r) {
+            continue;
+        }
+        let road = map.get_r(*r);
         world
-            .add(Obj::Intersection(*i))
-            .hitbox(map.get_i(*i).polygon.clone())
+            .add(Obj::PerimeterRoad(*r))
+            .hitbox(road.get_thick_polygon())
             .drawn_in_master_batch()
             .hover_color(colors::HOVER)
             .tooltip(Text::from(format!(
-                "{} possible shortcuts cross this intersection",
-                neighbourhood.shortcuts.count_per_intersection.get(*i)
+                "Press E to expand the neighbourhood to include {}",
+                road_name(app, road)
             )))
-            .clickable()
-            .hotkey(lctrl(Key::D), "debug")
+            .hotkey(Key::E, "expand")
             .build(ctx);
     }
 
+    for i in &neighbourhood.interior_intersections {
+        let polygon = map.get_i(*i).polygon.clone();
+        let obj = world
+            .add(Obj::Intersection(*i))
+            .hitbox(polygon.clone())
+            .drawn_in_master_batch();
+        // Show exactly which turns the diagonal filters here block
+        let obj = match app.edits().intersections.get(i) {
+            Some(filters) => {
+                let mut batch = GeomBatch::new();
+                batch.push(colors::HOVER, polygon);
+                for filter in filters {
+                    batch.append(render::render_blocked_movements(
+                        map,
+                        *i,
+                        filter.avoid_movements_between_roads(),
+                    ));
+                }
+                obj.draw_hovered(batch)
+            }
+            None => obj.hover_color(colors::HOVER),
+        };
+        obj.tooltip(Text::from(format!(
+            "{} possible shortcuts cross this intersection",
+            neighbourhood.shortcuts.count_per_intersection.get(*i)
+        )))
+        .clickable()
+        .hotkey(lctrl(Key::D), "debug")
+        .hotkey(Key::L, "lock")
+        .build(ctx);
+    }
+
     world.initialize_hover(ctx);
     world
 }
@@ -54,63 +102,148 @@ pub fn handle_world_outcome(
     ctx: &mut EventCtx,
     app: &mut App,
     outcome: WorldOutcome<Obj>,
+    neighbourhood: &Neighbourhood,
 ) -> EditOutcome {
     let map = &app.per_map.map;
     match outcome {
         WorldOutcome::ClickedObject(Obj::Road(r)) => {
-            let road = map.get_r(r);
-            // The world doesn't contain non-driveable roads, so no need to check for that error
-            if road.is_deadend_for_driving(&app.per_map.map) {
-                return EditOutcome::error(ctx, "You can't filter a dead-end");
-            }
-
             // Place the filter on the part of the road that was clicked
             // These calls shouldn't fail -- since we clicked a road, the cursor must be in
             // map-space. And project_pt returns a point that's guaranteed to be on the polyline.
+            let road = map.get_r(r);
             let cursor_pt = ctx.canvas.get_cursor_in_map_space().unwrap();
             let pt_on_line = road.center_pts.project_pt(cursor_pt);
             let (distance, _) = road.center_pts.dist_along_of_point(pt_on_line).unwrap();
 
-            if road.oneway_for_driving().is_some() {
-                if app.session.layers.autofix_one_ways {
-                    modals::fix_oneway_and_add_filter(ctx, app, &[(r, distance)]);
-                    return EditOutcome::UpdateAll;
-                }
-
-                return EditOutcome::Transition(Transition::Push(
-                    modals::ResolveOneWayAndFilter::new_state(ctx, vec![(r, distance)]),
-                ));
+            // If the street is split into tiny segments and one of them is already filtered,
+            // don't place a duplicate nearby
+            if let Some(other) = filtered_sibling(app, neighbourhood, r) {
+                return EditOutcome::error(
+                    ctx,
+                    &format!(
+                        "This short piece of {} is already filtered nearby. Click that filter to remove it.",
+                        road_name(app, map.get_r(other))
+                    ),
+                );
             }
-
+            toggle_filter(ctx, app, r, distance)
+        }
+        WorldOutcome::ClickedObject(Obj::Intersection(i)) => {
             app.per_map.proposals.before_edit();
-            if mut_edits!(app).roads.remove(&r).is_none() {
-                let mut filter_type = app.session.filter_type;
-
-                if filter_type != FilterType::BusGate
-                    && !app.per_map.map.get_bus_routes_on_road(r).is_empty()
-                {
-                    if app.session.layers.autofix_bus_gates {
-                        filter_type = FilterType::BusGate;
-                    } else {
-                        // If we have a one-way bus route, the one-way resolver will win and we
-                        // won't warn about bus gates. Oh well.
-                        app.per_map.proposals.cancel_empty_edit();
-                        return EditOutcome::Transition(Transition::Push(
-                            modals::ResolveBusGate::new_state(ctx, app, vec![(r, distance)]),
-                        ));
-                    }
+            let changed = if ctx.is_key_down(Key::LeftShift) {
+                DiagonalFilter::add_another_alternative(app, i)
+            } else {
+                DiagonalFilter::cycle_through_alternatives(app, i)
+            };
+            if !changed {
+                app.per_map.proposals.cancel_empty_edit();
+                return EditOutcome::error(ctx, LOCKED_MSG);
+            }
+            redraw_all_filters(ctx, app);
+            EditOutcome::UpdateAll
+        }
+        WorldOutcome::Keypress("lock", Obj::Road(r)) => {
+            if !app.edits().roads.contains_key(&r) {
+                return EditOutcome::error(ctx, "There's no filter on this road to lock");
+            }
+            app.per_map.proposals.before_edit();
+            let filter = mut_edits!(app).roads.get_mut(&r).unwrap();
+            filter.locked = !filter.locked;
+            redraw_all_filters(ctx, app);
+            EditOutcome::UpdateAll
+        }
+        WorldOutcome::Keypress("planters", Obj::Road(r)) => {
+            match app.edits().roads.get(&r) {
+                Some(filter) if filter.filter_type == FilterType::WalkCycleOnly => {}
+                Some(_) => {
+                    return EditOutcome::error(
+                        ctx,
+                        "Only walking/cycling filters can be built from planters",
+                    );
+                }
+                None => {
+                    return EditOutcome::error(ctx, "There's no filter on this road to plant");
                 }
-
-                mut_edits!(app)
-                    .roads
-                    .insert(r, RoadFilter::new_by_user(distance, filter_type));
             }
+            app.per_map.proposals.before_edit();
+            let filter = mut_edits!(app).roads.get_mut(&r).unwrap();
+            filter.planters = !filter.planters;
+            // Building planters is new work, even on a filter that already existed
+            filter.user_modified = true;
             redraw_all_filters(ctx, app);
             EditOutcome::UpdateAll
         }
-        WorldOutcome::ClickedObject(Obj::Intersection(i)) => {
+        WorldOutcome::Keypress("trial", Obj::Road(r)) => {
+            let current = match app.edits().roads.get(&r) {
+                Some(filter) => filter.trial_until.clone().unwrap_or_default(),
+                None => {
+                    return EditOutcome::error(ctx, "There's no filter on this road to mark");
+                }
+            };
+            EditOutcome::Transition(Transition::Push(PromptInput::new_state(
+                ctx,
+                "If this filter is a trial, when does it end? (Like \"6 months\". Leave blank if it's permanent.)",
+                current,
+                Box::new(move |input, ctx, app| {
+                    app.per_map.proposals.before_edit();
+                    let input = input.trim().to_string();
+                    let filter = mut_edits!(app).roads.get_mut(&r).unwrap();
+                    filter.trial_until = if input.is_empty() { None } else { Some(input) };
+                    redraw_all_filters(ctx, app);
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }),
+            )))
+        }
+        WorldOutcome::Keypress("rotate", Obj::Road(r)) => {
+            let current = match app.edits().roads.get(&r) {
+                Some(filter) => filter
+                    .angle_override
+                    .map(|angle| angle.normalized_degrees().round().to_string())
+                    .unwrap_or_default(),
+                None => {
+                    return EditOutcome::error(ctx, "There's no filter on this road to rotate");
+                }
+            };
+            EditOutcome::Transition(Transition::Push(PromptInput::new_state(
+                ctx,
+                "Rotate the filter icon by how many degrees? (Leave blank for the default.)",
+                current,
+                Box::new(move |input, ctx, app| {
+                    let input = input.trim();
+                    let angle_override = if input.is_empty() {
+                        None
+                    } else if let Ok(degrees) = input.parse::<f64>() {
+                        Some(Angle::degrees(degrees))
+                    } else {
+                        return Transition::Replace(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![format!("{} isn't a number of degrees", input)],
+                        ));
+                    };
+                    app.per_map.proposals.before_edit();
+                    mut_edits!(app).roads.get_mut(&r).unwrap().angle_override = angle_override;
+                    redraw_all_filters(ctx, app);
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }),
+            )))
+        }
+        WorldOutcome::Keypress("lock", Obj::Intersection(i)) => {
+            if !app.edits().intersections.contains_key(&i) {
+                return EditOutcome::error(ctx, "There's no diagonal filter here to lock");
+            }
+            // If some are locked and some aren't, lock everything
+            let lock = !app
+                .edits()
+                .intersections
+                .get(&i)
+                .unwrap()
+                .iter()
+                .all(|f| f.locked);
             app.per_map.proposals.before_edit();
-            DiagonalFilter::cycle_through_alternatives(app, i);
+            for filter in mut_edits!(app).intersections.get_mut(&i).unwrap() {
+                filter.locked = lock;
+            }
             redraw_all_filters(ctx, app);
             EditOutcome::UpdateAll
         }
@@ -118,6 +251,28 @@ pub fn handle_world_outcome(
             open_browser(app.per_map.map.get_i(i).orig_id.to_string());
             EditOutcome::Nothing
         }
+        WorldOutcome::Keypress("rename", Obj::Road(r)) => {
+            let current = app.edits().road_names.get(&r).cloned().unwrap_or_default();
+            EditOutcome::Transition(Transition::Push(PromptInput::new_state(
+                ctx,
+                "Rename this road (leave blank to use the original name)",
+                current,
+                Box::new(move |name, ctx, app| {
+                    app.per_map.proposals.before_edit();
+                    let name = name.trim().to_string();
+                    if name.is_empty() {
+                        mut_edits!(app).road_names.remove(&r);
+                    } else {
+                        mut_edits!(app).road_names.insert(r, name);
+                    }
+                    crate::relabel_roads(ctx, app);
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }),
+            )))
+        }
+        WorldOutcome::Keypress("expand", Obj::PerimeterRoad(r)) => {
+            expand_across_road(ctx, app, neighbourhood, r)
+        }
         WorldOutcome::Keypress("debug", Obj::Road(r)) => {
             open_browser(app.per_map.map.get_r(r).orig_id.osm_way_id.to_string());
             EditOutcome::Nothing
@@ -125,3 +280,312 @@ pub fn handle_world_outcome(
         _ => EditOutcome::Nothing,
     }
 }
+
+/// Find all interior roads belonging to the same OSM way as `start` that're connected to it
+/// without branching at real junctions, where every segment, including `start`, is shorter than
+/// `threshold`. Together these form one logical street from the user's point of view. If `start`
+/// isn't short, it's a street by itself.
+fn logical_street(
+    map: &Map,
+    neighbourhood: &Neighbourhood,
+    start: RoadID,
+    threshold: Distance,
+) -> BTreeSet<RoadID> {
+    let way = map.get_r(start).orig_id.osm_way_id;
+    connected_short_segments(
+        start,
+        threshold,
+        |r| map.get_r(r).length(),
+        |r| {
+            let mut next = Vec::new();
+            for i in map.get_r(r).endpoints() {
+                let intersection = map.get_i(i);
+                if intersection.roads.len() != 2 {
+                    continue;
+                }
+                next.extend(intersection.roads.iter().cloned().filter(|other| {
+                    *other != r
+                        && neighbourhood.interior_roads.contains(other)
+                        && map.get_r(*other).orig_id.osm_way_id == way
+                }));
+            }
+            next
+        },
+    )
+}
+
+/// The search behind `logical_street`, independent of the map. `continuations` lists the segments
+/// continuing the same street from either end of a road.
+fn connected_short_segments<L: Fn(RoadID) -> Distance, C: Fn(RoadID) -> Vec<RoadID>>(
+    start: RoadID,
+    threshold: Distance,
+    length: L,
+    continuations: C,
+) -> BTreeSet<RoadID> {
+    let mut visited = BTreeSet::new();
+    visited.insert(start);
+    if length(start) >= threshold {
+        return visited;
+    }
+    let mut queue = vec![start];
+    while let Some(r) = queue.pop() {
+        for next in continuations(r) {
+            if !visited.contains(&next) && length(next) < threshold {
+                visited.insert(next);
+                queue.push(next);
+            }
+        }
+    }
+    visited
+}
+
+/// If `r` isn't filtered, but another segment of the same logical street is, returns that segment
+fn filtered_sibling(app: &App, neighbourhood: &Neighbourhood, r: RoadID) -> Option<RoadID> {
+    if app.edits().roads.contains_key(&r) {
+        return None;
+    }
+    let threshold = Distance::meters(app.session.layers.merge_short_segments_below);
+    logical_street(&app.per_map.map, neighbourhood, r, threshold)
+        .into_iter()
+        .find(|other| app.edits().roads.contains_key(other))
+}
+
+/// Add a filter at some position along a road, or remove the existing one. This handles one-ways
+/// and bus routes, asking the user how to resolve problems.
+pub fn toggle_filter(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    r: RoadID,
+    distance: Distance,
+) -> EditOutcome {
+    let road = app.per_map.map.get_r(r);
+    if app.edits().roads.get(&r).map(|f| f.locked).unwrap_or(false) {
+        return EditOutcome::error(ctx, LOCKED_MSG);
+    }
+    // Callers only pass driveable roads, so no need to check for that error
+    if road.is_deadend_for_driving(&app.per_map.map) {
+        return EditOutcome::error(ctx, "You can't filter a dead-end");
+    }
+
+    if road.oneway_for_driving().is_some() {
+        if app.session.layers.autofix_one_ways {
+            modals::fix_oneway_and_add_filter(ctx, app, &[(r, distance)]);
+            return EditOutcome::UpdateAll;
+        }
+
+        return EditOutcome::Transition(Transition::Push(
+            modals::ResolveOneWayAndFilter::new_state(ctx, vec![(r, distance)]),
+        ));
+    }
+
+    app.per_map.proposals.before_edit();
+    if mut_edits!(app).roads.remove(&r).is_none() {
+        let mut filter_type = app.session.filter_type;
+
+        if filter_type != FilterType::BusGate
+            && !app.per_map.map.get_bus_routes_on_road(r).is_empty()
+        {
+            if app.session.layers.autofix_bus_gates {
+                filter_type = FilterType::BusGate;
+            } else {
+                // If we have a one-way bus route, the one-way resolver will win and we
+                // won't warn about bus gates. Oh well.
+                app.per_map.proposals.cancel_empty_edit();
+                return EditOutcome::Transition(Transition::Push(
+                    modals::ResolveBusGate::new_state(ctx, app, vec![(r, distance)]),
+                ));
+            }
+        }
+
+        mut_edits!(app).roads.insert(
+            r,
+            RoadFilter::new_by_user(distance, filter_type)
+                .enforced_by(app.session.bus_gate_enforcement),
+        );
+
+        if let Some(warning) = filter_type
+            .placement_warning(app.per_map.map.get_r(r), app.session.bus_gate_enforcement)
+        {
+            redraw_all_filters(ctx, app);
+            // Recreate to pick up the new filter, since returning a transition skips the usual
+            // update
+            return EditOutcome::Transition(Transition::Multi(vec![
+                Transition::Recreate,
+                Transition::Push(PopupMsg::new_state(ctx, "Warning", vec![warning])),
+            ]));
+        }
+    }
+    redraw_all_filters(ctx, app);
+    EditOutcome::UpdateAll
+}
+
+/// Filter every interior road meeting at an intersection, right next to it, closing the junction
+/// to through-traffic like a plaza. Dead-ends and arms already filtered are skipped. One-ways and
+/// bus routes are handled like the freehand tool does, fixed automatically or asked about, and it
+/// all becomes one undo step.
+pub fn close_all_arms(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    neighbourhood: &Neighbourhood,
+    i: IntersectionID,
+) -> EditOutcome {
+    let map = &app.per_map.map;
+    let mut arms = Vec::new();
+    for r in &map.get_i(i).roads {
+        let road = map.get_r(*r);
+        if !neighbourhood.interior_roads.contains(r)
+            || app.edits().roads.contains_key(r)
+            || road.is_deadend_for_driving(map)
+        {
+            continue;
+        }
+        let dist = if road.src_i == i {
+            0.1 * road.length()
+        } else {
+            0.9 * road.length()
+        };
+        arms.push((*r, dist));
+    }
+    if arms.is_empty() {
+        return EditOutcome::error(ctx, "None of the roads here can take a new filter");
+    }
+
+    let mut oneways = Vec::new();
+    let mut bus_roads = Vec::new();
+    app.per_map.proposals.start_edit_group();
+    for (r, dist) in arms {
+        add_road_filter(ctx, app, r, dist, &mut oneways, &mut bus_roads);
+    }
+    // This finishes the edit group
+    resolve_skipped_roads(ctx, app, oneways, bus_roads)
+}
+
+/// Grow the neighbourhood by the block on the other side of a perimeter road, then recalculate
+/// everything
+fn expand_across_road(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    neighbourhood: &Neighbourhood,
+    r: RoadID,
+) -> EditOutcome {
+    let id = neighbourhood.id;
+    let block = match app.partitioning().block_across_road(id, r) {
+        Some(block) => block,
+        None => {
+            return EditOutcome::error(
+                ctx,
+                "There's nothing on the other side of this road to add",
+            );
+        }
+    };
+    let mut add_all = vec![block];
+    if app.session.add_intermediate_blocks {
+        add_all.extend(app.partitioning().find_intermediate_blocks(id, block));
+    }
+    // Ignore the return value if another neighbourhood is deleted
+    if let Err(err) = mut_partitioning!(app).transfer_blocks(&app.per_map.map, add_all, id) {
+        return EditOutcome::error(ctx, &err.to_string());
+    }
+    crate::redraw_filter_summary(ctx, app);
+
+    let num_cells_before = neighbourhood.cells.len();
+    let num_cells_after = Neighbourhood::new(app, id).cells.len();
+    let mut transitions = vec![Transition::Replace(DesignLTN::new_state(ctx, app, id))];
+    if num_cells_after < num_cells_before {
+        transitions.push(Transition::Push(PopupMsg::new_state(
+            ctx,
+            "Cells merged",
+            vec![format!(
+                "{} is now part of this neighbourhood, joining some cells together. There are {} cells now, instead of {}.",
+                road_name(app, app.per_map.map.get_r(r)),
+                num_cells_after,
+                num_cells_before
+            )],
+        )));
+    }
+    EditOutcome::Transition(Transition::Multi(transitions))
+}
+
+/// Preview where a new filter would land on the hovered road, before the user clicks
+pub fn draw_ghost_filter(
+    ctx: &EventCtx,
+    app: &App,
+    neighbourhood: &Neighbourhood,
+    hovering: Option<Obj>,
+) -> Drawable {
+    let r = match hovering {
+        Some(Obj::Road(r))
+            if !app.edits().roads.contains_key(&r)
+                && filtered_sibling(app, neighbourhood, r).is_none() =>
+        {
+            r
+        }
+        _ => return Drawable::empty(ctx),
+    };
+    let cursor_pt = match ctx.canvas.get_cursor_in_map_space() {
+        Some(pt) => pt,
+        None => return Drawable::empty(ctx),
+    };
+    let road = app.per_map.map.get_r(r);
+    let pt_on_line = road.center_pts.project_pt(cursor_pt);
+    let distance = match road.center_pts.dist_along_of_point(pt_on_line) {
+        Some((distance, _)) => distance,
+        None => return Drawable::empty(ctx),
+    };
+    RoadFilter::new_by_user(distance, app.session.filter_type)
+        .enforced_by(app.session.bus_gate_enforcement)
+        .draw_icon(
+            ctx,
+            road,
+            app.session.layers.filter_icon_scale,
+            app.session.layers.filter_style,
+        )
+        .color(RewriteColor::ChangeAlpha(0.4))
+        .upload(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connected_short_segments() {
+        // A street split into segments 0-1-2-3-4, with 2 much longer than the rest. Segment 5
+        // continues from 4, but belongs to another street, so it's never a continuation.
+        let lengths = [10.0, 10.0, 100.0, 10.0, 10.0, 5.0];
+        let length = |r: RoadID| Distance::meters(lengths[r.0]);
+        let continuations = |r: RoadID| match r.0 {
+            0 => vec![RoadID(1)],
+            4 => vec![RoadID(3)],
+            5 => Vec::new(),
+            i => vec![RoadID(i - 1), RoadID(i + 1)],
+        };
+        let ids = |list: &[usize]| list.iter().map(|r| RoadID(*r)).collect::<BTreeSet<_>>();
+        let threshold = Distance::meters(20.0);
+
+        // The long segment stops the search in both directions
+        assert_eq!(
+            connected_short_segments(RoadID(0), threshold, length, continuations),
+            ids(&[0, 1])
+        );
+        assert_eq!(
+            connected_short_segments(RoadID(4), threshold, length, continuations),
+            ids(&[3, 4])
+        );
+        // And is a street by itself
+        assert_eq!(
+            connected_short_segments(RoadID(2), threshold, length, continuations),
+            ids(&[2])
+        );
+        assert_eq!(
+            connected_short_segments(RoadID(5), threshold, length, continuations),
+            ids(&[5])
+        );
+
+        // With a higher threshold, everything on the street merges
+        assert_eq!(
+            connected_short_segments(RoadID(0), Distance::meters(200.0), length, continuations),
+            ids(&[0, 1, 2, 3, 4])
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use geom::PolyLine;
+use geom::{Distance, PolyLine};
+use map_model::RoadID;
 use widgetry::EventCtx;
 
 use super::{modals, EditMode, EditOutcome};
@@ -31,7 +32,8 @@ fn make_filters_along_path(
     let mut oneways = Vec::new();
     let mut bus_roads = Vec::new();
 
-    app.per_map.proposals.before_edit();
+    // Fixing one-ways along the way makes its own edits, but it should all be undone together
+    app.per_map.proposals.start_edit_group();
     for r in &neighbourhood.interior_roads {
         if app.edits().roads.contains_key(r) {
             continue;
@@ -48,30 +50,7 @@ fn make_filters_along_path(
                 .map(|pair| pair.0)
                 .unwrap_or(road.center_pts.length() / 2.0);
 
-            if road.oneway_for_driving().is_some() {
-                if app.session.layers.autofix_one_ways {
-                    modals::fix_oneway_and_add_filter(ctx, app, &[(*r, dist)]);
-                } else {
-                    oneways.push((*r, dist));
-                }
-                continue;
-            }
-
-            let mut filter_type = app.session.filter_type;
-            if filter_type != FilterType::BusGate
-                && !app.per_map.map.get_bus_routes_on_road(*r).is_empty()
-            {
-                if app.session.layers.autofix_bus_gates {
-                    filter_type = FilterType::BusGate;
-                } else {
-                    bus_roads.push((*r, dist));
-                    continue;
-                }
-            }
-
-            mut_edits!(app)
-                .roads
-                .insert(*r, RoadFilter::new_by_user(dist, filter_type));
+            add_road_filter(ctx, app, *r, dist, &mut oneways, &mut bus_roads);
         }
     }
     for i in &neighbourhood.interior_intersections {
@@ -80,17 +59,67 @@ fn make_filters_along_path(
             DiagonalFilter::cycle_through_alternatives(app, *i);
         }
     }
-    redraw_all_filters(ctx, app);
+    // This finishes the edit group
+    resolve_skipped_roads(ctx, app, oneways, bus_roads)
+}
+
+/// Filter a road with the current filter type, as part of an edit already in progress. One-way
+/// roads and bus routes need the user to decide what to do, so unless the layer settings say to
+/// fix those automatically, they're skipped and remembered in `oneways` and `bus_roads`.
+pub fn add_road_filter(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    r: RoadID,
+    dist: Distance,
+    oneways: &mut Vec<(RoadID, Distance)>,
+    bus_roads: &mut Vec<(RoadID, Distance)>,
+) {
+    let road = app.per_map.map.get_r(r);
+    if road.oneway_for_driving().is_some() {
+        if app.session.layers.autofix_one_ways {
+            modals::fix_oneway_and_add_filter(ctx, app, &[(r, dist)]);
+        } else {
+            oneways.push((r, dist));
+        }
+        return;
+    }
 
+    let mut filter_type = app.session.filter_type;
+    if filter_type != FilterType::BusGate && !app.per_map.map.get_bus_routes_on_road(r).is_empty() {
+        if app.session.layers.autofix_bus_gates {
+            filter_type = FilterType::BusGate;
+        } else {
+            bus_roads.push((r, dist));
+            return;
+        }
+    }
+
+    mut_edits!(app).roads.insert(
+        r,
+        RoadFilter::new_by_user(dist, filter_type).enforced_by(app.session.bus_gate_enforcement),
+    );
+}
+
+/// Finishes the edit group that `add_road_filter` was called in, first asking the user about any
+/// roads that were skipped. Their answer becomes part of the same group, so undoing reverts
+/// everything, including fixed one-ways.
+pub fn resolve_skipped_roads(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    oneways: Vec<(RoadID, Distance)>,
+    bus_roads: Vec<(RoadID, Distance)>,
+) -> EditOutcome {
+    redraw_all_filters(ctx, app);
     if !oneways.is_empty() {
-        EditOutcome::Transition(Transition::Push(modals::ResolveOneWayAndFilter::new_state(
-            ctx, oneways,
-        )))
+        EditOutcome::Transition(Transition::Push(
+            modals::ResolveOneWayAndFilter::finishing_edit_group(ctx, oneways, bus_roads),
+        ))
     } else if !bus_roads.is_empty() {
-        EditOutcome::Transition(Transition::Push(modals::ResolveBusGate::new_state(
-            ctx, app, bus_roads,
-        )))
+        EditOutcome::Transition(Transition::Push(
+            modals::ResolveBusGate::finishing_edit_group(ctx, app, bus_roads),
+        ))
     } else {
+        app.per_map.proposals.finish_edit_group();
         EditOutcome::UpdateAll
     }
 }
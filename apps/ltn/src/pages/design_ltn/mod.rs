@@ -1,29 +1,56 @@
+mod cell_lock;
+mod connect_cells;
+mod context_menu;
+mod filter_ranking;
 mod filters;
 mod freehand_filters;
 mod modals;
 mod one_ways;
 mod page;
+mod reachability;
+mod select_filters;
 mod shortcuts;
+mod sketch_cell;
 mod speed_limits;
 
+use geom::Distance;
 use map_model::{IntersectionID, Road, RoadID};
 use widgetry::mapspace::{ObjectID, World};
-use widgetry::tools::{PolyLineLasso, PopupMsg};
+use widgetry::tools::{Lasso, PolyLineLasso, PopupMsg};
 use widgetry::{EventCtx, Panel};
 
-use crate::{is_private, logic, pages, App, Neighbourhood, Transition};
+use crate::{is_private, logic, pages, App, FilterType, Neighbourhood, Transition};
 
-pub use page::DesignLTN;
+pub use cell_lock::{check_cell_lock, CheckedCells};
+pub use page::{fit_neighbourhood, DesignLTN};
 
 pub enum EditMode {
     Filters,
     FreehandFilters(PolyLineLasso),
+    /// Select existing filters to change their type all at once
+    SelectFilters(Lasso),
+    /// Draw around streets that should form one cell, and place filters to make it so
+    SketchCell(Lasso),
+    /// Click two cells to remove the filter between them. Holds a road inside the first cell
+    /// picked, since cell indices change whenever the neighbourhood is rebuilt.
+    ConnectCells(Option<RoadID>),
     Oneways,
     // Is a road clicked on right now?
     Shortcuts(Option<shortcuts::FocusedRoad>),
     SpeedLimits,
 }
 
+/// What the neighbourhood is colored by while designing it
+#[derive(Clone, Copy, PartialEq)]
+pub enum ViewMode {
+    /// Each cell gets its own color
+    Cells,
+    /// Only the heatmap of shortcuts through the neighbourhood
+    Shortcuts,
+    /// Cells as a bus driver sees them, passing through bus gates
+    BusCells,
+}
+
 pub struct EditNeighbourhood {
     // Only pub for drawing
     pub world: World<Obj>,
@@ -33,6 +60,8 @@ pub struct EditNeighbourhood {
 pub enum Obj {
     Road(RoadID),
     Intersection(IntersectionID),
+    /// A road on the neighbourhood's boundary
+    PerimeterRoad(RoadID),
 }
 impl ObjectID for Obj {}
 
@@ -66,7 +95,12 @@ impl EditNeighbourhood {
         Self {
             world: match &app.session.edit_mode {
                 EditMode::Filters => filters::make_world(ctx, app, neighbourhood),
-                EditMode::FreehandFilters(_) => World::new(),
+                EditMode::FreehandFilters(_)
+                | EditMode::SelectFilters(_)
+                | EditMode::SketchCell(_) => World::new(),
+                EditMode::ConnectCells(picked) => {
+                    connect_cells::make_world(ctx, app, neighbourhood, *picked)
+                }
                 EditMode::Oneways => one_ways::make_world(ctx, app, neighbourhood),
                 EditMode::Shortcuts(focus) => shortcuts::make_world(ctx, app, neighbourhood, focus),
                 EditMode::SpeedLimits => speed_limits::make_world(ctx, app, neighbourhood),
@@ -83,11 +117,26 @@ impl EditNeighbourhood {
         if let EditMode::FreehandFilters(_) = app.session.edit_mode {
             return freehand_filters::event(ctx, app, neighbourhood);
         }
+        if let EditMode::SelectFilters(_) = app.session.edit_mode {
+            return select_filters::event(ctx, app);
+        }
+        if let EditMode::SketchCell(_) = app.session.edit_mode {
+            return sketch_cell::event(ctx, app, neighbourhood);
+        }
+        if let EditOutcome::Transition(t) = context_menu::event(ctx, app, neighbourhood) {
+            self.world.hack_unset_hovering();
+            return EditOutcome::Transition(t);
+        }
 
         let outcome = self.world.event(ctx);
         let outcome = match app.session.edit_mode {
-            EditMode::Filters => filters::handle_world_outcome(ctx, app, outcome),
-            EditMode::FreehandFilters(_) => unreachable!(),
+            EditMode::Filters => filters::handle_world_outcome(ctx, app, outcome, neighbourhood),
+            EditMode::FreehandFilters(_) | EditMode::SelectFilters(_) | EditMode::SketchCell(_) => {
+                unreachable!()
+            }
+            EditMode::ConnectCells(_) => {
+                connect_cells::handle_world_outcome(ctx, app, outcome, neighbourhood)
+            }
             EditMode::Oneways => one_ways::handle_world_outcome(ctx, app, outcome),
             EditMode::Shortcuts(_) => shortcuts::handle_world_outcome(app, outcome, neighbourhood),
             EditMode::SpeedLimits => speed_limits::handle_world_outcome(ctx, app, outcome),
@@ -130,9 +179,15 @@ impl EditNeighbourhood {
                 if let EditMode::Shortcuts(ref mut maybe_focus) = app.session.edit_mode {
                     *maybe_focus = None;
                 }
-                if let EditMode::FreehandFilters(_) = app.session.edit_mode {
+                if let EditMode::FreehandFilters(_)
+                | EditMode::SelectFilters(_)
+                | EditMode::SketchCell(_) = app.session.edit_mode
+                {
                     app.session.edit_mode = EditMode::Filters;
                 }
+                if let EditMode::ConnectCells(ref mut picked) = app.session.edit_mode {
+                    *picked = None;
+                }
                 EditOutcome::UpdateAll
             }
             "Modal filter - no entry"
@@ -149,6 +204,18 @@ impl EditNeighbourhood {
                 app.session.edit_mode = EditMode::FreehandFilters(PolyLineLasso::new());
                 EditOutcome::UpdatePanelAndWorld
             }
+            "Select filters" => {
+                app.session.edit_mode = EditMode::SelectFilters(Lasso::new(Distance::meters(1.0)));
+                EditOutcome::UpdatePanelAndWorld
+            }
+            "Sketch a cell" => {
+                app.session.edit_mode = EditMode::SketchCell(Lasso::new(Distance::meters(1.0)));
+                EditOutcome::UpdatePanelAndWorld
+            }
+            "Connect cells" => {
+                app.session.edit_mode = EditMode::ConnectCells(None);
+                EditOutcome::UpdatePanelAndWorld
+            }
             "One-ways" => {
                 app.session.edit_mode = EditMode::Oneways;
                 EditOutcome::UpdatePanelAndWorld
@@ -184,13 +251,29 @@ impl EditNeighbourhood {
                 app.session.edit_mode = EditMode::SpeedLimits;
                 EditOutcome::UpdatePanelAndWorld
             }
-            _ => EditOutcome::Nothing,
+            x => {
+                if let Some(ft) = FilterType::ALL
+                    .into_iter()
+                    .find(|ft| x == recent_filter_action(*ft))
+                {
+                    app.session.pick_filter_type(ft);
+                    app.session.save_settings();
+                    app.session.edit_mode = EditMode::Filters;
+                    return EditOutcome::UpdatePanelAndWorld;
+                }
+                EditOutcome::Nothing
+            }
         }
     }
 }
 
+/// The panel action for switching back to a recently used filter type
+fn recent_filter_action(filter_type: FilterType) -> String {
+    format!("switch to {}", filter_type.describe())
+}
+
 fn road_name(app: &App, road: &Road) -> String {
-    let mut name = road.get_name(app.opts.language.as_ref());
+    let mut name = app.edits().get_road_name(road, app.opts.language.as_ref());
     if name == "???" {
         name = "unnamed road".to_string();
     }
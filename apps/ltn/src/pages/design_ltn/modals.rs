@@ -9,15 +9,40 @@ use widgetry::{
     RewriteColor, State, Text, Texture, Toggle, Widget,
 };
 
-use crate::{mut_edits, redraw_all_filters, App, FilterType, RoadFilter, Transition};
+use crate::{
+    logic, mut_edits, redraw_all_filters, App, Enforcement, FilterType, RoadFilter, Transition,
+};
 
 pub struct ResolveOneWayAndFilter {
     panel: Panel,
     roads: Vec<(RoadID, Distance)>,
+    /// Roads on bus routes to ask about afterwards
+    bus_roads: Vec<(RoadID, Distance)>,
+    /// Is there an edit group from `start_edit_group` waiting for this answer?
+    finish_edit_group: bool,
 }
 
 impl ResolveOneWayAndFilter {
     pub fn new_state(ctx: &mut EventCtx, roads: Vec<(RoadID, Distance)>) -> Box<dyn State<App>> {
+        Self::new(ctx, roads, Vec::new(), false)
+    }
+
+    /// Like `new_state`, but finishes the edit group in progress after the filters are placed.
+    /// Then asks about `bus_roads`, if there are any.
+    pub fn finishing_edit_group(
+        ctx: &mut EventCtx,
+        roads: Vec<(RoadID, Distance)>,
+        bus_roads: Vec<(RoadID, Distance)>,
+    ) -> Box<dyn State<App>> {
+        Self::new(ctx, roads, bus_roads, true)
+    }
+
+    fn new(
+        ctx: &mut EventCtx,
+        roads: Vec<(RoadID, Distance)>,
+        bus_roads: Vec<(RoadID, Distance)>,
+        finish_edit_group: bool,
+    ) -> Box<dyn State<App>> {
         let mut txt = Text::new();
         txt.add_line(Line("Warning").small_heading());
         txt.add_line("A modal filter cannot be placed on a one-way street.");
@@ -29,9 +54,15 @@ impl ResolveOneWayAndFilter {
             Toggle::checkbox(ctx, "Don't show this warning again", None, true),
             ctx.style().btn_solid_primary.text("OK").build_def(ctx),
         ]))
+        .keyboard_navigation()
         .build(ctx);
 
-        Box::new(Self { panel, roads })
+        Box::new(Self {
+            panel,
+            roads,
+            bus_roads,
+            finish_edit_group,
+        })
     }
 }
 
@@ -44,6 +75,16 @@ impl State<App> for ResolveOneWayAndFilter {
 
             fix_oneway_and_add_filter(ctx, app, &self.roads);
 
+            if self.finish_edit_group {
+                if !self.bus_roads.is_empty() {
+                    return Transition::Replace(ResolveBusGate::finishing_edit_group(
+                        ctx,
+                        app,
+                        std::mem::take(&mut self.bus_roads),
+                    ));
+                }
+                app.per_map.proposals.finish_edit_group();
+            }
             return Transition::Multi(vec![Transition::Pop, Transition::Recreate]);
         }
         Transition::Keep
@@ -66,11 +107,11 @@ pub fn fix_oneway_and_add_filter(ctx: &mut EventCtx, app: &mut App, roads: &[(Ro
         edits
             .commands
             .push(app.per_map.map.edit_road_cmd(*r, |new| {
-                LaneSpec::toggle_road_direction(&mut new.lanes_ltr, driving_side);
+                logic::map_edits::toggle_traffic_direction(&mut new.lanes_ltr, driving_side);
                 // Maybe we just flipped a one-way forwards to a one-way backwards. So one more
                 // time to make it two-way
                 if LaneSpec::oneway_for_driving(&new.lanes_ltr) == Some(Direction::Back) {
-                    LaneSpec::toggle_road_direction(&mut new.lanes_ltr, driving_side);
+                    logic::map_edits::toggle_traffic_direction(&mut new.lanes_ltr, driving_side);
                 }
             }));
     }
@@ -90,9 +131,11 @@ pub fn fix_oneway_and_add_filter(ctx: &mut EventCtx, app: &mut App, roads: &[(Ro
             mut_edits!(app).one_ways.insert(r, r_edit);
         }
 
-        mut_edits!(app)
-            .roads
-            .insert(r, RoadFilter::new_by_user(*dist, app.session.filter_type));
+        mut_edits!(app).roads.insert(
+            r,
+            RoadFilter::new_by_user(*dist, app.session.filter_type)
+                .enforced_by(app.session.bus_gate_enforcement),
+        );
     }
 
     redraw_all_filters(ctx, app);
@@ -101,6 +144,8 @@ pub fn fix_oneway_and_add_filter(ctx: &mut EventCtx, app: &mut App, roads: &[(Ro
 pub struct ResolveBusGate {
     panel: Panel,
     roads: Vec<(RoadID, Distance)>,
+    /// Is there an edit group from `start_edit_group` waiting for this answer?
+    finish_edit_group: bool,
 }
 
 impl ResolveBusGate {
@@ -108,6 +153,24 @@ impl ResolveBusGate {
         ctx: &mut EventCtx,
         app: &mut App,
         roads: Vec<(RoadID, Distance)>,
+    ) -> Box<dyn State<App>> {
+        Self::new(ctx, app, roads, false)
+    }
+
+    /// Like `new_state`, but finishes the edit group in progress after the bus gates are placed
+    pub fn finishing_edit_group(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        roads: Vec<(RoadID, Distance)>,
+    ) -> Box<dyn State<App>> {
+        Self::new(ctx, app, roads, true)
+    }
+
+    fn new(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        roads: Vec<(RoadID, Distance)>,
+        finish_edit_group: bool,
     ) -> Box<dyn State<App>> {
         // TODO This'll mess up the placement, but we don't have easy access to the bottom panel
         // here
@@ -134,9 +197,14 @@ impl ResolveBusGate {
             Toggle::checkbox(ctx, "Don't show this warning again", None, true),
             ctx.style().btn_solid_primary.text("OK").build_def(ctx),
         ]))
+        .keyboard_navigation()
         .build(ctx);
 
-        Box::new(Self { panel, roads })
+        Box::new(Self {
+            panel,
+            roads,
+            finish_edit_group,
+        })
     }
 }
 
@@ -151,9 +219,14 @@ impl State<App> for ResolveBusGate {
 
             app.per_map.proposals.before_edit();
             for (r, dist) in self.roads.drain(..) {
-                mut_edits!(app)
-                    .roads
-                    .insert(r, RoadFilter::new_by_user(dist, FilterType::BusGate));
+                mut_edits!(app).roads.insert(
+                    r,
+                    RoadFilter::new_by_user(dist, FilterType::BusGate)
+                        .enforced_by(app.session.bus_gate_enforcement),
+                );
+            }
+            if self.finish_edit_group {
+                app.per_map.proposals.finish_edit_group();
             }
             redraw_all_filters(ctx, app);
 
@@ -178,16 +251,17 @@ pub struct ChangeFilterType {
 
 impl ChangeFilterType {
     pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let enforcement = app.session.bus_gate_enforcement;
         let filter = |ft: FilterType, hotkey: Key, name: &str| {
             ctx.style()
                 .btn_solid_primary
-                .icon_text(ft.svg_path(), name)
+                .icon_text(ft.svg_path_enforced_by(enforcement), name)
                 .image_color(
-                    RewriteColor::Change(ft.hide_color(), Color::CLEAR),
+                    RewriteColor::Change(ft.hide_color_enforced_by(enforcement), Color::CLEAR),
                     ControlState::Default,
                 )
                 .image_color(
-                    RewriteColor::Change(ft.hide_color(), Color::CLEAR),
+                    RewriteColor::Change(ft.hide_color_enforced_by(enforcement), Color::CLEAR),
                     ControlState::Disabled,
                 )
                 .disabled(app.session.filter_type == ft)
@@ -229,13 +303,27 @@ impl ChangeFilterType {
                     Text::from(Line(match app.session.filter_type {
                         FilterType::WalkCycleOnly => "A physical barrier that only allows people walking, cycling, and rolling to pass. Often planters or bollards. Larger vehicles cannot enter.",
                         FilterType::NoEntry => "An alternative sign to indicate vehicles are not allowed to enter the street. Only people walking, cycling, and rolling may pass through.",
-                        FilterType::BusGate => "A bus gate sign and traffic cameras are installed to allow buses, pedestrians, and cyclists to pass. There is no physical barrier.",
+                        FilterType::BusGate => match enforcement {
+                            Enforcement::Camera => "A bus gate sign and traffic cameras are installed to allow buses, pedestrians, and cyclists to pass. There is no physical barrier.",
+                            Enforcement::Physical => "Rising bollards or a similar barrier only lower for buses. Pedestrians and cyclists can pass around them.",
+                        },
                         FilterType::SchoolStreet => "A closure during school hours only. The barrier usually allows teachers and staff to access the school.",
                     })).wrap_to_pixels(ctx, crate::SPRITE_WIDTH as f64).into_widget(ctx),
+                    if app.session.filter_type == FilterType::BusGate {
+                        Toggle::checkbox(
+                            ctx,
+                            "Physical barrier (rising bollards)",
+                            None,
+                            enforcement == Enforcement::Physical,
+                        )
+                    } else {
+                        Widget::nothing()
+                    },
                 ]),
             ]),
             ctx.style().btn_solid_primary.text("OK").hotkey(Key::Enter).build_def(ctx).centered_horiz(),
         ]))
+        .keyboard_navigation()
         .build(ctx);
         Box::new(Self { panel })
     }
@@ -243,25 +331,38 @@ impl ChangeFilterType {
 
 impl State<App> for ChangeFilterType {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+        let outcome = self.panel.event(ctx);
+        if let Outcome::Changed(_) = outcome {
+            app.session.bus_gate_enforcement =
+                if self.panel.is_checked("Physical barrier (rising bollards)") {
+                    Enforcement::Physical
+                } else {
+                    Enforcement::Camera
+                };
+            return Transition::Replace(Self::new_state(ctx, app));
+        }
+        if let Outcome::Clicked(x) = outcome {
             return match x.as_ref() {
                 "No entry" => {
-                    app.session.filter_type = FilterType::NoEntry;
+                    app.session.pick_filter_type(FilterType::NoEntry);
                     Transition::Replace(Self::new_state(ctx, app))
                 }
                 "Walking/cycling only" => {
-                    app.session.filter_type = FilterType::WalkCycleOnly;
+                    app.session.pick_filter_type(FilterType::WalkCycleOnly);
                     Transition::Replace(Self::new_state(ctx, app))
                 }
                 "Bus gate" => {
-                    app.session.filter_type = FilterType::BusGate;
+                    app.session.pick_filter_type(FilterType::BusGate);
                     Transition::Replace(Self::new_state(ctx, app))
                 }
                 "School street" => {
-                    app.session.filter_type = FilterType::SchoolStreet;
+                    app.session.pick_filter_type(FilterType::SchoolStreet);
                     Transition::Replace(Self::new_state(ctx, app))
                 }
-                "close" | "OK" => Transition::Multi(vec![Transition::Pop, Transition::Recreate]),
+                "close" | "OK" => {
+                    app.session.save_settings();
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }
                 _ => unreachable!(),
             };
         }
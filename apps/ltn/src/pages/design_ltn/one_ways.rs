@@ -1,6 +1,8 @@
-use osm2streets::LaneSpec;
+use geom::Distance;
+use map_model::{Direction, LaneType, RoadID};
 use widgetry::mapspace::{World, WorldOutcome};
-use widgetry::{EventCtx, Text};
+use widgetry::tools::ChooseSomething;
+use widgetry::{Choice, EventCtx, Key, Line, Text, Transition};
 
 use super::{road_name, EditOutcome, Obj};
 use crate::render::colors;
@@ -17,10 +19,15 @@ pub fn make_world(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood)
             .hitbox(road.get_thick_polygon())
             .drawn_in_master_batch()
             .hover_color(colors::HOVER)
-            .tooltip(Text::from(format!(
-                "Click to flip direction of {}",
-                road_name(app, road)
-            )))
+            .tooltip({
+                let mut txt = Text::from(format!(
+                    "Click to flip direction of {}",
+                    road_name(app, road)
+                ));
+                txt.add_line(Line("Press W to narrow or widen lanes").small());
+                txt
+            })
+            .hotkey(Key::W, "lane widths")
             .clickable()
             .build(ctx);
     }
@@ -35,29 +42,90 @@ pub fn handle_world_outcome(
     outcome: WorldOutcome<Obj>,
 ) -> EditOutcome {
     match outcome {
-        WorldOutcome::ClickedObject(Obj::Road(r)) => {
-            if app.edits().roads.contains_key(&r) {
-                return EditOutcome::error(ctx, "A one-way street can't have a filter");
+        WorldOutcome::ClickedObject(Obj::Road(r)) => toggle_direction(ctx, app, r),
+        WorldOutcome::Keypress("lane widths", Obj::Road(r)) => choose_lane_width(ctx, app, r),
+        _ => EditOutcome::Nothing,
+    }
+}
+
+/// Narrowing traffic lanes is a common way to calm speeds
+fn choose_lane_width(ctx: &mut EventCtx, app: &App, r: RoadID) -> EditOutcome {
+    let step = Distance::meters(0.5);
+    let road = app.per_map.map.get_r(r);
+    let units = &app.opts.units;
+    let mut choices = Vec::new();
+    for (idx, lane) in road.lanes.iter().enumerate() {
+        if !matches!(lane.lane_type, LaneType::Driving | LaneType::Bus) {
+            continue;
+        }
+        let describe = format!(
+            "{} lane {}",
+            lane.lane_type.short_name(),
+            match lane.dir {
+                Direction::Fwd => "forwards",
+                Direction::Back => "backwards",
             }
-            if app
-                .per_map
-                .map
-                .get_r(r)
-                .is_deadend_for_driving(&app.per_map.map)
+        );
+        for (verb, delta) in [("Narrow", -step), ("Widen", step)] {
+            let width = lane.width + delta;
+            if width < logic::map_edits::MIN_TRAFFIC_LANE_WIDTH
+                || width > logic::map_edits::MAX_TRAFFIC_LANE_WIDTH
             {
-                return EditOutcome::error(ctx, "A dead-end street can't be one-way");
+                continue;
             }
+            choices.push(Choice::new(
+                format!(
+                    "{} the {} #{} from {} to {}",
+                    verb,
+                    describe,
+                    idx + 1,
+                    lane.width.to_string(units),
+                    width.to_string(units)
+                ),
+                (idx, delta),
+            ));
+        }
+    }
+    if choices.is_empty() {
+        return EditOutcome::error(ctx, "This road has no traffic lanes that can change width");
+    }
 
-            let driving_side = app.per_map.map.get_config().driving_side;
+    EditOutcome::Transition(Transition::Push(ChooseSomething::new_state(
+        ctx,
+        format!("Adjust lane widths on {}", road_name(app, road)),
+        choices,
+        Box::new(move |(idx, delta), ctx, app| {
             let mut edits = app.per_map.map.get_edits().clone();
             edits.commands.push(app.per_map.map.edit_road_cmd(r, |new| {
-                LaneSpec::toggle_road_direction(&mut new.lanes_ltr, driving_side);
+                logic::map_edits::change_lane_width(&mut new.lanes_ltr, idx, delta);
             }));
-
             logic::map_edits::modify_road(ctx, app, r, edits);
+            Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+        }),
+    )))
+}
 
-            EditOutcome::UpdateAll
-        }
-        _ => EditOutcome::Nothing,
+/// Cycle a road between two-way and each one-way direction
+pub fn toggle_direction(ctx: &mut EventCtx, app: &mut App, r: RoadID) -> EditOutcome {
+    if app.edits().roads.contains_key(&r) {
+        return EditOutcome::error(ctx, "A one-way street can't have a filter");
     }
+    if app
+        .per_map
+        .map
+        .get_r(r)
+        .is_deadend_for_driving(&app.per_map.map)
+    {
+        return EditOutcome::error(ctx, "A dead-end street can't be one-way");
+    }
+
+    let driving_side = app.per_map.map.get_config().driving_side;
+    let mut edits = app.per_map.map.get_edits().clone();
+    edits.commands.push(app.per_map.map.edit_road_cmd(r, |new| {
+        logic::map_edits::toggle_traffic_direction(&mut new.lanes_ltr, driving_side);
+    }));
+
+    logic::map_edits::modify_road(ctx, app, r, edits);
+
+    EditOutcome::UpdateAll
 }
@@ -1,28 +1,64 @@
-use geom::{Angle, ArrowCap, Distance, PolyLine, Pt2D};
-use map_gui::tools::DrawSimpleRoadLabels;
+use std::collections::{BTreeMap, BTreeSet};
+
+use geom::{Angle, ArrowCap, Bounds, Circle, Distance, PolyLine, Pt2D};
+use instant::Instant;
+use map_gui::tools::{percentage_bar, DrawSimpleRoadLabels, FilePicker};
+use map_gui::ID;
+use map_model::{
+    osm, IntersectionID, LaneID, Map, PathConstraints, Position, RoadID, TransitStopID,
+};
 use osm2streets::Direction;
 use widgetry::mapspace::{DummyID, World};
 use widgetry::tools::{ChooseSomething, PopupMsg};
 use widgetry::{
     lctrl, Choice, Color, ControlState, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, Key,
-    Line, Outcome, Panel, RewriteColor, State, Text, TextExt, Widget,
+    Line, Outcome, Panel, RewriteColor, State, Text, TextExt, UpdateType, Widget,
 };
 
-use super::{EditMode, EditNeighbourhood, EditOutcome};
+use super::{EditMode, EditNeighbourhood, EditOutcome, ViewMode};
 use crate::components::{AppwidePanel, BottomPanel, Mode};
-use crate::logic::AutoFilterHeuristic;
+use crate::logic::{copy_filters, AutoFilterHeuristic};
 use crate::render::colors;
 use crate::{
-    is_private, pages, render, App, FilterType, Neighbourhood, NeighbourhoodID, Transition,
+    is_private, mut_edits, pages, redraw_all_filters, render, App, DiagonalFilter, Edits,
+    FilterType, Neighbourhood, NeighbourhoodID, Transition,
 };
 
+/// Hold this to temporarily see the map without any changes from the current proposal
+const HIDE_EDITS_KEY: Key = Key::Space;
+/// Flips between coloring cells and showing only the shortcut heatmap
+const TOGGLE_VIEW_KEY: Key = Key::V;
+/// Dims everything outside the hovered cell, or stops doing that
+const FOCUS_CELL_KEY: Key = Key::O;
+/// Picks a color for the hovered cell
+const CELL_COLOR_KEY: Key = Key::K;
+/// How long to crossfade between old and new cell colors after an edit
+const CELL_FADE_SECONDS: f64 = 0.5;
+/// How long the glow around newly placed filters takes to fade out
+const FILTER_GLOW_SECONDS: f64 = 1.5;
+
 pub struct DesignLTN {
     appwide_panel: AppwidePanel,
     bottom_panel: Panel,
     neighbourhood: Neighbourhood,
     draw_top_layer: Drawable,
     draw_under_roads_layer: Drawable,
+    /// Only filled in for `ViewMode::Shortcuts`
+    draw_shortcuts_view: Drawable,
+    /// Only filled in for `ViewMode::BusCells`
+    draw_bus_cells_view: Drawable,
+    /// The cells currently drawn, kept around to stabilize colors and animate the next change
+    render_cells: Option<render::RenderCells>,
+    cell_fade: Option<CellFade>,
+    /// Halos around just-placed filters, and when they started fading
+    filter_glow: Option<(GeomBatch, Instant)>,
+    draw_filter_glow: Drawable,
+    /// The filters as of the last update, to find new ones to highlight. Empty when the page was
+    /// just opened, so existing filters don't glow.
+    seen_filters: Option<SeenFilters>,
     fade_irrelevant: Drawable,
+    /// Replaces `fade_irrelevant` while focusing on one cell
+    draw_focus_cell: Option<Drawable>,
     labels: DrawSimpleRoadLabels,
     highlight_cell: World<DummyID>,
     edit: EditNeighbourhood,
@@ -31,6 +67,22 @@ pub struct DesignLTN {
 
     show_unreachable_cell: Drawable,
     show_suspicious_perimeters: Drawable,
+    cut_off_stops: Vec<TransitStopID>,
+    show_cut_off_stops: Drawable,
+    /// Cul-de-sacs in the street network as loaded aren't the proposal's doing. Only depends on the
+    /// neighbourhood, so it's calculated once.
+    dead_ends_before_changes: BTreeSet<RoadID>,
+    /// Interior roads that drivers could pass through before, but that now lead nowhere at one end
+    new_dead_ends: BTreeSet<RoadID>,
+    show_new_dead_ends: Drawable,
+    /// A faint preview of the filter that clicking would place
+    ghost_filter: Drawable,
+    /// When the option is on, filters inside this neighbourhood and faint ones everywhere else.
+    /// Otherwise the app-wide drawing of all filters is used.
+    split_filters: Option<(render::Toggle3Zoomed, render::Toggle3Zoomed)>,
+    /// Edits made from a popup only show up after a `Transition::Recreate`, so check the cell
+    /// lock on the first event
+    check_cell_lock: bool,
 }
 
 impl DesignLTN {
@@ -38,19 +90,39 @@ impl DesignLTN {
         ctx: &mut EventCtx,
         app: &mut App,
         id: NeighbourhoodID,
+    ) -> Box<dyn State<App>> {
+        Self::new(ctx, app, id, false, None)
+    }
+
+    /// Like `new_state`, but also moves the camera to frame the neighbourhood. Use this when
+    /// the user first opens a neighbourhood.
+    pub fn enter(ctx: &mut EventCtx, app: &mut App, id: NeighbourhoodID) -> Box<dyn State<App>> {
+        Self::new(ctx, app, id, true, None)
+    }
+
+    fn new(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        id: NeighbourhoodID,
+        fit_camera: bool,
+        seen_filters: Option<SeenFilters>,
     ) -> Box<dyn State<App>> {
         app.per_map.current_neighbourhood = Some(id);
 
         let neighbourhood = Neighbourhood::new(app, id);
+        if fit_camera {
+            fit_neighbourhood(ctx, app, &neighbourhood);
+        }
         let fade_irrelevant = neighbourhood.fade_irrelevant(ctx, app);
 
         let mut label_roads = neighbourhood.perimeter_roads.clone();
         label_roads.extend(neighbourhood.interior_roads.clone());
-        let labels = DrawSimpleRoadLabels::new(
+        let labels = DrawSimpleRoadLabels::new_with_name_overrides(
             ctx,
             app,
             colors::LOCAL_ROAD_LABEL,
             Box::new(move |r| label_roads.contains(&r.id)),
+            app.edits().road_names.clone(),
         );
 
         let mut show_suspicious_perimeters = GeomBatch::new();
@@ -59,13 +131,26 @@ impl DesignLTN {
                 .push(Color::RED, app.per_map.map.get_r(*r).get_thick_polygon());
         }
 
+        let dead_ends_before_changes =
+            neighbourhood.dead_ends(&app.per_map.map, &app.per_map.edits_before_changes);
+        let new_dead_ends =
+            &neighbourhood.dead_ends(&app.per_map.map, app.edits()) - &dead_ends_before_changes;
+
         let mut state = Self {
             appwide_panel: AppwidePanel::new(ctx, app, Mode::ModifyNeighbourhood),
             bottom_panel: Panel::empty(ctx),
             neighbourhood,
             draw_top_layer: Drawable::empty(ctx),
             draw_under_roads_layer: Drawable::empty(ctx),
+            draw_shortcuts_view: Drawable::empty(ctx),
+            draw_bus_cells_view: Drawable::empty(ctx),
+            render_cells: None,
+            cell_fade: None,
+            filter_glow: None,
+            draw_filter_glow: Drawable::empty(ctx),
+            seen_filters,
             fade_irrelevant,
+            draw_focus_cell: None,
             labels,
             highlight_cell: World::new(),
             edit: EditNeighbourhood::temporary(),
@@ -75,6 +160,14 @@ impl DesignLTN {
 
             show_unreachable_cell: Drawable::empty(ctx),
             show_suspicious_perimeters: ctx.upload(show_suspicious_perimeters),
+            cut_off_stops: Vec::new(),
+            show_cut_off_stops: Drawable::empty(ctx),
+            dead_ends_before_changes,
+            new_dead_ends,
+            show_new_dead_ends: Drawable::empty(ctx),
+            ghost_filter: Drawable::empty(ctx),
+            split_filters: None,
+            check_cell_lock: true,
         };
         state.update(ctx, app);
         Box::new(state)
@@ -82,12 +175,64 @@ impl DesignLTN {
 
     fn update(&mut self, ctx: &mut EventCtx, app: &App) {
         let (edit, draw_top_layer, draw_under_roads_layer, render_cells, highlight_cell) =
-            setup_editing(ctx, app, &self.neighbourhood, &self.labels);
+            setup_editing(
+                ctx,
+                app,
+                &self.neighbourhood,
+                &self.labels,
+                self.render_cells.as_ref(),
+            );
         self.edit = edit;
         self.draw_top_layer = draw_top_layer;
         self.draw_under_roads_layer = draw_under_roads_layer;
         self.highlight_cell = highlight_cell;
 
+        self.split_filters = app
+            .session
+            .layers
+            .faint_other_filters
+            .then(|| draw_split_filters(ctx, app, &self.neighbourhood));
+
+        self.cell_fade = None;
+        if let Some(ref previous) = self.render_cells {
+            if app.session.layers.animate_cell_changes && !previous.same_cells(&render_cells) {
+                self.cell_fade = Some(CellFade {
+                    before: previous.draw_colored_areas(),
+                    after: render_cells.draw_colored_areas(),
+                    started: Instant::now(),
+                });
+                self.draw_under_roads_layer = ctx.upload(previous.draw_colored_areas());
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+
+        self.draw_shortcuts_view = if app.session.view_mode == ViewMode::Shortcuts {
+            ctx.upload(self.neighbourhood.shortcuts.draw_heatmap(app))
+        } else {
+            Drawable::empty(ctx)
+        };
+        self.draw_bus_cells_view = if app.session.view_mode == ViewMode::BusCells {
+            let map = &app.per_map.map;
+            let bus_cells = self.neighbourhood.cells_for_buses(map, app.edits());
+            let render = render::RenderCells::new_for_cells(map, &self.neighbourhood, &bus_cells);
+            let mut batch = render.draw_colored_areas();
+            batch.append(render.draw_island_outlines());
+            ctx.upload(batch)
+        } else {
+            Drawable::empty(ctx)
+        };
+
+        // Most updates don't add filters; leave any glow that's still fading alone
+        if let Some(ref seen) = self.seen_filters {
+            let halos = new_filter_halos(app, seen);
+            if !halos.is_empty() {
+                self.draw_filter_glow = ctx.upload(halos.clone());
+                self.filter_glow = Some((halos, Instant::now()));
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+        self.seen_filters = Some(SeenFilters::new(app));
+
         let mut show_unreachable_cell = GeomBatch::new();
         let mut disconnected_cells = 0;
         for (idx, cell) in self.neighbourhood.cells.iter().enumerate() {
@@ -131,6 +276,64 @@ impl DesignLTN {
                 .build_widget(ctx, "warning2")
         };
 
+        self.cut_off_stops = find_cut_off_transit_stops(app, &self.neighbourhood);
+        let mut show_cut_off_stops = GeomBatch::new();
+        for ts in &self.cut_off_stops {
+            let pos = app.per_map.map.get_ts(*ts).driving_pos;
+            show_cut_off_stops.push(
+                Color::RED.alpha(0.8),
+                Circle::new(pos.pt(&app.per_map.map), Distance::meters(10.0)).to_polygon(),
+            );
+        }
+        self.show_cut_off_stops = ctx.upload(show_cut_off_stops);
+        let warning3 = if self.cut_off_stops.is_empty() {
+            Widget::nothing()
+        } else {
+            let msg = if self.cut_off_stops.len() == 1 {
+                "1 bus stop is cut off from its route".to_string()
+            } else {
+                format!(
+                    "{} bus stops are cut off from their routes",
+                    self.cut_off_stops.len()
+                )
+            };
+
+            ctx.style()
+                .btn_plain
+                .icon_text("system/assets/tools/warning.svg", msg)
+                .label_color(Color::RED, ControlState::Default)
+                .no_tooltip()
+                .build_widget(ctx, "warning3")
+        };
+
+        let mut show_new_dead_ends = GeomBatch::new();
+        for r in &self.new_dead_ends {
+            show_new_dead_ends.push(
+                colors::NEW_DEAD_END,
+                app.per_map.map.get_r(*r).get_thick_polygon(),
+            );
+        }
+        self.show_new_dead_ends = ctx.upload(show_new_dead_ends);
+        let warning4 = if self.new_dead_ends.is_empty() {
+            Widget::nothing()
+        } else {
+            let msg = if self.new_dead_ends.len() == 1 {
+                "1 road segment is now a dead-end".to_string()
+            } else {
+                format!(
+                    "{} road segments are now dead-ends",
+                    self.new_dead_ends.len()
+                )
+            };
+
+            // Not an error, just a consequence worth knowing about
+            ctx.style()
+                .btn_plain
+                .icon_text("system/assets/tools/no_entry.svg", msg)
+                .no_tooltip()
+                .build_widget(ctx, "warning4")
+        };
+
         self.bottom_panel = make_bottom_panel(
             ctx,
             app,
@@ -143,16 +346,303 @@ impl DesignLTN {
                 )
                 .text_widget(ctx)
                 .centered_horiz(),
+                {
+                    let pct = self.neighbourhood.fraction_calmed();
+                    percentage_bar(
+                        ctx,
+                        Text::from(format!("{}% of streets calmed", (pct * 100.0).round())),
+                        pct,
+                    )
+                    .centered_horiz()
+                },
+                {
+                    let cells = self.neighbourhood.cells.len();
+                    let areas = self.neighbourhood.walking_areas(&app.per_map.map);
+                    format!(
+                        "Cars: {} cell{}, pedestrians: {} connected area{}",
+                        cells,
+                        if cells == 1 { "" } else { "s" },
+                        areas,
+                        if areas == 1 { "" } else { "s" }
+                    )
+                    .text_widget(ctx)
+                    .centered_horiz()
+                },
+                {
+                    let roads =
+                        &self.neighbourhood.interior_roads | &self.neighbourhood.perimeter_roads;
+                    format!(
+                        "Signs/works needed at {} junctions",
+                        app.edits()
+                            .intersections_touched(&app.per_map.map, &roads)
+                            .len()
+                    )
+                    .text_widget(ctx)
+                    .centered_horiz()
+                },
                 warning1.centered_horiz(),
                 warning2.centered_horiz(),
+                warning3.centered_horiz(),
+                warning4.centered_horiz(),
             ])
             .centered_vert(),
         );
+        self.render_cells = Some(render_cells);
+        self.update_focus_cell(ctx, app);
+    }
+
+    /// Recalculate everything after the edits change. If the cells are locked and this edit
+    /// changed them, returns a prompt to keep or undo it.
+    fn edits_changed(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+        self.neighbourhood
+            .edits_changed(&app.per_map.map, app.edits());
+        self.new_dead_ends = &self.neighbourhood.dead_ends(&app.per_map.map, app.edits())
+            - &self.dead_ends_before_changes;
+        self.update(ctx, app);
+        super::check_cell_lock(ctx, app, &self.neighbourhood)
+    }
+
+    fn update_focus_cell(&mut self, ctx: &mut EventCtx, app: &App) {
+        let render_cells = self.render_cells.as_ref().unwrap();
+        self.draw_focus_cell = app
+            .session
+            .focus_cell
+            .and_then(|r| {
+                self.neighbourhood
+                    .cells
+                    .iter()
+                    .position(|cell| cell.roads.contains_key(&r))
+            })
+            .map(|idx| render::fade_outside(ctx, app, render_cells.polygons_per_cell[idx].clone()));
+    }
+
+    /// Which cell is the cursor over? Returns one of its roads, as an anchor for `focus_cell`.
+    fn hovered_cell(&self, ctx: &EventCtx) -> Option<RoadID> {
+        let pt = ctx.canvas.get_cursor_in_map_space()?;
+        let render_cells = self.render_cells.as_ref()?;
+        let idx = render_cells
+            .polygons_per_cell
+            .iter()
+            .position(|polygons| polygons.iter().any(|p| p.contains_pt(pt)))?;
+        self.neighbourhood.cells[idx].roads.keys().next().cloned()
+    }
+
+    fn draw_fade(&self, g: &mut GfxCtx) {
+        g.redraw(
+            self.draw_focus_cell
+                .as_ref()
+                .unwrap_or(&self.fade_irrelevant),
+        );
+    }
+}
+
+/// Choose the color of the cell containing `r`, and remember it for this map
+fn pick_cell_color(
+    ctx: &mut EventCtx,
+    neighbourhood: &Neighbourhood,
+    r: RoadID,
+) -> Box<dyn State<App>> {
+    let roads: Vec<RoadID> = neighbourhood
+        .cells
+        .iter()
+        .find(|cell| cell.roads.contains_key(&r))
+        .map(|cell| cell.roads.keys().cloned().collect())
+        .unwrap_or_default();
+
+    let mut choices = vec![Choice::new("automatic", None)];
+    for (idx, name) in colors::CELL_NAMES.iter().enumerate() {
+        choices.push(Choice::new(*name, Some(idx)));
+    }
+    ChooseSomething::new_state(
+        ctx,
+        "Color this cell",
+        choices,
+        Box::new(move |color, _, app| {
+            let saved = app
+                .session
+                .cell_colors
+                .entry(app.per_map.map.get_name().clone())
+                .or_insert_with(BTreeMap::new);
+            for r in roads {
+                saved.remove(&r);
+                if let Some(idx) = color {
+                    saved.insert(r, idx);
+                }
+            }
+            app.session.save_settings();
+            Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+        }),
+    )
+}
+
+/// Hatch across every filter that splits a road between cells, and along every diagonal filter
+/// inside the neighbourhood
+fn draw_cell_seams(batch: &mut GeomBatch, app: &App, neighbourhood: &Neighbourhood) {
+    let map = &app.per_map.map;
+    let thickness = Distance::meters(3.0);
+    for r in &neighbourhood.interior_roads {
+        let filter = match app.edits().roads.get(r) {
+            Some(filter) => filter,
+            None => continue,
+        };
+        let num_cells = neighbourhood
+            .cells
+            .iter()
+            .filter(|cell| cell.roads.contains_key(r))
+            .count();
+        if num_cells < 2 {
+            continue;
+        }
+        let road = map.get_r(*r);
+        if let Ok((pt, angle)) = road.center_pts.dist_along(filter.dist) {
+            let half_width = road.get_width() / 2.0;
+            let across = angle.rotate_degs(90.0);
+            if let Ok(seam) = geom::Line::new(
+                pt.project_away(half_width, across.opposite()),
+                pt.project_away(half_width, across),
+            ) {
+                render::draw_hatched_seam(batch, &seam, thickness);
+            }
+        }
+    }
+
+    for (i, filters) in &app.edits().intersections {
+        if !map
+            .get_i(*i)
+            .roads
+            .iter()
+            .any(|r| neighbourhood.interior_roads.contains(r))
+        {
+            continue;
+        }
+        for filter in filters {
+            render::draw_hatched_seam(batch, &filter.geometry(map), thickness);
+        }
+    }
+}
+
+/// Every filter at some point in time, without the rest of the edits
+#[derive(Clone)]
+struct SeenFilters {
+    roads: BTreeSet<RoadID>,
+    intersections: BTreeMap<IntersectionID, Vec<DiagonalFilter>>,
+}
+
+impl SeenFilters {
+    fn new(app: &App) -> Self {
+        Self {
+            roads: app.edits().roads.keys().cloned().collect(),
+            intersections: app.edits().intersections.clone(),
+        }
+    }
+}
+
+/// A halo behind every filter that's been added since `seen`
+fn new_filter_halos(app: &App, seen: &SeenFilters) -> GeomBatch {
+    let map = &app.per_map.map;
+    let scale = app.session.layers.filter_icon_scale;
+
+    let mut batch = GeomBatch::new();
+    for (r, filter) in &app.edits().roads {
+        if seen.roads.contains(r) {
+            continue;
+        }
+        let road = map.get_r(*r);
+        if let Ok((pt, _)) = road.center_pts.dist_along(filter.dist) {
+            batch.push(
+                Color::YELLOW.alpha(0.8),
+                Circle::new(pt, scale * road.get_width()).to_polygon(),
+            );
+        }
+    }
+    for (i, filters) in &app.edits().intersections {
+        let before = seen.intersections.get(i);
+        for filter in filters {
+            if before
+                .map(|list| list.iter().any(|x| x.approx_eq(filter)))
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            let (pt, width) = filter.icon_placement(map, scale);
+            batch.push(
+                Color::YELLOW.alpha(0.8),
+                Circle::new(pt, Distance::meters(width)).to_polygon(),
+            );
+        }
+    }
+    batch
+}
+
+struct CellFade {
+    before: GeomBatch,
+    after: GeomBatch,
+    started: Instant,
+}
+
+impl CellFade {
+    /// Draw the old cells fading out and the new ones fading in. `pct` is from 0 to 1.
+    fn blend(&self, pct: f64) -> GeomBatch {
+        let mut batch = self
+            .before
+            .clone()
+            .color(RewriteColor::ChangeAlpha((0.8 * (1.0 - pct)) as f32));
+        batch.append(
+            self.after
+                .clone()
+                .color(RewriteColor::ChangeAlpha((0.8 * pct) as f32)),
+        );
+        batch
     }
 }
 
 impl State<App> for DesignLTN {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if std::mem::take(&mut self.check_cell_lock) {
+            if let Some(t) = super::check_cell_lock(ctx, app, &self.neighbourhood) {
+                return t;
+            }
+        }
+
+        // The cell and filter fades both animate off the same update event
+        let is_update = ctx.input.nonblocking_is_update_event().is_some()
+            && (self.cell_fade.is_some() || self.filter_glow.is_some());
+        if is_update {
+            ctx.input.use_update_event();
+        }
+        if let Some(ref fade) = self.cell_fade {
+            if is_update {
+                let pct = abstutil::elapsed_seconds(fade.started) / CELL_FADE_SECONDS;
+                if pct >= 1.0 {
+                    self.draw_under_roads_layer = ctx.upload(fade.after.clone());
+                    self.cell_fade = None;
+                } else {
+                    self.draw_under_roads_layer = ctx.upload(fade.blend(pct));
+                }
+            }
+            if self.cell_fade.is_some() {
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+        if let Some((ref halos, started)) = self.filter_glow {
+            if is_update {
+                let pct = abstutil::elapsed_seconds(started) / FILTER_GLOW_SECONDS;
+                if pct >= 1.0 {
+                    self.draw_filter_glow = Drawable::empty(ctx);
+                    self.filter_glow = None;
+                } else {
+                    self.draw_filter_glow = ctx.upload(
+                        halos
+                            .clone()
+                            .color(RewriteColor::ChangeAlpha((1.0 - pct) as f32)),
+                    );
+                }
+            }
+            if self.filter_glow.is_some() {
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+
         if let Some(t) = self
             .appwide_panel
             .event(ctx, app, &self.preserve_state, help)
@@ -168,7 +658,19 @@ impl State<App> for DesignLTN {
             return t;
         }
         if let Outcome::Clicked(x) = self.bottom_panel.event(ctx) {
-            if x == "Advanced" {
+            if x == "fit neighbourhood" {
+                if ctx.is_key_down(Key::LeftShift) {
+                    if let Some(hull) = self
+                        .neighbourhood
+                        .filters_hull(&app.per_map.map, app.edits())
+                    {
+                        fit_bounds(ctx, hull.get_bounds());
+                    }
+                } else {
+                    fit_neighbourhood(ctx, app, &self.neighbourhood);
+                }
+                return Transition::Keep;
+            } else if x == "Advanced" {
                 return launch_advanced(ctx, app, self.neighbourhood.id);
             } else if x == "warning1" {
                 return Transition::Push(PopupMsg::new_state(
@@ -191,6 +693,41 @@ impl State<App> for DesignLTN {
                         "If you're near the edge of the map, it might be an error. Try importing a larger area, including the next major road in that direction",
                         ],
                         ));
+            } else if x == "warning3" {
+                let mut lines = vec![
+                    "A filter that buses can't pass sits on the same road as these stops:"
+                        .to_string(),
+                    String::new(),
+                ];
+                for ts in &self.cut_off_stops {
+                    let stop = app.per_map.map.get_ts(*ts);
+                    lines.push(format!(
+                        "{} (on {})",
+                        stop.name,
+                        super::road_name(app, app.per_map.map.get_r(ts.road))
+                    ));
+                }
+                lines.push(String::new());
+                lines.push("Consider using a bus gate instead.".to_string());
+                return Transition::Push(PopupMsg::new_state(ctx, "Bus stops cut off", lines));
+            } else if x == "warning4" {
+                let map = &app.per_map.map;
+                let names: BTreeSet<String> = self
+                    .new_dead_ends
+                    .iter()
+                    .map(|r| super::road_name(app, map.get_r(*r)))
+                    .collect();
+                let mut lines = vec![
+                    "Drivers could pass through these streets before, but now have to turn around:"
+                        .to_string(),
+                    String::new(),
+                ];
+                lines.extend(names);
+                lines.push(String::new());
+                lines.push(
+                    "Check there's room for delivery and refuse vehicles to turn.".to_string(),
+                );
+                return Transition::Push(PopupMsg::new_state(ctx, "New dead-ends", lines));
             }
 
             match self.edit.handle_panel_action(
@@ -206,10 +743,7 @@ impl State<App> for DesignLTN {
                     return Transition::Keep;
                 }
                 EditOutcome::UpdateAll => {
-                    self.neighbourhood
-                        .edits_changed(&app.per_map.map, app.edits());
-                    self.update(ctx, app);
-                    return Transition::Keep;
+                    return self.edits_changed(ctx, app).unwrap_or(Transition::Keep);
                 }
                 EditOutcome::Transition(t) => {
                     return t;
@@ -217,20 +751,69 @@ impl State<App> for DesignLTN {
             }
         }
 
+        if ctx.input.pressed(TOGGLE_VIEW_KEY) {
+            app.session.view_mode = match app.session.view_mode {
+                ViewMode::Cells => ViewMode::Shortcuts,
+                ViewMode::Shortcuts => ViewMode::BusCells,
+                ViewMode::BusCells => ViewMode::Cells,
+            };
+            self.update(ctx, app);
+            return Transition::Keep;
+        }
+
+        if ctx.input.pressed(FOCUS_CELL_KEY) {
+            let hovered = self.hovered_cell(ctx);
+            // Pressing the key on the focused cell again (or off every cell) goes back to normal
+            let same_cell = match (hovered, app.session.focus_cell) {
+                (Some(r1), Some(r2)) => self
+                    .neighbourhood
+                    .cells
+                    .iter()
+                    .any(|cell| cell.roads.contains_key(&r1) && cell.roads.contains_key(&r2)),
+                _ => false,
+            };
+            app.session.focus_cell = if same_cell { None } else { hovered };
+            self.update_focus_cell(ctx, app);
+            return Transition::Keep;
+        }
+
+        if ctx.input.pressed(CELL_COLOR_KEY) {
+            if let Some(r) = self.hovered_cell(ctx) {
+                return Transition::Push(pick_cell_color(ctx, &self.neighbourhood, r));
+            }
+        }
+
+        let mut update_ghost = ctx.redo_mouseover();
         match self.edit.event(ctx, app, &self.neighbourhood) {
             EditOutcome::Nothing => {}
             EditOutcome::UpdatePanelAndWorld => {
                 self.update(ctx, app);
+                update_ghost = true;
             }
             EditOutcome::UpdateAll => {
-                self.neighbourhood
-                    .edits_changed(&app.per_map.map, app.edits());
-                self.update(ctx, app);
+                if let Some(t) = self.edits_changed(ctx, app) {
+                    self.ghost_filter = Drawable::empty(ctx);
+                    return t;
+                }
+                update_ghost = true;
             }
             EditOutcome::Transition(t) => {
+                self.ghost_filter = Drawable::empty(ctx);
                 return t;
             }
         }
+        if update_ghost {
+            self.ghost_filter = if let EditMode::Filters = app.session.edit_mode {
+                super::filters::draw_ghost_filter(
+                    ctx,
+                    app,
+                    &self.neighbourhood,
+                    self.edit.world.get_hovering(),
+                )
+            } else {
+                Drawable::empty(ctx)
+            };
+        }
 
         self.highlight_cell.event(ctx);
 
@@ -242,10 +825,43 @@ impl State<App> for DesignLTN {
     }
 
     fn draw(&self, g: &mut GfxCtx, app: &App) {
-        app.draw_with_layering(g, |g| g.redraw(&self.draw_under_roads_layer));
-        g.redraw(&self.fade_irrelevant);
-        self.draw_top_layer.draw(g);
-        self.highlight_cell.draw(g);
+        // While the key is held, skip everything derived from the proposal, so it's easy to
+        // compare with the original map. Nothing about the edits changes.
+        if g.is_key_down(HIDE_EDITS_KEY) {
+            app.draw_with_layering(g, |_| {});
+            // Roads narrowed or widened are baked into the basemap, so draw them as they were
+            g.redraw(&app.per_map.draw_unedited_roads);
+            self.draw_fade(g);
+
+            self.appwide_panel.draw(g);
+            self.bottom_panel.draw(g);
+            app.session.layers.draw_without_edits(g, app);
+            self.labels.draw(g);
+            app.per_map.draw_major_road_labels.draw(g);
+            app.per_map.draw_poi_icons.draw(g);
+            return;
+        }
+
+        match app.session.view_mode {
+            ViewMode::Cells => {
+                app.draw_with_layering(g, |g| g.redraw(&self.draw_under_roads_layer));
+                self.draw_fade(g);
+                self.draw_top_layer.draw(g);
+                self.highlight_cell.draw(g);
+            }
+            ViewMode::Shortcuts => {
+                app.draw_with_layering(g, |_| {});
+                self.draw_fade(g);
+                // The heatmap is already part of the world in this edit mode
+                if !matches!(app.session.edit_mode, EditMode::Shortcuts(_)) {
+                    g.redraw(&self.draw_shortcuts_view);
+                }
+            }
+            ViewMode::BusCells => {
+                app.draw_with_layering(g, |g| g.redraw(&self.draw_bus_cells_view));
+                self.draw_fade(g);
+            }
+        }
         self.edit.world.draw(g);
 
         self.appwide_panel.draw(g);
@@ -253,7 +869,15 @@ impl State<App> for DesignLTN {
         app.session.layers.draw(g, app);
         self.labels.draw(g);
         app.per_map.draw_major_road_labels.draw(g);
-        app.per_map.draw_all_filters.draw(g);
+        // The glow goes behind the filter icons
+        g.redraw(&self.draw_filter_glow);
+        if let Some((ref inside, ref outside)) = self.split_filters {
+            outside.draw(g);
+            inside.draw(g);
+        } else {
+            app.per_map.draw_all_filters.draw(g);
+        }
+        g.redraw(&self.ghost_filter);
         app.per_map.draw_poi_icons.draw(g);
 
         if self.bottom_panel.currently_hovering() == Some(&"warning1".to_string()) {
@@ -262,22 +886,66 @@ impl State<App> for DesignLTN {
         if self.bottom_panel.currently_hovering() == Some(&"warning2".to_string()) {
             g.redraw(&self.show_suspicious_perimeters);
         }
+        if self.bottom_panel.currently_hovering() == Some(&"warning3".to_string()) {
+            g.redraw(&self.show_cut_off_stops);
+        }
+        if self.bottom_panel.currently_hovering() == Some(&"warning4".to_string()) {
+            g.redraw(&self.show_new_dead_ends);
+        }
 
         if let EditMode::FreehandFilters(ref lasso) = app.session.edit_mode {
             lasso.draw(g);
         }
+        if let EditMode::SelectFilters(ref lasso) | EditMode::SketchCell(ref lasso) =
+            app.session.edit_mode
+        {
+            lasso.draw(g);
+        }
     }
 
     fn recreate(&mut self, ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
-        Self::new_state(ctx, app, self.neighbourhood.id)
+        // Filters placed through a popup show up here, so keep looking for new ones
+        Self::new(
+            ctx,
+            app,
+            self.neighbourhood.id,
+            false,
+            self.seen_filters.take(),
+        )
     }
 }
 
+/// Zoom and center the camera so all of the neighbourhood's roads fill the screen
+pub fn fit_neighbourhood(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood) {
+    let map = &app.per_map.map;
+    let mut bounds = Bounds::new();
+    for r in neighbourhood
+        .interior_roads
+        .iter()
+        .chain(neighbourhood.perimeter_roads.iter())
+    {
+        bounds.union(map.get_r(*r).get_thick_polygon().get_bounds());
+    }
+    fit_bounds(ctx, bounds);
+}
+
+fn fit_bounds(ctx: &mut EventCtx, bounds: Bounds) {
+    if bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+        return;
+    }
+    // Leave some padding around the edges, and room for the bottom panel
+    ctx.canvas.cam_zoom = (0.8 * ctx.canvas.window_width / bounds.width())
+        .min(0.7 * ctx.canvas.window_height / bounds.height())
+        .min(ctx.canvas.max_zoom());
+    ctx.canvas.center_on_map_pt(bounds.center());
+}
+
 fn setup_editing(
     ctx: &mut EventCtx,
     app: &App,
     neighbourhood: &Neighbourhood,
     labels: &DrawSimpleRoadLabels,
+    previous_cells: Option<&render::RenderCells>,
 ) -> (
     EditNeighbourhood,
     Drawable,
@@ -294,11 +962,52 @@ fn setup_editing(
     // edit.world so that we draw it even while hovering on roads/intersections in a cell
     let mut highlight_cell = World::new();
 
-    let render_cells = render::RenderCells::new(map, neighbourhood);
+    let mut render_cells = if app.session.layers.only_color_minor_roads {
+        render::RenderCells::new_minor_roads_only(map, neighbourhood)
+    } else {
+        render::RenderCells::new(map, neighbourhood)
+    };
+    render_cells.keep_colors(previous_cells, app.session.cell_colors.get(map.get_name()));
+    if app.session.layers.shade_cells_by_shortcuts {
+        render_cells.shade_by_shortcuts(neighbourhood);
+    }
 
     let draw_under_roads_layer = render_cells.draw_colored_areas();
+    if app.session.layers.only_color_minor_roads {
+        // Keep the busier roads visible as context, but faded out and clearly not part of any cell
+        for r in &neighbourhood.interior_roads {
+            let road = map.get_r(*r);
+            if road.get_rank() != osm::RoadRank::Local {
+                draw_top_layer.push(app.cs.fade_map_dark, road.get_thick_polygon());
+            }
+        }
+    }
     draw_top_layer.append(render_cells.draw_island_outlines());
 
+    // A filter splits its road between two cells. Optionally tint each side with its cell's color.
+    if app.session.layers.tint_filtered_roads {
+        for r in &neighbourhood.interior_roads {
+            let filter = match app.edits().roads.get(r) {
+                Some(filter) => filter,
+                None => continue,
+            };
+            let (before, after) = render::split_thick_polygon(map.get_r(*r), filter.dist);
+            for (idx, cell) in neighbourhood.cells.iter().enumerate() {
+                if let Some(interval) = cell.roads.get(r) {
+                    let piece = if interval.end <= filter.dist {
+                        before.clone()
+                    } else {
+                        after.clone()
+                    };
+                    draw_top_layer.push(render_cells.colors[idx].alpha(0.5), piece);
+                }
+            }
+        }
+    }
+    if app.session.layers.hatch_cell_seams {
+        draw_cell_seams(&mut draw_top_layer, app, neighbourhood);
+    }
+
     // Highlight border arrows when hovered
     for (idx, polygons) in render_cells.polygons_per_cell.iter().enumerate() {
         // Edge case happening near https://www.openstreetmap.org/way/106879596
@@ -338,6 +1047,31 @@ fn setup_editing(
         }
     }
 
+    // Badge cells containing a school, since the school run makes them a priority
+    let school = GeomBatch::load_svg(ctx, "system/assets/map/school.svg")
+        .scale(0.2)
+        .color(RewriteColor::ChangeAll(Color::WHITE));
+    for idx in neighbourhood.cells_with_schools(map) {
+        let polygons = &render_cells.polygons_per_cell[idx];
+        for polygon in polygons {
+            draw_top_layer.push(
+                colors::SCHOOL_CELL,
+                polygon.to_outline(Distance::meters(3.0)),
+            );
+        }
+        if let Some(biggest) = polygons
+            .iter()
+            .max_by(|a, b| a.area().partial_cmp(&b.area()).unwrap())
+        {
+            let center = biggest.polylabel();
+            draw_top_layer.push(
+                colors::SCHOOL_CELL,
+                Circle::new(center, Distance::meters(8.0)).to_polygon(),
+            );
+            draw_top_layer.append(school.clone().centered_on(center));
+        }
+    }
+
     // Draw one-way arrows and mark private roads
     let private_road = GeomBatch::load_svg(ctx, "system/assets/map/private_road.svg");
 
@@ -426,8 +1160,169 @@ fn setup_editing(
     )
 }
 
+/// Draw the filters inside this neighbourhood normally, and all others faintly
+fn draw_split_filters(
+    ctx: &EventCtx,
+    app: &App,
+    neighbourhood: &Neighbourhood,
+) -> (render::Toggle3Zoomed, render::Toggle3Zoomed) {
+    // Only the filters are needed to draw, so avoid cloning the edit history
+    let mut inside = crate::Edits::default();
+    let mut outside = crate::Edits::default();
+    for (r, filter) in &app.edits().roads {
+        let edits = if neighbourhood.interior_roads.contains(r) {
+            &mut inside
+        } else {
+            &mut outside
+        };
+        edits.roads.insert(*r, filter.clone());
+    }
+    for (i, filters) in &app.edits().intersections {
+        let edits = if neighbourhood.interior_intersections.contains(i) {
+            &mut inside
+        } else {
+            &mut outside
+        };
+        edits.intersections.insert(*i, filters.clone());
+    }
+    for (i, bans) in &app.edits().banned_turns {
+        let edits = if neighbourhood.interior_intersections.contains(i) {
+            &mut inside
+        } else {
+            &mut outside
+        };
+        edits.banned_turns.insert(*i, bans.clone());
+    }
+
+    let map = &app.per_map.map;
+    let scale = app.session.layers.filter_icon_scale;
+    let zoom = app.session.layers.filter_zoom();
+    let style = app.session.layers.filter_style;
+    // Zoomed all the way out, individual filters are replaced by the same summary as usual
+    let summary = render::render_filter_summary(ctx, map, app.partitioning(), app.edits());
+    (
+        inside
+            .draw(ctx, map, scale, zoom, style)
+            .with_summary(summary),
+        outside
+            .draw_dimmed(ctx, map, scale, zoom, style, 0.3)
+            .hidden_when_summarized(),
+    )
+}
+
+/// Find transit stops in the neighbourhood that buses can't reach or leave anymore, because of
+/// filters or banned turns anywhere along the way. Each route serving the stop is followed from the
+/// previous stop and to the next one. For stops without any known route, a bus just has to get
+/// there from the perimeter and back out again.
+fn find_cut_off_transit_stops(app: &App, neighbourhood: &Neighbourhood) -> Vec<TransitStopID> {
+    let map = &app.per_map.map;
+    // Buses pass through bus gates
+    let mut edits = app.edits().routing_copy();
+    edits
+        .roads
+        .retain(|_, filter| filter.filter_type != FilterType::BusGate);
+    for list in edits.intersections.values_mut() {
+        list.retain(|filter| filter.filter_type != FilterType::BusGate);
+    }
+
+    let perimeter_lanes: Vec<LaneID> = neighbourhood
+        .perimeter_roads
+        .iter()
+        .flat_map(|r| map.get_r(*r).lanes.iter())
+        .filter(|lane| PathConstraints::Bus.can_use(lane, map))
+        .map(|lane| lane.id)
+        .collect();
+
+    let mut stops = Vec::new();
+    for ts in map.all_transit_stops().keys() {
+        if !neighbourhood.interior_roads.contains(&ts.road)
+            && !neighbourhood.perimeter_roads.contains(&ts.road)
+        {
+            continue;
+        }
+        let pos = map.get_ts(*ts).driving_pos;
+        let routes = map.get_routes_serving_stop(*ts);
+        let reachable = if routes.is_empty() {
+            bus_can_reach(map, &edits, perimeter_lanes.clone(), |l| l == pos.lane())
+                && bus_can_reach(map, &edits, vec![pos.lane()], |l| {
+                    neighbourhood.perimeter_roads.contains(&l.road)
+                })
+        } else {
+            routes.into_iter().all(|route| {
+                let idx = route.stops.iter().position(|x| x == ts).unwrap();
+                let from = if idx == 0 {
+                    Position::start(route.start)
+                } else {
+                    map.get_ts(route.stops[idx - 1]).driving_pos
+                };
+                let to = match route.stops.get(idx + 1) {
+                    Some(next) => Some(map.get_ts(*next).driving_pos),
+                    None => route.end_border.map(|l| Position::end(l, map)),
+                };
+                bus_can_drive(map, &edits, from, pos)
+                    && to
+                        .map(|to| bus_can_drive(map, &edits, pos, to))
+                        .unwrap_or(true)
+            })
+        };
+        if !reachable {
+            stops.push(*ts);
+        }
+    }
+    stops
+}
+
+/// Can a bus drive between two positions? Like `Edits::turn_allowed`, a filter anywhere along a
+/// road closes all of it, so a stop on a road with a filter can't be served without turning around.
+fn bus_can_drive(map: &Map, edits: &Edits, from: Position, to: Position) -> bool {
+    if edits.roads.contains_key(&from.lane().road) || edits.roads.contains_key(&to.lane().road) {
+        return false;
+    }
+    if from.lane() == to.lane() && from.dist_along() <= to.dist_along() {
+        return true;
+    }
+    bus_can_reach(map, edits, vec![from.lane()], |l| l == to.lane())
+}
+
+/// Starting from the end of any of these lanes, can a bus reach the start of a lane matching the
+/// goal? One-ways, filters, and banned turns all apply.
+fn bus_can_reach<F: Fn(LaneID) -> bool>(
+    map: &Map,
+    edits: &Edits,
+    starts: Vec<LaneID>,
+    is_goal: F,
+) -> bool {
+    let mut visited: BTreeSet<LaneID> = starts.iter().cloned().collect();
+    let mut queue = starts;
+    while let Some(current) = queue.pop() {
+        for turn in map.get_turns_for(current, PathConstraints::Bus) {
+            let next = turn.id.dst;
+            if !edits.turn_allowed(turn.id, map) {
+                continue;
+            }
+            // Check before visited, in case the bus has to loop back around to a lane it started on
+            if is_goal(next) {
+                return true;
+            }
+            if visited.insert(next) {
+                queue.push(next);
+            }
+        }
+    }
+    false
+}
+
 fn launch_advanced(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Transition {
-    let mut choices = vec![Choice::string("Automatically place modal filters")];
+    let mut choices = vec![
+        Choice::string("Automatically place modal filters"),
+        Choice::string("Copy filters from another neighbourhood"),
+        Choice::string("Remove all filters from this neighbourhood"),
+        Choice::string("Show which cells can reach each other"),
+        Choice::string("Find filters that don't change any route"),
+        Choice::string("Rank filters by how much traffic they stop"),
+        Choice::string("Check filters along a bus route"),
+        Choice::string("Import speed limits from a CSV file"),
+    ];
     if !app.partitioning().custom_boundaries.contains_key(&id) {
         choices.push(Choice::string("Customize boundary (for drawing only)"));
         choices.push(Choice::string("Convert to freehand area"));
@@ -447,6 +1342,24 @@ fn launch_advanced(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Transi
                     format!("Converted from {:?}", id),
                     app.partitioning().get_info(id).block.polygon.clone(),
                 ))
+            } else if choice == "Copy filters from another neighbourhood" {
+                choose_neighbourhood_to_copy(ctx, app, id)
+            } else if choice == "Remove all filters from this neighbourhood" {
+                remove_all_filters(ctx, app, id)
+            } else if choice == "Show which cells can reach each other" {
+                Transition::Replace(super::reachability::ReachabilityMatrix::new_state(
+                    ctx, app, id,
+                ))
+            } else if choice == "Find filters that don't change any route" {
+                find_redundant_filters(ctx, app, id)
+            } else if choice == "Rank filters by how much traffic they stop" {
+                Transition::Replace(super::filter_ranking::FilterRanking::new_state(
+                    ctx, app, id,
+                ))
+            } else if choice == "Check filters along a bus route" {
+                choose_bus_route(ctx, app)
+            } else if choice == "Import speed limits from a CSV file" {
+                import_speed_limits(ctx)
             } else {
                 Transition::Replace(ChooseSomething::new_state(
                     ctx,
@@ -476,14 +1389,302 @@ fn launch_advanced(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Transi
     ))
 }
 
+fn import_speed_limits(ctx: &mut EventCtx) -> Transition {
+    Transition::Replace(FilePicker::new_state(
+        ctx,
+        None,
+        Box::new(|ctx, app, maybe_path| {
+            let path = if let Ok(Some(path)) = maybe_path {
+                path
+            } else {
+                return Transition::Pop;
+            };
+            match crate::logic::map_edits::import_speed_limits(ctx, app, path) {
+                Ok(unmatched) => {
+                    let mut lines = Vec::new();
+                    if unmatched.is_empty() {
+                        lines.push("Every row matched a road.".to_string());
+                    } else {
+                        lines.push(format!("{} rows didn't match:", unmatched.len()));
+                        // Don't make an enormous popup
+                        let max = 20;
+                        let extra = unmatched.len().saturating_sub(max);
+                        lines.extend(unmatched.into_iter().take(max));
+                        if extra > 0 {
+                            lines.push(format!("... and {extra} more"));
+                        }
+                    }
+                    Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::Recreate,
+                        Transition::Push(PopupMsg::new_state(ctx, "Speed limits imported", lines)),
+                    ])
+                }
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![
+                        format!("Couldn't import speed limits: {err}"),
+                        "The CSV needs an osm_way_id column, and a mph or kph column.".to_string(),
+                    ],
+                )),
+            }
+        }),
+    ))
+}
+
+fn choose_bus_route(ctx: &mut EventCtx, app: &App) -> Transition {
+    let mut routes = BTreeSet::new();
+    for road in app.per_map.map.all_roads() {
+        routes.extend(
+            app.per_map
+                .map
+                .get_bus_routes_on_road(road.id)
+                .iter()
+                .cloned(),
+        );
+    }
+    if routes.is_empty() {
+        return Transition::Replace(PopupMsg::new_state(
+            ctx,
+            "No bus routes",
+            vec!["OpenStreetMap doesn't have any bus routes in this area"],
+        ));
+    }
+
+    Transition::Replace(ChooseSomething::new_state(
+        ctx,
+        "Which bus route?",
+        routes.into_iter().map(Choice::string).collect(),
+        Box::new(|route, ctx, app| {
+            let map = &app.per_map.map;
+            let (road_filters, diagonal_filters) = app.edits().filters_on_bus_route(map, &route);
+            let describe = |filter_type: FilterType| {
+                if filter_type == FilterType::BusGate {
+                    "bus gate (OK)"
+                } else {
+                    "BLOCKS THE ROUTE"
+                }
+            };
+
+            let mut lines = Vec::new();
+            let mut num_blocking = 0;
+            for (r, filter_type) in road_filters {
+                if filter_type != FilterType::BusGate {
+                    num_blocking += 1;
+                }
+                lines.push(format!(
+                    "- {}: {}",
+                    super::road_name(app, map.get_r(r)),
+                    describe(filter_type)
+                ));
+            }
+            for (i, filter_type) in diagonal_filters {
+                if filter_type != FilterType::BusGate {
+                    num_blocking += 1;
+                }
+                lines.push(format!(
+                    "- Diagonal filter at {}: {}",
+                    map.get_i(i).name(app.opts.language.as_ref(), map),
+                    describe(filter_type)
+                ));
+            }
+
+            let summary = if lines.is_empty() {
+                format!("No filters along {}", route)
+            } else if num_blocking == 0 {
+                format!(
+                    "{} filters along {}, all passable by buses",
+                    lines.len(),
+                    route
+                )
+            } else {
+                format!(
+                    "{} of {} filters along {} block buses",
+                    num_blocking,
+                    lines.len(),
+                    route
+                )
+            };
+            lines.insert(0, summary);
+            Transition::Replace(PopupMsg::new_state(ctx, "Filters on bus route", lines))
+        }),
+    ))
+}
+
+fn choose_neighbourhood_to_copy(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Transition {
+    let center = app.partitioning().get_info(id).block.polygon.center();
+    let mut sources = Vec::new();
+    for (other, info) in app.partitioning().all_neighbourhoods() {
+        if *other == id {
+            continue;
+        }
+        let num_filters = info
+            .block
+            .perimeter
+            .interior
+            .iter()
+            .filter(|r| app.edits().roads.contains_key(*r))
+            .count();
+        if num_filters > 0 {
+            let dist = info.block.polygon.center().dist_to(center);
+            sources.push((dist, *other, num_filters));
+        }
+    }
+    if sources.is_empty() {
+        return Transition::Replace(PopupMsg::new_state(
+            ctx,
+            "Nothing to copy",
+            vec!["No other neighbourhood has filters yet"],
+        ));
+    }
+    sources.sort_by_key(|(dist, _, _)| *dist);
+
+    let choices = sources
+        .into_iter()
+        .map(|(dist, other, num_filters)| {
+            Choice::new(
+                format!(
+                    "Neighbourhood #{} ({} filters, {} away)",
+                    other.0,
+                    num_filters,
+                    dist.to_string(&app.opts.units)
+                ),
+                other,
+            )
+        })
+        .collect();
+
+    Transition::Replace(ChooseSomething::new_state(
+        ctx,
+        "Copy the filters from which neighbourhood?",
+        choices,
+        Box::new(move |from, ctx, app| {
+            let (num_placed, problems) = ctx.loading_screen("copy filters", |_, _| {
+                let from = Neighbourhood::new(app, from);
+                let to = Neighbourhood::new(app, id);
+                app.per_map.proposals.start_edit_group();
+                let result = copy_filters(app, &from, &to);
+                app.per_map.proposals.finish_edit_group();
+                result
+            });
+            redraw_all_filters(ctx, app);
+
+            let mut lines = vec![format!("Copied {} filters", num_placed)];
+            if !problems.is_empty() {
+                lines.push(String::new());
+                lines.extend(problems);
+            }
+            Transition::Multi(vec![
+                Transition::Pop,
+                Transition::Recreate,
+                Transition::Push(PopupMsg::new_state(ctx, "Copied filters", lines)),
+            ])
+        }),
+    ))
+}
+
+/// Lists the filters that could all be removed together without changing any route
+fn find_redundant_filters(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Transition {
+    let redundant = ctx.loading_screen("find redundant filters", |_, timer| {
+        let neighbourhood = Neighbourhood::new(app, id);
+        neighbourhood.redundant_filters(&app.per_map.map, app.edits(), timer)
+    });
+    let map = &app.per_map.map;
+    let lines = if redundant.is_empty() {
+        vec!["Every filter in this neighbourhood changes some route".to_string()]
+    } else {
+        let mut lines = vec![
+            format!(
+                "These {} filters have no routing effect. Removing all of them wouldn't change any shortcut or which streets drivers can reach:",
+                redundant.len()
+            ),
+            String::new(),
+        ];
+        for id in redundant {
+            lines.push(match id {
+                ID::Road(r) => format!("- {}", super::road_name(app, map.get_r(r))),
+                ID::Intersection(i) => format!(
+                    "- diagonal filter at {}",
+                    map.get_i(i).name(app.opts.language.as_ref(), map)
+                ),
+                _ => unreachable!(),
+            });
+        }
+        lines
+    };
+    Transition::Replace(PopupMsg::new_state(ctx, "Redundant filters", lines))
+}
+
+/// Removes every filter from the neighbourhood's interior, except for locked ones
+fn remove_all_filters(ctx: &mut EventCtx, app: &mut App, id: NeighbourhoodID) -> Transition {
+    let neighbourhood = Neighbourhood::new(app, id);
+    let mut num_locked = 0;
+    app.per_map.proposals.before_edit();
+    let edits = &mut mut_edits!(app);
+    edits.roads.retain(|r, filter| {
+        if !neighbourhood.interior_roads.contains(r) {
+            return true;
+        }
+        if filter.locked {
+            num_locked += 1;
+        }
+        filter.locked
+    });
+    for (i, list) in &mut edits.intersections {
+        if neighbourhood.interior_intersections.contains(i) {
+            list.retain(|filter| filter.locked);
+            num_locked += list.len();
+        }
+    }
+    edits.intersections.retain(|_, list| !list.is_empty());
+    app.per_map.proposals.cancel_empty_edit();
+    redraw_all_filters(ctx, app);
+
+    if num_locked == 0 {
+        return Transition::Multi(vec![Transition::Pop, Transition::Recreate]);
+    }
+    Transition::Multi(vec![
+        Transition::Pop,
+        Transition::Recreate,
+        Transition::Push(PopupMsg::new_state(
+            ctx,
+            "Locked filters kept",
+            vec![format!(
+                "{} locked filters weren't removed. Press L while hovering on one to unlock it.",
+                num_locked
+            )],
+        )),
+    ])
+}
+
 fn help() -> Vec<&'static str> {
     vec![
         "The colored cells show where it's possible to drive without leaving the neighbourhood.",
         "",
         "The darker red roads have more predicted shortcutting traffic.",
+        "Press V to switch between seeing the cells, only the shortcuts, and the cells for buses.",
+        "(Buses can pass through bus gates, so their cells may be bigger.)",
         "",
         "Hint: You can place filters at roads or intersections.",
+        "Hold Shift when clicking an intersection to add another diagonal filter there.",
         "Use the lasso tool to quickly sketch your idea.",
+        "Press F6 and draw around existing filters to change all of their types at once.",
+        "Press F7 and draw around some streets to place the filters that make them one cell.",
+        "Press F8 and click two cells to remove the filter between them.",
+        "Press N while hovering on a road to rename it.",
+        "Press L while hovering on a filter to lock it.",
+        "Press T while hovering on a filter to mark it as a trial.",
+        "Press A while hovering on a filter to rotate its icon.",
+        "Press G while hovering on a walking/cycling filter to build it from planters.",
+        "Right-click any road for a menu of changes to make there.",
+        "Press F to fit the whole neighbourhood on screen, or hold Shift and press F to fit just the filters and turn restrictions.",
+        "Press O while hovering on a cell to fade everything else, and again to stop.",
+        "Press K while hovering on a cell to pick its color. Colors are remembered for this map.",
+        "Press E while hovering on a road along the boundary to expand the neighbourhood across it.",
+        "",
+        "Hold Space to temporarily hide your changes and compare with the original map.",
+        "Once you're happy with the cells, lock the cell layout in the legend to be asked before any edit changes them.",
     ]
 }
 
@@ -511,14 +1712,23 @@ fn make_bottom_panel(
                 .disabled(app.edits().previous_version.is_none())
                 .hotkey(lctrl(Key::Z))
                 .build_widget(ctx, "undo"),
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/location.svg")
+                .hotkey(Key::F)
+                .build_widget(ctx, "fit neighbourhood"),
             Widget::col(vec![
                 // TODO Only count new filters, not existing
-                format!(
-                    "{} filters",
-                    app.edits().roads.len() + app.edits().intersections.len()
-                )
-                .text_widget(ctx),
+                format!("{} filters", app.edits().num_filters()).text_widget(ctx),
                 format!("{} road directions changed", app.edits().one_ways.len()).text_widget(ctx),
+                {
+                    let area = app.edits().planting_area_m2(&app.per_map.map);
+                    if area > 0.0 {
+                        format!("{} m² of new planting", area.round()).text_widget(ctx)
+                    } else {
+                        Widget::nothing()
+                    }
+                },
             ]),
         ]),
         Widget::vertical_separator(ctx),
@@ -554,9 +1764,38 @@ fn make_bottom_panel(
     BottomPanel::new(ctx, appwide_panel, row)
 }
 
+/// One-click buttons for switching back to filter types picked recently
+fn recent_filter_palette(ctx: &mut EventCtx, app: &App) -> Widget {
+    let enforcement = app.session.bus_gate_enforcement;
+    Widget::custom_row(
+        app.session
+            .recent_filter_types
+            .iter()
+            .filter(|ft| **ft != app.session.filter_type)
+            .map(|ft| {
+                let hide_color = ft.hide_color_enforced_by(enforcement);
+                ctx.style()
+                    .btn_plain
+                    .icon(ft.svg_path_enforced_by(enforcement))
+                    .image_dims(30.0)
+                    .image_color(
+                        RewriteColor::Change(hide_color, Color::CLEAR),
+                        ControlState::Default,
+                    )
+                    .tooltip(format!("Switch back to {}", ft.describe().to_lowercase()))
+                    .build_widget(ctx, super::recent_filter_action(*ft))
+                    .centered_vert()
+            })
+            .collect(),
+    )
+}
+
 fn edit_mode(ctx: &mut EventCtx, app: &App) -> Widget {
     let edit_mode = &app.session.edit_mode;
-    let hide_color = app.session.filter_type.hide_color();
+    let hide_color = app
+        .session
+        .filter_type
+        .hide_color_enforced_by(app.session.bus_gate_enforcement);
     let name = match app.session.filter_type {
         FilterType::WalkCycleOnly => "Modal filter -- walking/cycling only",
         FilterType::NoEntry => "Modal filter - no entry",
@@ -568,7 +1807,11 @@ fn edit_mode(ctx: &mut EventCtx, app: &App) -> Widget {
         Widget::custom_row(vec![
             ctx.style()
                 .btn_solid_primary
-                .icon(app.session.filter_type.svg_path())
+                .icon(
+                    app.session
+                        .filter_type
+                        .svg_path_enforced_by(app.session.bus_gate_enforcement),
+                )
                 .image_color(
                     RewriteColor::Change(hide_color, Color::CLEAR),
                     ControlState::Default,
@@ -597,6 +1840,7 @@ fn edit_mode(ctx: &mut EventCtx, app: &App) -> Widget {
                 .dropdown()
                 .build_widget(ctx, "Change modal filter")
                 .centered_vert(),
+            recent_filter_palette(ctx, app),
         ]),
         ctx.style()
             .btn_solid_primary
@@ -613,6 +1857,55 @@ fn edit_mode(ctx: &mut EventCtx, app: &App) -> Widget {
             })
             .build_widget(ctx, "Freehand filters")
             .centered_vert(),
+        ctx.style()
+            .btn_solid_primary
+            .icon("system/assets/tools/include.svg")
+            .disabled(matches!(edit_mode, EditMode::SelectFilters(_)))
+            .hotkey(Key::F6)
+            .tooltip_and_disabled({
+                let mut txt = Text::new();
+                txt.add_line(Line(Key::F6.describe()).fg(ctx.style().text_hotkey_color));
+                txt.append(Line(" - Select filters"));
+                txt.add_line(Line("Click and drag").fg(ctx.style().text_hotkey_color));
+                txt.append(Line(" around existing filters to change their type"));
+                txt
+            })
+            .build_widget(ctx, "Select filters")
+            .centered_vert(),
+        ctx.style()
+            .btn_solid_primary
+            .icon("system/assets/tools/pencil.svg")
+            .disabled(matches!(edit_mode, EditMode::SketchCell(_)))
+            .hotkey(Key::F7)
+            .tooltip_and_disabled({
+                let mut txt = Text::new();
+                txt.add_line(Line(Key::F7.describe()).fg(ctx.style().text_hotkey_color));
+                txt.append(Line(" - Sketch a cell"));
+                txt.add_line(Line("Click and drag").fg(ctx.style().text_hotkey_color));
+                txt.append(Line(
+                    " around streets that should form one cell, and filters will be placed to separate it",
+                ));
+                txt
+            })
+            .build_widget(ctx, "Sketch a cell")
+            .centered_vert(),
+        ctx.style()
+            .btn_solid_primary
+            .icon("system/assets/tools/eraser.svg")
+            .disabled(matches!(edit_mode, EditMode::ConnectCells(_)))
+            .hotkey(Key::F8)
+            .tooltip_and_disabled({
+                let mut txt = Text::new();
+                txt.add_line(Line(Key::F8.describe()).fg(ctx.style().text_hotkey_color));
+                txt.append(Line(" - Connect cells"));
+                txt.add_line(Line("Click").fg(ctx.style().text_hotkey_color));
+                txt.append(Line(
+                    " two neighbouring cells to remove the filter between them",
+                ));
+                txt
+            })
+            .build_widget(ctx, "Connect cells")
+            .centered_vert(),
         ctx.style()
             .btn_solid_primary
             .icon("system/assets/tools/one_ways.svg")
@@ -0,0 +1,122 @@
+use geom::{Polygon, Pt2D};
+use map_gui::tools::grey_out_map;
+use widgetry::{
+    Color, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, State, Text,
+    TextExt, Widget,
+};
+
+use crate::render::{colors, RenderCells};
+use crate::{App, CellReachability, Neighbourhood, NeighbourhoodID, Transition};
+
+/// A grid showing which cells a driver can reach from which other cells
+pub struct ReachabilityMatrix {
+    panel: Panel,
+}
+
+impl ReachabilityMatrix {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Box<dyn State<App>> {
+        let map = &app.per_map.map;
+        let neighbourhood = Neighbourhood::new(app, id);
+        let cell_colors = RenderCells::new(map, &neighbourhood).colors;
+        let matrix = neighbourhood.cell_reachability(map, app.edits());
+
+        let size = 24.0;
+        let mut batch = GeomBatch::new();
+        let square =
+            |batch: &mut GeomBatch, row: usize, column: usize, color: Color, label: &str| {
+                batch.push(
+                    color,
+                    Polygon::rectangle(size - 2.0, size - 2.0)
+                        .translate(size * column as f64, size * row as f64),
+                );
+                batch.append(
+                    Text::from(Line(label).fg(Color::BLACK))
+                        .render_autocropped(ctx)
+                        .scale_to_fit_height(0.5 * size)
+                        .centered_on(Pt2D::new(
+                            size * (column as f64 + 0.5) - 1.0,
+                            size * (row as f64 + 0.5) - 1.0,
+                        )),
+                );
+            };
+
+        // Label the rows and columns with the cell colors
+        for (idx, color) in cell_colors.iter().enumerate() {
+            let label = (idx + 1).to_string();
+            square(&mut batch, 0, idx + 1, color.alpha(1.0), &label);
+            square(&mut batch, idx + 1, 0, color.alpha(1.0), &label);
+        }
+        let mut num_unreachable = 0;
+        for (from, row) in matrix.iter().enumerate() {
+            for (to, reachability) in row.iter().enumerate() {
+                let (color, label) = match reachability {
+                    CellReachability::Internal => (Color::grey(0.8), "="),
+                    CellReachability::External => (*colors::POROUS, "E"),
+                    CellReachability::Unreachable => {
+                        num_unreachable += 1;
+                        (*colors::IMPERMEABLE, "X")
+                    }
+                };
+                square(&mut batch, from + 1, to + 1, color, label);
+            }
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Which cells can drivers reach?")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Rows are where a trip starts, and columns are where it ends.".text_widget(ctx),
+            "= means the same cell".text_widget(ctx),
+            "E means reachable only by leaving the neighbourhood and driving around the perimeter"
+                .text_widget(ctx),
+            "X means unreachable by car".text_widget(ctx),
+            batch.into_widget(ctx),
+        ];
+        if num_unreachable > 0 {
+            col.push(
+                Line(format!(
+                    "{} pairs of cells aren't connected at all. Check one-ways near the borders.",
+                    num_unreachable
+                ))
+                .fg(Color::RED)
+                .into_widget(ctx),
+            );
+        }
+
+        Box::new(Self {
+            panel: Panel::new_builder(Widget::col(col))
+                .keyboard_navigation()
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for ReachabilityMatrix {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+        if ctx.input.pressed(Key::Escape) || self.panel.clicked_outside(ctx) {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,124 @@
+use geom::Polygon;
+use map_model::{IntersectionID, RoadID};
+use widgetry::tools::{ChooseSomething, PopupMsg};
+use widgetry::{Choice, EventCtx};
+
+use super::{EditMode, EditOutcome};
+use crate::{mut_edits, redraw_all_filters, App, Enforcement, FilterType, Transition};
+
+pub fn event(ctx: &mut EventCtx, app: &mut App) -> EditOutcome {
+    if let EditMode::SelectFilters(ref mut lasso) = app.session.edit_mode {
+        if let Some(polygon) = lasso.event(ctx) {
+            // Reset the tool
+            app.session.edit_mode = EditMode::Filters;
+            choose_new_type(ctx, app, polygon)
+        } else {
+            // Do this instead of EditOutcome::Nothing to interrupt other processing
+            EditOutcome::Transition(Transition::Keep)
+        }
+    } else {
+        unreachable!()
+    }
+}
+
+fn choose_new_type(ctx: &mut EventCtx, app: &App, polygon: Polygon) -> EditOutcome {
+    let map = &app.per_map.map;
+    let mut roads: Vec<RoadID> = Vec::new();
+    let mut intersections: Vec<IntersectionID> = Vec::new();
+    let mut num_locked = 0;
+    for (r, filter) in &app.edits().roads {
+        if let Ok((pt, _)) = map.get_r(*r).center_pts.dist_along(filter.dist) {
+            if polygon.contains_pt(pt) {
+                if filter.locked {
+                    num_locked += 1;
+                } else {
+                    roads.push(*r);
+                }
+            }
+        }
+    }
+    for (i, filters) in &app.edits().intersections {
+        if filters.iter().any(|filter| {
+            filter
+                .geometry(map)
+                .middle()
+                .map(|pt| polygon.contains_pt(pt))
+                .unwrap_or(false)
+        }) {
+            if app.edits().is_intersection_locked(*i) {
+                num_locked += 1;
+            } else {
+                intersections.push(*i);
+            }
+        }
+    }
+
+    if roads.is_empty() && intersections.is_empty() {
+        return EditOutcome::error(
+            ctx,
+            if num_locked == 0 {
+                "No filters selected"
+            } else {
+                "All of the selected filters are locked"
+            },
+        );
+    }
+
+    let count = roads.len() + intersections.len();
+    EditOutcome::Transition(Transition::Push(ChooseSomething::new_state(
+        ctx,
+        format!("Change {} filters to...", count),
+        vec![
+            Choice::new(
+                "Modal filter -- walking/cycling only",
+                (FilterType::WalkCycleOnly, Enforcement::Camera),
+            ),
+            Choice::new("No entry", (FilterType::NoEntry, Enforcement::Camera)),
+            Choice::new(
+                "Bus gate (camera)",
+                (FilterType::BusGate, Enforcement::Camera),
+            ),
+            Choice::new(
+                "Bus gate (physical barrier)",
+                (FilterType::BusGate, Enforcement::Physical),
+            ),
+            Choice::new(
+                "School street",
+                (FilterType::SchoolStreet, Enforcement::Camera),
+            ),
+        ],
+        Box::new(move |(filter_type, enforcement), ctx, app| {
+            // All of the changes happen in one edit, so one undo reverts them
+            app.per_map.proposals.before_edit();
+            let edits = &mut mut_edits!(app);
+            for r in &roads {
+                let filter = edits.roads.get_mut(r).unwrap();
+                filter.filter_type = filter_type;
+                filter.enforcement = enforcement;
+                filter.user_modified = true;
+            }
+            for i in &intersections {
+                for filter in edits.intersections.get_mut(i).unwrap() {
+                    filter.filter_type = filter_type;
+                    filter.enforcement = enforcement;
+                    filter.user_modified = true;
+                }
+            }
+            app.per_map.proposals.cancel_empty_edit();
+            redraw_all_filters(ctx, app);
+
+            let mut transitions = vec![Transition::Pop, Transition::Recreate];
+            if num_locked > 0 {
+                transitions.push(Transition::Push(PopupMsg::new_state(
+                    ctx,
+                    "Some filters unchanged",
+                    vec![format!(
+                        "{} locked filters weren't changed. Unlock them first.",
+                        num_locked
+                    )],
+                )));
+            }
+            Transition::Multi(transitions)
+        }),
+    )))
+}
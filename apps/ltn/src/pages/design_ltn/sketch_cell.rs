@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use geom::Polygon;
+use map_model::RoadID;
+use widgetry::EventCtx;
+
+use super::freehand_filters::{add_road_filter, resolve_skipped_roads};
+use super::{EditMode, EditOutcome};
+use crate::{logic, App, Neighbourhood, Transition};
+
+pub fn event(ctx: &mut EventCtx, app: &mut App, neighbourhood: &Neighbourhood) -> EditOutcome {
+    if let EditMode::SketchCell(ref mut lasso) = app.session.edit_mode {
+        if let Some(polygon) = lasso.event(ctx) {
+            // Reset the tool
+            app.session.edit_mode = EditMode::Filters;
+            realize_cell(ctx, app, neighbourhood, polygon)
+        } else {
+            // Do this instead of EditOutcome::Nothing to interrupt other processing
+            EditOutcome::Transition(Transition::Keep)
+        }
+    } else {
+        unreachable!()
+    }
+}
+
+/// The streets drawn around should become one cell, separate from everything else in the
+/// neighbourhood. Sketch more cells one at a time.
+fn realize_cell(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    neighbourhood: &Neighbourhood,
+    polygon: Polygon,
+) -> EditOutcome {
+    let map = &app.per_map.map;
+    let desired: BTreeMap<RoadID, usize> = neighbourhood
+        .interior_roads
+        .iter()
+        .filter(|r| polygon.contains_pt(map.get_r(**r).center_pts.middle()))
+        .map(|r| (*r, 0))
+        .collect();
+    if desired.is_empty() {
+        return EditOutcome::error(
+            ctx,
+            "Draw around the streets inside the neighbourhood that should form one cell",
+        );
+    }
+
+    let filters = logic::filters_to_realize_cells(map, neighbourhood, app.edits(), &desired);
+    if filters.is_empty() {
+        return EditOutcome::error(ctx, "Those streets are already separated from the rest");
+    }
+
+    let mut oneways = Vec::new();
+    let mut bus_roads = Vec::new();
+    app.per_map.proposals.start_edit_group();
+    for (r, dist) in filters {
+        add_road_filter(ctx, app, r, dist, &mut oneways, &mut bus_roads);
+    }
+    // This finishes the edit group
+    resolve_skipped_roads(ctx, app, oneways, bus_roads)
+}
@@ -1,11 +1,12 @@
 use geom::{Speed, UnitFmt};
+use map_model::{Direction, Road, RoadID};
 use widgetry::mapspace::{World, WorldOutcome};
-use widgetry::tools::ColorLegend;
-use widgetry::{EventCtx, Text, Widget};
+use widgetry::tools::{ChooseSomething, ColorLegend};
+use widgetry::{Choice, EventCtx, Key, Line, Text, Transition, Widget};
 
 use super::{EditOutcome, Obj};
 use crate::render::colors;
-use crate::{logic, App, Neighbourhood};
+use crate::{logic, mut_edits, App, DirectedSpeedLimit, Neighbourhood};
 
 pub fn widget(ctx: &mut EventCtx) -> Widget {
     ColorLegend::categories(
@@ -30,7 +31,33 @@ pub fn make_world(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood)
         .chain(neighbourhood.perimeter_roads.iter())
     {
         let road = map.get_r(*r);
-        let s = road.speed_limit.to_miles_per_hour().round();
+        let limit = app.edits().speed_limits.get(r);
+        // Color by the faster direction
+        let s = limit
+            .map(|limit| limit.fwd.max(limit.back))
+            .unwrap_or(road.speed_limit)
+            .to_miles_per_hour()
+            .round();
+
+        let mut tooltip = match limit {
+            Some(limit) => Text::from_multiline(vec![
+                Line(format!(
+                    "Speed limit {} is {}",
+                    direction_label(app, road, Direction::Fwd),
+                    describe(limit.fwd)
+                )),
+                Line(format!(
+                    "Speed limit {} is {}",
+                    direction_label(app, road, Direction::Back),
+                    describe(limit.back)
+                )),
+            ]),
+            None => Text::from(format!(
+                "Current speed limit is {}",
+                describe(road.speed_limit)
+            )),
+        };
+        tooltip.add_line(Line("Press D to set a different limit in each direction").small());
 
         world
             .add(Obj::Road(*r))
@@ -45,11 +72,8 @@ pub fn make_world(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood)
                 colors::SPEED_LIMITS[3]
             })
             .hover_color(colors::HOVER)
-            .tooltip(Text::from(format!(
-                "Current speed limit is {} ({})",
-                road.speed_limit.to_string(&UnitFmt::imperial()),
-                road.speed_limit.to_string(&UnitFmt::metric()),
-            )))
+            .tooltip(tooltip)
+            .hotkey(Key::D, "per direction")
             .clickable()
             .build(ctx);
     }
@@ -65,19 +89,105 @@ pub fn handle_world_outcome(
 ) -> EditOutcome {
     match outcome {
         WorldOutcome::ClickedObject(Obj::Road(r)) => {
-            if app.per_map.map.get_r(r).speed_limit == Speed::miles_per_hour(20.0) {
+            if app.per_map.map.get_r(r).speed_limit == Speed::miles_per_hour(20.0)
+                && !app.edits().speed_limits.contains_key(&r)
+            {
                 return EditOutcome::Nothing;
             }
 
-            let mut edits = app.per_map.map.get_edits().clone();
-            edits.commands.push(app.per_map.map.edit_road_cmd(r, |new| {
-                new.speed_limit = Speed::miles_per_hour(20.0);
-            }));
-
-            logic::map_edits::modify_road(ctx, app, r, edits);
-
+            let twenty = Speed::miles_per_hour(20.0);
+            set_speed_limit(
+                ctx,
+                app,
+                r,
+                DirectedSpeedLimit {
+                    fwd: twenty,
+                    back: twenty,
+                },
+            );
             EditOutcome::UpdateAll
         }
+        WorldOutcome::Keypress("per direction", Obj::Road(r)) => {
+            let road = app.per_map.map.get_r(r);
+            let fwd = direction_label(app, road, Direction::Fwd);
+            let back = direction_label(app, road, Direction::Back);
+            let mut choices = Vec::new();
+            for (mph1, mph2) in [(20.0, 30.0), (30.0, 20.0), (20.0, 20.0), (30.0, 30.0)] {
+                let limit = DirectedSpeedLimit {
+                    fwd: Speed::miles_per_hour(mph1),
+                    back: Speed::miles_per_hour(mph2),
+                };
+                choices.push(Choice::new(
+                    if mph1 == mph2 {
+                        format!("{}mph in both directions", mph1)
+                    } else {
+                        format!("{}mph {}, {}mph {}", mph1, fwd, mph2, back)
+                    },
+                    limit,
+                ));
+            }
+
+            EditOutcome::Transition(Transition::Push(ChooseSomething::new_state(
+                ctx,
+                "Set the speed limit in each direction",
+                choices,
+                Box::new(move |limit, ctx, app| {
+                    set_speed_limit(ctx, app, r, limit);
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }),
+            )))
+        }
         _ => EditOutcome::Nothing,
     }
 }
+
+pub fn set_speed_limit(ctx: &mut EventCtx, app: &mut App, r: RoadID, limit: DirectedSpeedLimit) {
+    let mut edits = app.per_map.map.get_edits().clone();
+    edits.commands.push(app.per_map.map.edit_road_cmd(r, |new| {
+        new.speed_limit = limit.slowest();
+    }));
+
+    // This calls before_edit
+    logic::map_edits::modify_road(ctx, app, r, edits);
+    if limit.fwd == limit.back {
+        mut_edits!(app).speed_limits.remove(&r);
+    } else {
+        mut_edits!(app).speed_limits.insert(r, limit);
+    }
+    app.per_map.draw_speed_limit_edits = crate::render::render_speed_limit_edits(
+        ctx,
+        &app.per_map.map,
+        app.edits(),
+        &app.opts.units,
+    );
+}
+
+fn describe(speed: Speed) -> String {
+    format!(
+        "{} ({})",
+        speed.to_string(&UnitFmt::imperial()),
+        speed.to_string(&UnitFmt::metric())
+    )
+}
+
+/// Describe a direction of a road by the next road it heads towards
+fn direction_label(app: &App, road: &Road, dir: Direction) -> String {
+    let map = &app.per_map.map;
+    let name = app.edits().get_road_name(road, app.opts.language.as_ref());
+    let i = match dir {
+        Direction::Fwd => road.dst_i,
+        Direction::Back => road.src_i,
+    };
+    for r in &map.get_i(i).roads {
+        let other = app
+            .edits()
+            .get_road_name(map.get_r(*r), app.opts.language.as_ref());
+        if *r != road.id && other != name && other != "???" {
+            return format!("towards {}", other);
+        }
+    }
+    match dir {
+        Direction::Fwd => "forwards".to_string(),
+        Direction::Back => "backwards".to_string(),
+    }
+}
@@ -0,0 +1,396 @@
+//! A guided flow for people new to the tool. It walks through picking a neighbourhood, seeing the
+//! shortcuts through it, placing filters automatically, checking emergency access, and saving,
+//! leaving behind a starter plan to refine in the normal design mode.
+
+use widgetry::mapspace::{World, WorldOutcome};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use super::design_ltn::fit_neighbourhood;
+use crate::logic::AutoFilterHeuristic;
+use crate::render::colors;
+use crate::{
+    mut_edits, pages, redraw_all_filters, App, Enforcement, FilterType, Neighbourhood,
+    NeighbourhoodID, Transition,
+};
+
+pub struct FilterWizard {
+    step: Step,
+    panel: Panel,
+    world: World<NeighbourhoodID>,
+    fade_irrelevant: Drawable,
+    draw_shortcuts: Drawable,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    PickNeighbourhood,
+    ThroughRoutes(NeighbourhoodID),
+    SuggestFilters(NeighbourhoodID),
+    EmergencyAccess(NeighbourhoodID),
+    Save(NeighbourhoodID),
+}
+
+impl Step {
+    fn number(self) -> usize {
+        match self {
+            Step::PickNeighbourhood => 1,
+            Step::ThroughRoutes(_) => 2,
+            Step::SuggestFilters(_) => 3,
+            Step::EmergencyAccess(_) => 4,
+            Step::Save(_) => 5,
+        }
+    }
+
+    fn next(self) -> Step {
+        match self {
+            Step::PickNeighbourhood => unreachable!(),
+            Step::ThroughRoutes(id) => Step::SuggestFilters(id),
+            Step::SuggestFilters(id) => Step::EmergencyAccess(id),
+            Step::EmergencyAccess(id) | Step::Save(id) => Step::Save(id),
+        }
+    }
+
+    fn previous(self) -> Step {
+        match self {
+            Step::PickNeighbourhood | Step::ThroughRoutes(_) => Step::PickNeighbourhood,
+            Step::SuggestFilters(id) => Step::ThroughRoutes(id),
+            Step::EmergencyAccess(id) => Step::SuggestFilters(id),
+            Step::Save(id) => Step::EmergencyAccess(id),
+        }
+    }
+}
+
+impl FilterWizard {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        Self::step(ctx, app, Step::PickNeighbourhood)
+    }
+
+    fn step(ctx: &mut EventCtx, app: &mut App, step: Step) -> Box<dyn State<App>> {
+        let mut state = Self {
+            step,
+            panel: Panel::empty(ctx),
+            world: World::new(),
+            fade_irrelevant: Drawable::empty(ctx),
+            draw_shortcuts: Drawable::empty(ctx),
+        };
+
+        let contents = match step {
+            Step::PickNeighbourhood => {
+                app.per_map.current_neighbourhood = None;
+                for (id, info) in app.partitioning().all_neighbourhoods() {
+                    state
+                        .world
+                        .add(*id)
+                        .hitbox(info.block.polygon.clone())
+                        .draw_color(Color::YELLOW.alpha(0.2))
+                        .hover_alpha(0.5)
+                        .clickable()
+                        .build(ctx);
+                }
+                state.world.initialize_hover(ctx);
+
+                vec![Text::from(
+                    "Click the area you'd like to make low-traffic. Areas are bounded by main roads, which keep carrying through-traffic.",
+                )
+                .wrap_to_pct(ctx, 20)
+                .into_widget(ctx)]
+            }
+            Step::ThroughRoutes(id) => {
+                let neighbourhood = state.focus(ctx, app, id);
+                let (quiet_streets, total_streets) = neighbourhood
+                    .shortcuts
+                    .quiet_and_total_streets(&neighbourhood);
+                vec![
+                    Text::from_multiline(vec![
+                        Line(format!(
+                            "Drivers could take {} different shortcuts through this area.",
+                            neighbourhood.shortcuts.paths.len()
+                        )),
+                        Line(format!(
+                            "{quiet_streets} of {total_streets} streets have no shortcuts through them already."
+                        )),
+                        Line(""),
+                        Line("Redder streets carry more shortcuts. These are the routes to break up.")
+                            .secondary(),
+                    ])
+                    .wrap_to_pct(ctx, 20)
+                    .into_widget(ctx),
+                    nav_buttons(ctx),
+                ]
+            }
+            Step::SuggestFilters(id) => {
+                let neighbourhood = state.focus(ctx, app, id);
+                vec![
+                    Text::from_multiline(vec![
+                        Line("Modal filters stop drivers passing through, while people walking and cycling still can."),
+                        Line(""),
+                        Line(format!(
+                            "{} filters in this area. {} shortcuts remain.",
+                            count_filters(app, &neighbourhood),
+                            neighbourhood.shortcuts.paths.len()
+                        )),
+                    ])
+                    .wrap_to_pct(ctx, 20)
+                    .into_widget(ctx),
+                    Widget::row(vec![
+                        "Strategy:".text_widget(ctx).centered_vert(),
+                        Widget::dropdown(
+                            ctx,
+                            "heuristic",
+                            AutoFilterHeuristic::Greedy,
+                            AutoFilterHeuristic::choices(),
+                        ),
+                    ]),
+                    ctx.style()
+                        .btn_outline
+                        .text("Suggest a filter")
+                        .build_def(ctx),
+                    nav_buttons(ctx),
+                ]
+            }
+            Step::EmergencyAccess(id) => {
+                let neighbourhood = state.focus(ctx, app, id);
+                let blocking = blocking_filters(app, &neighbourhood);
+                let disconnected = neighbourhood
+                    .cells
+                    .iter()
+                    .filter(|c| c.is_disconnected())
+                    .count();
+
+                let mut txt = Text::new();
+                if blocking == 0 {
+                    txt.add_line(
+                        "Every filter in this area lets emergency vehicles through. Camera-enforced bus gates only fine private drivers.",
+                    );
+                } else {
+                    txt.add_line(format!(
+                        "{blocking} filters physically block emergency vehicles. Fire and ambulance services usually need removable bollards or a camera-enforced gate instead."
+                    ));
+                }
+                if disconnected > 0 {
+                    txt.add_line(Line(""));
+                    txt.add_line(
+                        Line(format!(
+                            "{disconnected} parts of this area can't be reached by any vehicle at all"
+                        ))
+                        .fg(colors::DISCONNECTED_CELL.alpha(1.0)),
+                    );
+                }
+
+                vec![
+                    txt.wrap_to_pct(ctx, 20).into_widget(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("Use camera-enforced bus gates")
+                        .disabled(blocking == 0)
+                        .build_def(ctx),
+                    nav_buttons(ctx),
+                ]
+            }
+            Step::Save(id) => {
+                let neighbourhood = state.focus(ctx, app, id);
+                vec![
+                    Text::from_multiline(vec![
+                        Line(format!(
+                            "Your starter plan has {} filters and leaves {} shortcuts.",
+                            count_filters(app, &neighbourhood),
+                            neighbourhood.shortcuts.paths.len()
+                        )),
+                        Line("Save it now, then keep refining it by hand.").secondary(),
+                    ])
+                    .wrap_to_pct(ctx, 20)
+                    .into_widget(ctx),
+                    Widget::row(vec![
+                        ctx.style().btn_solid_primary.text("Save").build_def(ctx),
+                        ctx.style().btn_outline.text("Keep editing").build_def(ctx),
+                    ]),
+                    ctx.style().btn_plain.text("Back").build_def(ctx),
+                ]
+            }
+        };
+
+        let mut col = vec![Widget::row(vec![
+            Line(format!("Filter wizard: step {} of 5", step.number()))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        col.extend(contents);
+        state.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .keyboard_navigation()
+            .build(ctx);
+
+        Box::new(state)
+    }
+
+    /// Set up drawing for one neighbourhood, returning it
+    fn focus(&mut self, ctx: &mut EventCtx, app: &mut App, id: NeighbourhoodID) -> Neighbourhood {
+        app.per_map.current_neighbourhood = Some(id);
+        let neighbourhood = Neighbourhood::new(app, id);
+        if self.step == Step::ThroughRoutes(id) {
+            fit_neighbourhood(ctx, app, &neighbourhood);
+        }
+        self.fade_irrelevant = neighbourhood.fade_irrelevant(ctx, app);
+        self.draw_shortcuts = ctx.upload(neighbourhood.shortcuts.draw_heatmap(app));
+        neighbourhood
+    }
+
+    fn id(&self) -> NeighbourhoodID {
+        match self.step {
+            Step::PickNeighbourhood => unreachable!(),
+            Step::ThroughRoutes(id)
+            | Step::SuggestFilters(id)
+            | Step::EmergencyAccess(id)
+            | Step::Save(id) => id,
+        }
+    }
+}
+
+impl State<App> for FilterWizard {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            return match x.as_ref() {
+                "close" => Transition::Pop,
+                "Back" => Transition::Replace(Self::step(ctx, app, self.step.previous())),
+                "Next" => Transition::Replace(Self::step(ctx, app, self.step.next())),
+                "Suggest a filter" => {
+                    let id = self.id();
+                    let heuristic: AutoFilterHeuristic = self.panel.dropdown_value("heuristic");
+                    match ctx.loading_screen(
+                        "automatically filter a neighbourhood",
+                        |ctx, timer| {
+                            let neighbourhood = Neighbourhood::new(app, id);
+                            heuristic.apply(ctx, app, &neighbourhood, timer)
+                        },
+                    ) {
+                        Ok(()) => {
+                            let next = Transition::Replace(Self::step(ctx, app, self.step));
+                            let neighbourhood = Neighbourhood::new(app, id);
+                            match pages::check_cell_lock(ctx, app, &neighbourhood) {
+                                Some(prompt) => Transition::Multi(vec![next, prompt]),
+                                None => next,
+                            }
+                        }
+                        Err(err) => Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![err.to_string()],
+                        )),
+                    }
+                }
+                "Use camera-enforced bus gates" => {
+                    let neighbourhood = Neighbourhood::new(app, self.id());
+                    app.per_map.proposals.before_edit();
+                    let edits = mut_edits!(app);
+                    for r in &neighbourhood.interior_roads {
+                        if let Some(filter) = edits.roads.get_mut(r) {
+                            if !filter.locked && filter.filter_type.is_physical(filter.enforcement)
+                            {
+                                filter.filter_type = FilterType::BusGate;
+                                filter.enforcement = Enforcement::Camera;
+                                filter.user_modified = true;
+                            }
+                        }
+                    }
+                    for i in &neighbourhood.interior_intersections {
+                        for filter in edits.intersections.get_mut(i).into_iter().flatten() {
+                            if !filter.locked && filter.filter_type.is_physical(filter.enforcement)
+                            {
+                                filter.filter_type = FilterType::BusGate;
+                                filter.enforcement = Enforcement::Camera;
+                                filter.user_modified = true;
+                            }
+                        }
+                    }
+                    // Every physical filter might be locked
+                    app.per_map.proposals.cancel_empty_edit();
+                    redraw_all_filters(ctx, app);
+                    Transition::Replace(Self::step(ctx, app, self.step))
+                }
+                "Save" => {
+                    let blocks = app.partitioning().neighbourhood_to_blocks(self.id());
+                    Transition::Replace(crate::save::SaveDialog::new_state(
+                        ctx,
+                        app,
+                        crate::save::PreserveState::DesignLTN(blocks),
+                    ))
+                }
+                "Keep editing" => Transition::Replace(pages::DesignLTN::enter(ctx, app, self.id())),
+                _ => unreachable!(),
+            };
+        }
+
+        if self.step == Step::PickNeighbourhood {
+            if let WorldOutcome::ClickedObject(id) = self.world.event(ctx) {
+                return Transition::Replace(Self::step(ctx, app, Step::ThroughRoutes(id)));
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn recreate(&mut self, ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        Self::step(ctx, app, self.step)
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        app.draw_with_layering(g, |g| {
+            self.world.draw(g);
+            g.redraw(&self.fade_irrelevant);
+            g.redraw(&self.draw_shortcuts);
+        });
+        app.per_map.draw_major_road_labels.draw(g);
+        app.per_map.draw_all_filters.draw(g);
+        self.panel.draw(g);
+    }
+}
+
+fn nav_buttons(ctx: &mut EventCtx) -> Widget {
+    Widget::row(vec![
+        ctx.style().btn_plain.text("Back").build_def(ctx),
+        ctx.style().btn_solid_primary.text("Next").build_def(ctx),
+    ])
+}
+
+fn count_filters(app: &App, neighbourhood: &Neighbourhood) -> usize {
+    let edits = app.edits();
+    neighbourhood
+        .interior_roads
+        .iter()
+        .filter(|r| edits.roads.contains_key(r))
+        .count()
+        + neighbourhood
+            .interior_intersections
+            .iter()
+            .filter_map(|i| edits.intersections.get(i))
+            .map(|filters| filters.len())
+            .sum::<usize>()
+}
+
+/// Counts filters in the neighbourhood that fire engines and ambulances can't pass. Filters that
+/// are only signed or camera-enforced don't physically stop them.
+fn blocking_filters(app: &App, neighbourhood: &Neighbourhood) -> usize {
+    let edits = app.edits();
+    neighbourhood
+        .interior_roads
+        .iter()
+        .filter_map(|r| edits.roads.get(r))
+        .filter(|f| f.filter_type.is_physical(f.enforcement))
+        .count()
+        + neighbourhood
+            .interior_intersections
+            .iter()
+            .filter_map(|i| edits.intersections.get(i))
+            .flatten()
+            .filter(|f| f.filter_type.is_physical(f.enforcement))
+            .count()
+}
@@ -1,6 +1,8 @@
 use std::collections::BTreeSet;
 
-use geom::{Distance, Polygon, QuadTree};
+use anyhow::Result;
+
+use geom::{Distance, LonLat, Polygon, QuadTree, Ring};
 use map_gui::tools::EditPolygon;
 use map_model::RoadID;
 use widgetry::tools::Lasso;
@@ -176,6 +178,7 @@ impl State<App> for FreehandBoundary {
                         } else {
                             mut_partitioning!(app).add_custom_boundary(custom)
                         };
+                        crate::redraw_filter_summary(ctx, app);
                         // TODO Clicking is weird, acts like we click load proposal
                         return Transition::Replace(pages::DesignLTN::new_state(ctx, app, id));
                     }
@@ -264,6 +267,27 @@ fn help() -> Vec<&'static str> {
     ]
 }
 
+/// Read a boundary drawn in another tool from a GeoJSON file containing one WGS84 polygon. Returns
+/// a name for it (the feature's `name` property, or else the filename) and the polygon in
+/// map-space.
+pub fn load_geojson_boundary(app: &App, path: &str) -> Result<(String, Polygon)> {
+    let raw = String::from_utf8(abstio::slurp_file(path)?)?;
+    let mut list = LonLat::parse_geojson_polygons(raw)?;
+    if list.len() != 1 {
+        bail!(
+            "{path} should contain exactly one polygon, but has {}",
+            list.len()
+        );
+    }
+    let (gps_pts, name) = list.pop().unwrap();
+    let pts = match app.per_map.map.get_gps_bounds().try_convert(&gps_pts) {
+        Some(pts) => pts,
+        None => bail!("The boundary in {path} isn't entirely inside this map"),
+    };
+    let polygon = Ring::deduping_new(pts)?.into_polygon();
+    Ok((name.unwrap_or_else(|| abstutil::basename(path)), polygon))
+}
+
 fn polygon_to_custom_boundary(
     app: &App,
     boundary_polygon: Polygon,
@@ -4,6 +4,7 @@ mod crossings;
 mod customize_boundary;
 mod cycle_network;
 mod design_ltn;
+mod filter_wizard;
 mod freehand_boundary;
 mod per_resident_impact;
 mod pick_area;
@@ -16,8 +17,9 @@ pub use census::Census;
 pub use crossings::Crossings;
 pub use customize_boundary::CustomizeBoundary;
 pub use cycle_network::CycleNetwork;
-pub use design_ltn::{DesignLTN, EditMode};
-pub use freehand_boundary::FreehandBoundary;
+pub use design_ltn::{check_cell_lock, CheckedCells, DesignLTN, EditMode, ViewMode};
+pub use filter_wizard::FilterWizard;
+pub use freehand_boundary::{load_geojson_boundary, FreehandBoundary};
 pub use per_resident_impact::PerResidentImpact;
 pub use pick_area::{PickArea, PickAreaStyle};
 pub use predict_impact::ShowImpactResults;
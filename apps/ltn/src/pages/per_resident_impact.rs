@@ -52,11 +52,12 @@ impl PerResidentImpact {
         let fade_irrelevant = neighbourhood.fade_irrelevant(ctx, app);
         let mut label_roads = neighbourhood.perimeter_roads.clone();
         label_roads.extend(neighbourhood.interior_roads.clone());
-        let labels = DrawSimpleRoadLabels::new(
+        let labels = DrawSimpleRoadLabels::new_with_name_overrides(
             ctx,
             app,
             colors::LOCAL_ROAD_LABEL,
             Box::new(move |r| label_roads.contains(&r.id)),
+            app.edits().road_names.clone(),
         );
 
         let mut buildings_inside = BTreeSet::new();
@@ -1,5 +1,6 @@
+use map_gui::tools::FilePicker;
 use widgetry::mapspace::{World, WorldOutcome};
-use widgetry::tools::{ChooseSomething, PromptInput};
+use widgetry::tools::{ChooseSomething, PopupMsg, PromptInput};
 use widgetry::{Choice, Color, DrawBaselayer, EventCtx, GfxCtx, Outcome, Panel, State, Widget};
 
 use crate::components::{AppwidePanel, BottomPanel, Mode};
@@ -20,7 +21,11 @@ impl PickArea {
         if let pages::EditMode::Shortcuts(ref mut maybe_focus) = app.session.edit_mode {
             *maybe_focus = None;
         }
-        if let pages::EditMode::FreehandFilters(_) = app.session.edit_mode {
+        if let pages::EditMode::FreehandFilters(_)
+        | pages::EditMode::SelectFilters(_)
+        | pages::EditMode::SketchCell(_)
+        | pages::EditMode::ConnectCells(_) = app.session.edit_mode
+        {
             app.session.edit_mode = pages::EditMode::Filters;
         }
 
@@ -39,6 +44,7 @@ impl PickArea {
                     .btn_outline
                     .text("Manage custom boundaries")
                     .build_def(ctx),
+                ctx.style().btn_outline.text("Filter wizard").build_def(ctx),
             ]),
         );
 
@@ -76,13 +82,15 @@ impl State<App> for PickArea {
                 return change_draw_style(ctx);
             } else if x == "Manage custom boundaries" {
                 return manage_custom_boundary(ctx, app);
+            } else if x == "Filter wizard" {
+                return Transition::Push(pages::FilterWizard::new_state(ctx, app));
             } else {
                 unreachable!()
             }
         }
 
         if let WorldOutcome::ClickedObject(id) = self.world.event(ctx) {
-            return Transition::Push(pages::DesignLTN::new_state(ctx, app, id));
+            return Transition::Push(pages::DesignLTN::enter(ctx, app, id));
         }
 
         Transition::Keep
@@ -175,8 +183,10 @@ pub enum PickAreaStyle {
 fn help() -> Vec<&'static str> {
     vec![
         "Basic map navigation: click and drag to pan, swipe or scroll to zoom",
+        "Press Tab to move through a panel's controls and Enter to use one. Picking things on the map still needs a mouse.",
         "",
         "Click a neighbourhood to analyze it. You can adjust boundaries there.",
+        "New to this? The filter wizard walks through making a first plan step by step.",
     ]
 }
 
@@ -199,29 +209,64 @@ fn change_draw_style(ctx: &mut EventCtx) -> Transition {
     ))
 }
 
+#[derive(Clone, Copy)]
+enum ManageCustom {
+    CreateNew,
+    ImportGeoJson,
+    Open(NeighbourhoodID),
+}
+
 fn manage_custom_boundary(ctx: &mut EventCtx, app: &App) -> Transition {
-    let mut choices = vec![Choice::new("Create new", None)];
+    let mut choices = vec![
+        Choice::new("Create new", ManageCustom::CreateNew),
+        Choice::new("Import from a GeoJSON file", ManageCustom::ImportGeoJson),
+    ];
     for (id, custom) in &app.partitioning().custom_boundaries {
-        choices.push(Choice::new(&custom.name, Some(*id)));
+        choices.push(Choice::new(&custom.name, ManageCustom::Open(*id)));
     }
 
     Transition::Push(ChooseSomething::new_state(
         ctx,
         "Manage custom boundaries",
         choices,
-        Box::new(move |choice, ctx, app| {
-            if let Some(id) = choice {
-                Transition::Clear(vec![pages::DesignLTN::new_state(ctx, app, id)])
-            } else {
-                Transition::Replace(PromptInput::new_state(
-                    ctx,
-                    "Name the custom boundary",
-                    String::new(),
-                    Box::new(|name, ctx, app| {
-                        Transition::Clear(vec![pages::FreehandBoundary::blank(ctx, app, name)])
-                    }),
-                ))
+        Box::new(move |choice, ctx, app| match choice {
+            ManageCustom::Open(id) => {
+                Transition::Clear(vec![pages::DesignLTN::enter(ctx, app, id)])
             }
+            ManageCustom::CreateNew => Transition::Replace(PromptInput::new_state(
+                ctx,
+                "Name the custom boundary",
+                String::new(),
+                Box::new(|name, ctx, app| {
+                    Transition::Clear(vec![pages::FreehandBoundary::blank(ctx, app, name)])
+                }),
+            )),
+            ManageCustom::ImportGeoJson => Transition::Replace(FilePicker::new_state(
+                ctx,
+                None,
+                Box::new(|ctx, app, maybe_path| {
+                    let path = if let Ok(Some(path)) = maybe_path {
+                        path
+                    } else {
+                        return Transition::Pop;
+                    };
+                    match pages::load_geojson_boundary(app, &path) {
+                        Ok((name, polygon)) => {
+                            Transition::Clear(vec![pages::FreehandBoundary::new_from_polygon(
+                                ctx, app, name, polygon,
+                            )])
+                        }
+                        Err(err) => Transition::Replace(PopupMsg::new_state(
+                            ctx,
+                            "Couldn't import boundary",
+                            vec![
+                                err.to_string(),
+                                "The GeoJSON file needs exactly one polygon.".to_string(),
+                            ],
+                        )),
+                    }
+                }),
+            )),
         }),
     ))
 }
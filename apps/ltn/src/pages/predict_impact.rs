@@ -6,18 +6,19 @@ use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 use serde::Serialize;
 
-use map_gui::tools::checkbox_per_mode;
-use map_model::{PathV2, Road};
+use geom::{Duration, Polygon};
+use map_gui::tools::{checkbox_per_mode, grey_out_map};
+use map_model::{PathV2, Road, RoadID};
 use synthpop::make::ScenarioGenerator;
 use synthpop::{Scenario, TripMode};
-use widgetry::tools::{FileLoader, PopupMsg};
+use widgetry::tools::{ColorLegend, FileLoader, PopupMsg};
 use widgetry::{
-    Color, DrawBaselayer, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
-    Panel, Slider, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, SimpleState, Slider, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
 };
 
 use crate::components::{AppwidePanel, Mode};
-use crate::logic::impact::{end_of_day, Filters, Impact};
+use crate::logic::impact::{end_of_day, Filters, Impact, RushHourReport};
 use crate::render::colors;
 use crate::{App, Transition};
 
@@ -27,6 +28,7 @@ use crate::{App, Transition};
 pub struct ShowImpactResults {
     appwide_panel: AppwidePanel,
     left_panel: Panel,
+    draw_displaced_traffic: Option<Drawable>,
 }
 
 impl ShowImpactResults {
@@ -70,6 +72,17 @@ impl ShowImpactResults {
             });
         }
 
+        let displaced = if app.per_map.impact.show_displaced_traffic {
+            app.per_map.impact.displaced_traffic()
+        } else {
+            Vec::new()
+        };
+        let draw_displaced = app
+            .per_map
+            .impact
+            .show_displaced_traffic
+            .then(|| draw_displaced_traffic(ctx, app, &displaced));
+
         let contents = Widget::col(vec![
             Line("Impact prediction").small_heading().into_widget(ctx),
             Text::from(Line("This tool starts with a travel demand model, calculates the route every trip takes before and after changes, and displays volumes along roads")).wrap_to_pct(ctx, 20).into_widget(ctx),
@@ -83,11 +96,32 @@ impl ShowImpactResults {
                 Text::from(Line("Results may be wrong for various reasons. Interpret carefully.").bold_body()).wrap_to_pct(ctx, 20).into_widget(ctx),
             // TODO Dropdown for the scenario, and explain its source/limitations
             app.per_map.impact.filters.to_panel(ctx, app),
+            Toggle::checkbox(
+                ctx,
+                "only show displaced traffic",
+                None,
+                app.per_map.impact.show_displaced_traffic,
+            ),
+            if let Some((_, max)) = displaced.first() {
+                ColorLegend::gradient_with_width(
+                    ctx,
+                    &colors::DISPLACED_TRAFFIC,
+                    vec!["1 extra trip".to_string(), format!("{} extra trips", max)],
+                    150.0,
+                )
+            } else {
+                Widget::nothing()
+            },
             app.per_map
                 .impact
                 .compare_counts
                 .get_panel_widget(ctx)
-                .named("compare counts"),
+                .named("compare counts")
+                .hide(app.per_map.impact.show_displaced_traffic),
+            ctx.style()
+                .btn_outline
+                .text("Test rush hour")
+                .build_def(ctx),
             ctx.style()
                 .btn_outline
                 .text("Save before/after counts to files (JSON)")
@@ -109,6 +143,7 @@ impl ShowImpactResults {
         Box::new(Self {
             appwide_panel,
             left_panel,
+            draw_displaced_traffic: draw_displaced,
         })
     }
 }
@@ -126,6 +161,18 @@ impl State<App> for ShowImpactResults {
         }
         match self.left_panel.event(ctx) {
             Outcome::Clicked(x) => match x.as_ref() {
+                "Test rush hour" => {
+                    let report = ctx.loading_screen("test rush hour", |_, timer| {
+                        let per_map = &mut app.per_map;
+                        per_map.impact.rush_hour_report(
+                            &per_map.map,
+                            &per_map.proposals.current_proposal.edits,
+                            &per_map.routing_params_before_changes,
+                            timer,
+                        )
+                    });
+                    return Transition::Push(RushHourResults::new_state(ctx, app, report));
+                }
                 "Save before/after counts to files (JSON)" => {
                     let path1 = "counts_a.json";
                     let path2 = "counts_b.json";
@@ -175,6 +222,10 @@ impl State<App> for ShowImpactResults {
                     return Transition::Keep;
                 }
             },
+            Outcome::Changed(x) if x == "only show displaced traffic" => {
+                app.per_map.impact.show_displaced_traffic = self.left_panel.is_checked(&x);
+                return Transition::Recreate;
+            }
             Outcome::Changed(_) => {
                 // TODO The sliders should only trigger updates when the user lets go; way too slow
                 // otherwise
@@ -215,7 +266,11 @@ impl State<App> for ShowImpactResults {
         g.clear(app.cs.void_background);
         g.redraw(&app.per_map.draw_map.boundary_polygon);
         g.redraw(&app.per_map.draw_map.draw_all_areas);
-        app.per_map.impact.compare_counts.draw(g, app);
+        if let Some(ref draw) = self.draw_displaced_traffic {
+            g.redraw(draw);
+        } else {
+            app.per_map.impact.compare_counts.draw(g, app);
+        }
         app.per_map.draw_all_filters.draw(g);
 
         self.appwide_panel.draw(g);
@@ -228,6 +283,20 @@ impl State<App> for ShowImpactResults {
     }
 }
 
+/// Color roads gaining traffic after the changes by how many extra trips they take. `displaced`
+/// is sorted busiest first, as `Impact::displaced_traffic` returns it.
+fn draw_displaced_traffic(ctx: &EventCtx, app: &App, displaced: &[(RoadID, usize)]) -> Drawable {
+    let mut batch = GeomBatch::new();
+    let max = displaced.first().map(|(_, extra)| *extra).unwrap_or(1) as f64;
+    for (r, extra) in displaced {
+        batch.push(
+            colors::DISPLACED_TRAFFIC.eval(*extra as f64 / max),
+            app.per_map.map.get_r(*r).get_thick_polygon(),
+        );
+    }
+    ctx.upload(batch)
+}
+
 impl Filters {
     fn from_panel(panel: &Panel) -> Filters {
         let (p1, p2) = (
@@ -282,9 +351,120 @@ fn help() -> Vec<&'static str> {
         "because we don't know where trips begin and end.",
         "",
         "And note this tool doesn't predict traffic dissipation as people decide to not drive.",
+        "",
+        "To see where filtered traffic ends up, only show displaced traffic.",
     ]
 }
 
+struct RushHourResults;
+
+impl RushHourResults {
+    fn new_state(ctx: &mut EventCtx, app: &App, report: RushHourReport) -> Box<dyn State<App>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Morning rush hour").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from_multiline(report.describe(app).into_iter().map(Line).collect())
+                .wrap_to_pct(ctx, 50)
+                .into_widget(ctx),
+            trip_time_histogram(ctx, app, &report),
+        ]))
+        .keyboard_navigation()
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(RushHourResults))
+    }
+}
+
+impl SimpleState<App> for RushHourResults {
+    fn on_click(&mut self, _: &mut EventCtx, _: &mut App, x: &str, _: &mut Panel) -> Transition {
+        if x == "close" {
+            return Transition::Pop;
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
+/// Overlays two histograms of simulated driving trip times, before and after the edits, so the
+/// shift in the whole distribution is visible -- not just the average.
+fn trip_time_histogram(ctx: &mut EventCtx, app: &App, report: &RushHourReport) -> Widget {
+    let num_buckets = 12;
+    let (width, height) = (360.0, 120.0);
+
+    let max_time = report
+        .simulated_before
+        .iter()
+        .chain(report.simulated_after.iter())
+        .max()
+        .cloned()
+        .unwrap_or(Duration::ZERO);
+    if max_time == Duration::ZERO {
+        return Widget::nothing();
+    }
+    let bucket_size = max_time / (num_buckets as f64);
+    let bucket = |time: Duration| ((time / bucket_size) as usize).min(num_buckets - 1);
+
+    let mut before = vec![0; num_buckets];
+    let mut after = vec![0; num_buckets];
+    for time in &report.simulated_before {
+        before[bucket(*time)] += 1;
+    }
+    for time in &report.simulated_after {
+        after[bucket(*time)] += 1;
+    }
+    let max_count = before
+        .iter()
+        .chain(after.iter())
+        .max()
+        .cloned()
+        .unwrap_or(0);
+
+    let bar_width = width / (num_buckets as f64);
+    let mut batch = GeomBatch::new();
+    batch.push(Color::CLEAR, Polygon::rectangle(width, height));
+    for (counts, color) in [
+        (&before, *colors::PLAN_ROUTE_BEFORE),
+        (&after, *colors::PLAN_ROUTE_AFTER),
+    ] {
+        for (idx, count) in counts.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let bar_height = height * (*count as f64) / (max_count as f64);
+            batch.push(
+                color.alpha(0.5),
+                Polygon::rectangle(bar_width, bar_height)
+                    .translate(bar_width * (idx as f64), height - bar_height),
+            );
+        }
+    }
+
+    Widget::col(vec![
+        "Distribution of simulated trip times".text_widget(ctx),
+        batch.into_widget(ctx),
+        Widget::row(vec![
+            "0".text_widget(ctx),
+            max_time
+                .to_string(&app.opts.units)
+                .text_widget(ctx)
+                .align_right(),
+        ]),
+        Widget::row(vec![
+            ColorLegend::row(ctx, *colors::PLAN_ROUTE_BEFORE, "before"),
+            ColorLegend::row(ctx, *colors::PLAN_ROUTE_AFTER, "after"),
+        ]),
+    ])
+    .section(ctx)
+}
+
 struct ChangedRoutes {
     panel: Panel,
     // TODO Not sure what to precompute. Smallest memory would be the PathRequest.
@@ -338,6 +518,7 @@ impl ChangedRoutes {
                     .into_widget(ctx),
             ]))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .keyboard_navigation()
             .build(ctx),
             paths,
             current: 0,
@@ -445,7 +626,7 @@ struct ExportRow {
 impl ExportRow {
     fn new(r: &Road, app: &App) -> Self {
         Self {
-            road_name: r.get_name(None),
+            road_name: app.edits().get_road_name(r, None),
             osm_way_id: r.orig_id.osm_way_id.0,
             osm_intersection1: r.orig_id.i1.0,
             osm_intersection2: r.orig_id.i2.0,
@@ -1,6 +1,6 @@
 use geom::{Distance, Duration, Polygon};
 use map_gui::tools::{InputWaypoints, TripManagement, TripManagementState, WaypointID};
-use map_model::{PathConstraints, PathV2, PathfinderCache};
+use map_model::{Map, PathConstraints, PathStepV2, PathV2, PathfinderCache};
 use synthpop::{TripEndpoint, TripMode};
 use widgetry::mapspace::World;
 use widgetry::{
@@ -179,8 +179,9 @@ impl RoutePlanner {
 
         let mut paths: Vec<(PathV2, Color)> = Vec::new();
 
-        let driving_before_changes_time = {
+        let driving_before_changes = {
             let mut total_time = Duration::ZERO;
+            let mut total_dist = Distance::ZERO;
             let mut params = app.per_map.routing_params_before_changes.clone();
             params.main_road_penalty = app.session.main_road_penalty;
 
@@ -194,6 +195,7 @@ impl RoutePlanner {
                     })
                 {
                     total_time += path.estimate_duration(map, None, Some(params.main_road_penalty));
+                    total_dist += path_length(&path, map);
                     paths.push((path, *colors::PLAN_ROUTE_BEFORE));
                 } else {
                     ok = false;
@@ -202,14 +204,15 @@ impl RoutePlanner {
             }
 
             if ok {
-                Some(total_time)
+                Some((total_time, total_dist))
             } else {
                 None
             }
         };
+        let driving_before_changes_time = driving_before_changes.map(|(time, _)| time);
 
         // The route respecting the filters
-        let driving_after_changes_time = {
+        let driving_after_changes = {
             let mut params = map.routing_params().clone();
             app.edits().update_routing_params(&mut params);
             params.main_road_penalty = app.session.main_road_penalty;
@@ -217,6 +220,7 @@ impl RoutePlanner {
             let mut ok = true;
 
             let mut total_time = Duration::ZERO;
+            let mut total_dist = Distance::ZERO;
             let mut paths_after = Vec::new();
             for pair in self.waypoints.get_waypoints().windows(2) {
                 if let Some(path) = TripEndpoint::path_req(pair[0], pair[1], TripMode::Drive, map)
@@ -225,7 +229,13 @@ impl RoutePlanner {
                             .pathfind_with_params(map, req, params.clone())
                     })
                 {
-                    total_time += path.estimate_duration(map, None, Some(params.main_road_penalty));
+                    total_time += path.estimate_duration_with_speeds(
+                        map,
+                        None,
+                        Some(params.main_road_penalty),
+                        |dr| app.edits().speed_limit(map, dr),
+                    );
+                    total_dist += path_length(&path, map);
                     paths_after.push((path, *colors::PLAN_ROUTE_AFTER));
                 } else {
                     ok = false;
@@ -238,11 +248,12 @@ impl RoutePlanner {
             }
 
             if ok {
-                Some(total_time)
+                Some((total_time, total_dist))
             } else {
                 None
             }
         };
+        let driving_after_changes_time = driving_after_changes.map(|(time, _)| time);
 
         let biking_time = if app.session.show_walking_cycling_routes {
             // No custom params, but don't use the map's built-in bike CH. Changes to one-way
@@ -278,20 +289,33 @@ impl RoutePlanner {
 
         let walking_time = if app.session.show_walking_cycling_routes {
             // Same as above -- don't use the built-in CH.
+            let mut params = map.routing_params().clone();
+            app.edits().update_walking_params(&mut params);
+
             let mut total_time = Duration::ZERO;
             let mut ok = true;
             for pair in self.waypoints.get_waypoints().windows(2) {
                 if let Some(path) = TripEndpoint::path_req(pair[0], pair[1], TripMode::Walk, map)
                     .and_then(|req| {
-                        self.pathfinder_cache.pathfind_with_params(
-                            map,
-                            req,
-                            map.routing_params().clone(),
-                        )
+                        self.pathfinder_cache
+                            .pathfind_with_params(map, req, params.clone())
                     })
                 {
                     total_time +=
                         path.estimate_duration(map, Some(map_model::MAX_WALKING_SPEED), None);
+                    // The estimate only covers walking, so add the time spent at crossings
+                    for step in path.get_steps() {
+                        if let PathStepV2::Movement(mvmnt) | PathStepV2::ContraflowMovement(mvmnt) =
+                            step
+                        {
+                            if mvmnt.from.road == mvmnt.to.road {
+                                if let Some(delay) = params.crossing_penalties.get(&mvmnt.from.road)
+                                {
+                                    total_time += *delay;
+                                }
+                            }
+                        }
+                    }
                     paths.push((path, *colors::PLAN_ROUTE_WALK));
                 } else {
                     ok = false;
@@ -348,6 +372,22 @@ impl RoutePlanner {
                         .align_right(),
                 ])
             },
+            if let (Some((time1, dist1)), Some((time2, dist2))) =
+                (driving_before_changes, driving_after_changes)
+            {
+                if time1 == time2 {
+                    Widget::nothing()
+                } else {
+                    format!(
+                        "{}, {} after changes",
+                        signed_duration(time2 - time1),
+                        signed_distance(app, dist2 - dist1)
+                    )
+                    .text_widget(ctx)
+                }
+            } else {
+                Widget::nothing()
+            },
             if app.session.show_walking_cycling_routes {
                 Widget::col(vec![
                     // TODO Is the tooltip that important? "This cycling route doesn't avoid
@@ -480,3 +520,30 @@ fn help() -> Vec<&'static str> {
         "but you can adjust the slow-down factor to mimic rush hour conditions",
     ]
 }
+
+/// Roughly how far a path goes. Distance inside intersections is ignored.
+fn path_length(path: &PathV2, map: &Map) -> Distance {
+    path.get_steps()
+        .iter()
+        .map(|step| match step {
+            PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) => map.get_r(dr.road).length(),
+            PathStepV2::Movement(_) | PathStepV2::ContraflowMovement(_) => Distance::ZERO,
+        })
+        .sum()
+}
+
+fn signed_duration(d: Duration) -> String {
+    if d < Duration::ZERO {
+        format!("-{}", (-d).to_rounded_string(0))
+    } else {
+        format!("+{}", d.to_rounded_string(0))
+    }
+}
+
+fn signed_distance(app: &App, d: Distance) -> String {
+    if d < Distance::ZERO {
+        format!("-{}", (-d).to_string(&app.opts.units))
+    } else {
+        format!("+{}", d.to_string(&app.opts.units))
+    }
+}
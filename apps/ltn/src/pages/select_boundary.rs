@@ -55,7 +55,11 @@ impl SelectBoundary {
         if let pages::EditMode::Shortcuts(ref mut maybe_focus) = app.session.edit_mode {
             *maybe_focus = None;
         }
-        if let pages::EditMode::FreehandFilters(_) = app.session.edit_mode {
+        if let pages::EditMode::FreehandFilters(_)
+        | pages::EditMode::SelectFilters(_)
+        | pages::EditMode::SketchCell(_)
+        | pages::EditMode::ConnectCells(_) = app.session.edit_mode
+        {
             app.session.edit_mode = pages::EditMode::Filters;
         }
 
@@ -133,7 +137,11 @@ impl SelectBoundary {
         self.last_failed_change = None;
         self.draw_last_error = Drawable::empty(ctx);
 
-        match self.try_toggle_block(app, id) {
+        let result = self.try_toggle_block(app, id);
+        if result.is_ok() {
+            crate::redraw_filter_summary(ctx, app);
+        }
+        match result {
             Ok(Some(new_neighbourhood)) => {
                 return Transition::Replace(SelectBoundary::new_state(ctx, app, new_neighbourhood));
             }
@@ -263,6 +271,7 @@ impl SelectBoundary {
             }
 
             // Just redraw everything
+            crate::redraw_filter_summary(ctx, app);
             self.world = World::new();
             for id in app.partitioning().all_block_ids() {
                 self.add_block(ctx, app, id);
@@ -312,6 +321,7 @@ impl State<App> for SelectBoundary {
                     // back to a different neighbourhood than we started with. And also the original
                     // partitioning will have been lost!!!
                     mut_partitioning!(app) = self.orig_partitioning.clone();
+                    crate::redraw_filter_summary(ctx, app);
                     return Transition::Replace(pages::DesignLTN::new_state(ctx, app, self.id));
                 }
                 "Confirm" => {
@@ -1,12 +1,13 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
 use geom::{Bounds, Distance, Polygon};
 use map_gui::tools::Grid;
-use map_model::Map;
+use map_model::osm::RoadRank;
+use map_model::{Map, RoadID};
 use widgetry::{Color, GeomBatch};
 
 use crate::render::colors;
-use crate::Neighbourhood;
+use crate::{Cell, Neighbourhood};
 
 const RESOLUTION_M: f64 = 10.0;
 
@@ -17,6 +18,11 @@ pub struct RenderCells {
     pub colors: Vec<Color>,
 
     boundary_polygon: Polygon,
+    /// Remembered so the coloring can be redone later, matching a previous result
+    cell_roads: Vec<BTreeSet<RoadID>>,
+    adjacencies: HashSet<(usize, usize)>,
+    /// Index into `colors::CELLS` per cell
+    palette: Vec<usize>,
 }
 
 struct RenderCellsBuilder {
@@ -28,6 +34,9 @@ struct RenderCellsBuilder {
     bounds: Bounds,
 
     boundary_polygon: Polygon,
+    cell_roads: Vec<BTreeSet<RoadID>>,
+    adjacencies: HashSet<(usize, usize)>,
+    palette: Vec<usize>,
 }
 
 impl RenderCells {
@@ -35,7 +44,101 @@ impl RenderCells {
     /// a grid, and then extracts a polygon from the raster. The results don't look perfect, but
     /// it's fast.
     pub fn new(map: &Map, neighbourhood: &Neighbourhood) -> RenderCells {
-        RenderCellsBuilder::new(map, neighbourhood).finalize()
+        RenderCellsBuilder::new(map, neighbourhood, &neighbourhood.cells, false).finalize()
+    }
+
+    /// Like `new`, but for some other partitioning of the same neighbourhood
+    pub fn new_for_cells(map: &Map, neighbourhood: &Neighbourhood, cells: &[Cell]) -> RenderCells {
+        RenderCellsBuilder::new(map, neighbourhood, cells, false).finalize()
+    }
+
+    /// Like `new`, but only local streets (residential, unclassified, and similar) claim space for
+    /// their cell. Busier interior roads are left out, so the areas around them go to whichever
+    /// minor streets are nearby.
+    pub fn new_minor_roads_only(map: &Map, neighbourhood: &Neighbourhood) -> RenderCells {
+        RenderCellsBuilder::new(map, neighbourhood, &neighbourhood.cells, true).finalize()
+    }
+
+    /// Recolor cells so they look stable across edits and sessions. Colors somebody picked by hand
+    /// in `saved` come first; it maps roads to an index into `colors::CELLS`, and a cell takes the
+    /// first color saved for any of its roads. Since roads keep their IDs, these stick even as
+    /// cells get renumbered. Every other cell inherits the color of the cell in `previous` sharing
+    /// the most roads with it. Each old cell passes on its color at most once, so when a cell
+    /// splits, only the larger piece keeps it. Any hint is dropped if it clashes with a neighbour.
+    /// Call this before `shade_by_shortcuts`.
+    pub fn keep_colors(
+        &mut self,
+        previous: Option<&RenderCells>,
+        saved: Option<&BTreeMap<RoadID, usize>>,
+    ) {
+        let continuity = previous
+            .map(|previous| self.continuity_hints(previous))
+            .unwrap_or_default();
+        let saved = saved
+            .map(|saved| self.saved_hints(saved))
+            .unwrap_or_default();
+        if continuity.iter().chain(saved.iter()).all(|x| x.is_none()) {
+            return;
+        }
+        self.palette = color_cells(
+            self.cell_roads.len(),
+            &self.adjacencies,
+            &[saved.as_slice(), continuity.as_slice()],
+        );
+        for (idx, palette_idx) in self.palette.iter().enumerate() {
+            if self.colors[idx] != colors::DISCONNECTED_CELL {
+                self.colors[idx] = colors::CELLS[*palette_idx].alpha(0.8);
+            }
+        }
+    }
+
+    /// After an edit, most cells are usually unchanged. Per cell, which color from `previous`
+    /// should it keep?
+    fn continuity_hints(&self, previous: &RenderCells) -> Vec<Option<usize>> {
+        // (number of shared roads, new cell, old cell)
+        let mut overlaps = Vec::new();
+        for (new_idx, roads) in self.cell_roads.iter().enumerate() {
+            for (old_idx, old_roads) in previous.cell_roads.iter().enumerate() {
+                let shared = roads.intersection(old_roads).count();
+                if shared > 0 {
+                    overlaps.push((shared, new_idx, old_idx));
+                }
+            }
+        }
+        // Most overlap first. Break ties by index to stay deterministic.
+        overlaps.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        let mut hints: Vec<Option<usize>> = vec![None; self.cell_roads.len()];
+        let mut used_old = HashSet::new();
+        for (_, new_idx, old_idx) in overlaps {
+            if hints[new_idx].is_none() && !used_old.contains(&old_idx) {
+                hints[new_idx] = Some(previous.palette[old_idx]);
+                used_old.insert(old_idx);
+            }
+        }
+        hints
+    }
+
+    fn saved_hints(&self, saved: &BTreeMap<RoadID, usize>) -> Vec<Option<usize>> {
+        self.cell_roads
+            .iter()
+            .map(|roads| {
+                roads
+                    .iter()
+                    .filter_map(|r| saved.get(r))
+                    .find(|idx| **idx < colors::CELLS.len())
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// True if both results partition the neighbourhood's roads into the same cells
+    pub fn same_cells(&self, other: &RenderCells) -> bool {
+        self.cell_roads.len() == other.cell_roads.len()
+            && self
+                .cell_roads
+                .iter()
+                .all(|roads| other.cell_roads.contains(roads))
     }
 
     /// Draw cells as areas with different colors. The colors are meaningless, but the same color
@@ -50,6 +153,29 @@ impl RenderCells {
         batch
     }
 
+    /// Replace the qualitative colors with a gradient, so cells with more shortcuts through their
+    /// roads look darker. This counts the shortcuts drivers could take, not simulated traffic.
+    /// Disconnected cells keep their warning color.
+    pub fn shade_by_shortcuts(&mut self, neighbourhood: &Neighbourhood) {
+        let volumes: Vec<usize> = neighbourhood
+            .cells
+            .iter()
+            .map(|cell| {
+                cell.roads
+                    .keys()
+                    .map(|r| neighbourhood.shortcuts.count_per_road.get(*r))
+                    .sum()
+            })
+            .collect();
+        let max = volumes.iter().max().cloned().unwrap_or(0).max(1) as f64;
+        for (idx, volume) in volumes.into_iter().enumerate() {
+            if self.colors[idx] == colors::DISCONNECTED_CELL {
+                continue;
+            }
+            self.colors[idx] = colors::CELL_SHORTCUTS.eval(volume as f64 / max).alpha(0.8);
+        }
+    }
+
     /// Draw the boundary between cells as a thick outline. It's meant to look like the
     /// neighbourhood is split into disconnected islands.
     pub fn draw_island_outlines(&self) -> GeomBatch {
@@ -93,7 +219,12 @@ impl RenderCells {
 }
 
 impl RenderCellsBuilder {
-    fn new(map: &Map, neighbourhood: &Neighbourhood) -> RenderCellsBuilder {
+    fn new(
+        map: &Map,
+        neighbourhood: &Neighbourhood,
+        cells: &[Cell],
+        minor_only: bool,
+    ) -> RenderCellsBuilder {
         let boundary_polygon = neighbourhood.boundary_polygon.clone();
         // Make a 2D grid covering the polygon. Each tile in the grid contains a cell index, which
         // will become a color by the end. None means no cell is assigned yet.
@@ -106,9 +237,12 @@ impl RenderCellsBuilder {
 
         // Initially fill out the grid based on the roads in each cell
         let mut warn_leak = true;
-        for (cell_idx, cell) in neighbourhood.cells.iter().enumerate() {
+        for (cell_idx, cell) in cells.iter().enumerate() {
             for (r, interval) in &cell.roads {
                 let road = map.get_r(*r);
+                if minor_only && road.get_rank() != RoadRank::Local {
+                    continue;
+                }
                 // Some roads with a filter are _very_ short, and this fails. The connecting roads
                 // on either side should contribute a grid cell and wind up fine.
                 if let Ok(slice) = road
@@ -152,7 +286,7 @@ impl RenderCellsBuilder {
         // Also mark the boundary polygon, so we can prevent the diffusion from "leaking" outside
         // the area. The grid covers the rectangular bounds of the polygon. Rather than make an
         // enum with 3 cases, just assign a new index to mean "boundary."
-        let boundary_marker = neighbourhood.cells.len();
+        let boundary_marker = cells.len();
         for (pt, _) in
             geom::PolyLine::unchecked_new(boundary_polygon.get_outer_ring().clone().into_points())
                 .step_along(Distance::meters(RESOLUTION_M / 2.0), Distance::ZERO)
@@ -167,10 +301,15 @@ impl RenderCellsBuilder {
         }
 
         let adjacencies = diffusion(&mut grid, boundary_marker);
-        let mut cell_colors = color_cells(neighbourhood.cells.len(), adjacencies);
+        let num_cells = cells.len();
+        let palette = color_cells(num_cells, &adjacencies, &[]);
+        let mut cell_colors: Vec<Color> = palette
+            .iter()
+            .map(|idx| colors::CELLS[*idx].alpha(0.8))
+            .collect();
 
         // Color some special cells
-        for (idx, cell) in neighbourhood.cells.iter().enumerate() {
+        for (idx, cell) in cells.iter().enumerate() {
             if cell.is_disconnected() {
                 cell_colors[idx] = colors::DISCONNECTED_CELL;
             }
@@ -182,6 +321,12 @@ impl RenderCellsBuilder {
             bounds,
 
             boundary_polygon,
+            cell_roads: cells
+                .iter()
+                .map(|cell| cell.roads.keys().cloned().collect())
+                .collect(),
+            adjacencies,
+            palette,
         }
     }
 
@@ -190,6 +335,9 @@ impl RenderCellsBuilder {
             polygons_per_cell: Vec::new(),
             colors: Vec::new(),
             boundary_polygon: self.boundary_polygon,
+            cell_roads: self.cell_roads,
+            adjacencies: self.adjacencies,
+            palette: self.palette,
         };
 
         for (idx, color) in self.colors.into_iter().enumerate() {
@@ -302,19 +450,45 @@ fn diffusion(grid: &mut Grid<Option<usize>>, boundary_marker: usize) -> HashSet<
     adjacencies
 }
 
-fn color_cells(num_cells: usize, adjacencies: HashSet<(usize, usize)>) -> Vec<Color> {
+/// Returns an index into `colors::CELLS` per cell. Each list of `hints` can request a particular
+/// color for some cells. Earlier lists take priority, and every hint gets picked before any other
+/// cell. Hints are honored unless two adjacent cells want the same color. Lists may be shorter than
+/// the number of cells.
+fn color_cells(
+    num_cells: usize,
+    adjacencies: &HashSet<(usize, usize)>,
+    hints: &[&[Option<usize>]],
+) -> Vec<usize> {
+    let mut assigned_colors: Vec<Option<usize>> = vec![None; num_cells];
+    for list in hints {
+        for (this_idx, hint) in list.iter().enumerate() {
+            if assigned_colors[this_idx].is_some() {
+                continue;
+            }
+            if let Some(hint) = *hint {
+                let clashes = (0..num_cells).any(|other_idx| {
+                    adjacencies.contains(&(this_idx, other_idx))
+                        && assigned_colors[other_idx] == Some(hint)
+                });
+                if !clashes {
+                    assigned_colors[this_idx] = Some(hint);
+                }
+            }
+        }
+    }
+
     // This is the same greedy logic as Perimeter::calculate_coloring
-    let mut assigned_colors = Vec::new();
     for this_idx in 0..num_cells {
+        if assigned_colors[this_idx].is_some() {
+            continue;
+        }
         let mut available_colors: Vec<bool> =
             std::iter::repeat(true).take(colors::CELLS.len()).collect();
-        // Find all neighbors
+        // Find all neighbors that already have a color
         for other_idx in 0..num_cells {
             if adjacencies.contains(&(this_idx, other_idx)) {
-                // We assign colors in order, so any neighbor index smaller than us has been
-                // chosen
-                if other_idx < this_idx {
-                    available_colors[assigned_colors[other_idx]] = false;
+                if let Some(color) = assigned_colors[other_idx] {
+                    available_colors[color] = false;
                 }
             }
         }
@@ -327,7 +501,7 @@ fn color_cells(num_cells: usize, adjacencies: HashSet<(usize, usize)>) -> Vec<Co
             if !available {
                 continue;
             }
-            if assigned_colors.iter().any(|x| *x == idx) {
+            if assigned_colors.iter().any(|x| *x == Some(idx)) {
                 if backup.is_none() {
                     backup = Some(idx);
                 }
@@ -336,14 +510,8 @@ fn color_cells(num_cells: usize, adjacencies: HashSet<(usize, usize)>) -> Vec<Co
                 break;
             }
         }
-        assigned_colors.push(
-            choice
-                .or(backup)
-                .unwrap_or_else(|| assigned_colors.len() % colors::CELLS.len()),
-        );
+        assigned_colors[this_idx] =
+            Some(choice.or(backup).unwrap_or(this_idx % colors::CELLS.len()));
     }
-    assigned_colors
-        .into_iter()
-        .map(|idx| colors::CELLS[idx].alpha(0.8))
-        .collect()
+    assigned_colors.into_iter().map(|x| x.unwrap()).collect()
 }
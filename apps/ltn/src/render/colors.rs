@@ -1,3 +1,4 @@
+use widgetry::tools::ColorScale;
 use widgetry::Color;
 
 lazy_static::lazy_static! {
@@ -16,6 +17,20 @@ lazy_static::lazy_static! {
         Color::hex("#ffed6f"),
     ];
 
+    // Sequential purples from colorbrewer2.org, to stay distinct from the red shortcut heatmap
+    pub static ref CELL_SHORTCUTS: ColorScale = ColorScale(vec![
+        Color::hex("#efedf5"),
+        Color::hex("#bcbddc"),
+        Color::hex("#756bb1"),
+    ]);
+
+    // Sequential yellow-orange-red from colorbrewer2.org, for roads absorbing displaced traffic
+    pub static ref DISPLACED_TRAFFIC: ColorScale = ColorScale(vec![
+        Color::hex("#ffeda0"),
+        Color::hex("#feb24c"),
+        Color::hex("#f03b20"),
+    ]);
+
     pub static ref PLAN_ROUTE_BEFORE: Color = Color::PURPLE;
     pub static ref PLAN_ROUTE_AFTER: Color = Color::CYAN;
     pub static ref PLAN_ROUTE_BIKE: Color = Color::GREEN;
@@ -42,6 +57,20 @@ lazy_static::lazy_static! {
     pub static ref NETWORK_THROUGH_TRAFFIC_STREET: Color = Color::hex("#F3A4A4");
 }
 
+/// What to call each of `CELLS` when somebody picks one
+pub const CELL_NAMES: [&str; 10] = [
+    "teal",
+    "pale yellow",
+    "lavender",
+    "blue",
+    "orange",
+    "lime",
+    "pink",
+    "purple",
+    "mint",
+    "yellow",
+];
+
 pub const DISCONNECTED_CELL: Color = Color::RED.alpha(0.5);
 
 pub const BLOCK_IN_BOUNDARY: Color = Color::BLUE.alpha(0.5);
@@ -51,3 +80,12 @@ pub const BLOCK_IN_FRONTIER: Color = Color::CYAN.alpha(0.2);
 pub const LOCAL_ROAD_LABEL: Color = Color::BLACK;
 pub const MAIN_ROAD_LABEL: Color = Color::WHITE;
 pub const HOVER: Color = Color::CYAN.alpha(0.5);
+pub const PICKED_CELL: Color = Color::YELLOW.alpha(0.6);
+pub const ONE_WAY_EDIT: Color = Color::PURPLE;
+pub const SPEED_LIMIT_EDIT: Color = Color::rgb_f(0.1, 0.5, 0.47);
+pub const SCHOOL_CELL: Color = Color::ORANGE;
+pub const BUS_GATE_BARRIER: Color = Color::rgb_f(0.8, 0.1, 0.1);
+pub const PLANTING: Color = Color::rgb_f(0.2, 0.6, 0.2);
+pub const NEW_DEAD_END: Color = Color::rgb_f(0.6, 0.2, 0.8).alpha(0.8);
+pub const FILTER_SUMMARY: Color = Color::rgb_f(0.0, 0.5, 0.3);
+pub const DESIRE_LINE: Color = Color::rgb_f(0.9, 0.4, 0.0);
@@ -1,13 +1,22 @@
 mod cells;
 pub mod colors;
 
-use geom::Distance;
-use map_model::{AmenityType, ExtraPOIType, Map};
-use widgetry::mapspace::DrawCustomUnzoomedShapes;
+use std::collections::BTreeSet;
+
+use geom::{ArrowCap, Circle, Distance, PolyLine, Polygon, Pt2D, Speed, UnitFmt};
+use map_gui::colors::ColorScheme;
+use map_model::{
+    AmenityType, CommonEndpoint, Direction, EditRoad, ExtraPOIType, IntersectionID, LaneSpec, Map,
+    RestrictionType, Road, RoadID, TurnType,
+};
+use widgetry::mapspace::{DrawCustomUnzoomedShapes, PerZoom};
 use widgetry::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, RewriteColor, Text};
 
 pub use cells::RenderCells;
 
+use crate::logic::Partitioning;
+use crate::{App, Edits};
+
 pub fn render_poi_icons(ctx: &EventCtx, map: &Map) -> Drawable {
     let mut batch = GeomBatch::new();
     let school = GeomBatch::load_svg(ctx, "system/assets/map/school.svg")
@@ -47,6 +56,41 @@ pub fn render_poi_icons(ctx: &EventCtx, map: &Map) -> Drawable {
     ctx.upload(batch)
 }
 
+/// Dim the whole map, except for the areas to `keep`. They mustn't overlap each other.
+pub fn fade_outside(ctx: &EventCtx, app: &App, keep: Vec<Polygon>) -> Drawable {
+    let fade_area = Polygon::with_holes(
+        app.per_map
+            .map
+            .get_boundary_polygon()
+            .get_outer_ring()
+            .clone(),
+        keep.into_iter().map(|p| p.into_outer_ring()).collect(),
+    );
+    GeomBatch::from(vec![(app.cs.fade_map_dark, fade_area)]).upload(ctx)
+}
+
+/// Split a road's thick polygon at some distance along its center, returning the pieces before and
+/// after that point. Both pieces always exist; a `dist` at or past either end of the road leaves a
+/// thin sliver on that side. If the road is too short to slice at all, both pieces are the whole
+/// road.
+pub fn split_thick_polygon(road: &Road, dist: Distance) -> (Polygon, Polygon) {
+    let min_len = Distance::meters(0.1);
+    let len = road.length();
+    let dist = if len <= min_len * 2.0 {
+        len / 2.0
+    } else {
+        dist.max(min_len).min(len - min_len)
+    };
+    let width = road.get_width();
+    match (
+        road.center_pts.maybe_exact_slice(Distance::ZERO, dist),
+        road.center_pts.maybe_exact_slice(dist, len),
+    ) {
+        (Ok(before), Ok(after)) => (before.make_polygons(width), after.make_polygons(width)),
+        _ => (road.get_thick_polygon(), road.get_thick_polygon()),
+    }
+}
+
 pub fn render_bus_routes(ctx: &EventCtx, map: &Map) -> Drawable {
     let mut batch = GeomBatch::new();
     for r in map.all_roads() {
@@ -75,28 +119,454 @@ pub fn render_bus_routes(ctx: &EventCtx, map: &Map) -> Drawable {
     ctx.upload(batch)
 }
 
+/// Draw the turns that the base map already bans, so users don't place redundant diagonal
+/// filters.
+pub fn render_turn_restrictions(ctx: &EventCtx, map: &Map) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for from in map.all_roads() {
+        for (restriction, to) in &from.turn_restrictions {
+            let to = map.get_r(*to);
+            let i = match from.common_endpoint(to) {
+                CommonEndpoint::One(i) => i,
+                _ => continue,
+            };
+            let banned: Vec<&Road> = match restriction {
+                RestrictionType::BanTurns => vec![to],
+                // Everything else from this road is banned
+                RestrictionType::OnlyAllowTurns => map
+                    .get_i(i)
+                    .roads
+                    .iter()
+                    .filter(|r| **r != from.id && **r != to.id)
+                    .map(|r| map.get_r(*r))
+                    .filter(|r| crate::is_driveable(r, map))
+                    .collect(),
+            };
+            for to in banned {
+                let pts = vec![
+                    pt_near(from, i),
+                    map.get_i(i).polygon.center(),
+                    pt_near(to, i),
+                ];
+                if let Some(arrow) = banned_turn_arrow(pts) {
+                    batch.push(Color::RED.alpha(0.8), arrow);
+                }
+            }
+        }
+
+        // Bans through a sequence of roads
+        for (via, to) in &from.complicated_turn_restrictions {
+            let via = map.get_r(*via);
+            let to = map.get_r(*to);
+            if let (CommonEndpoint::One(i1), CommonEndpoint::One(i2)) =
+                (from.common_endpoint(via), via.common_endpoint(to))
+            {
+                let pts = vec![
+                    pt_near(from, i1),
+                    map.get_i(i1).polygon.center(),
+                    map.get_i(i2).polygon.center(),
+                    pt_near(to, i2),
+                ];
+                if let Some(arrow) = banned_turn_arrow(pts) {
+                    batch.push(Color::RED.alpha(0.8), arrow);
+                }
+            }
+        }
+    }
+    ctx.upload(batch)
+}
+
+/// Highlight roads where the user changed the direction of traffic, with arrows pointing the new
+/// way. `Edits::one_ways` also holds other road edits, like speed limits, so those are skipped.
+pub fn render_one_way_edits(ctx: &EventCtx, map: &Map, edits: &crate::Edits) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for (r, r_edit) in &edits.one_ways {
+        let road = map.get_r(*r);
+        let orig = EditRoad::get_orig_from_osm(road, map.get_config());
+        let dir = LaneSpec::oneway_for_driving(&r_edit.lanes_ltr);
+        if dir == LaneSpec::oneway_for_driving(&orig.lanes_ltr) {
+            continue;
+        }
+
+        batch.push(colors::ONE_WAY_EDIT.alpha(0.5), road.get_thick_polygon());
+        // Two-way again, no arrows to draw
+        let dir = match dir {
+            Some(dir) => dir,
+            None => continue,
+        };
+        let thickness = 0.2 * road.get_width();
+        let arrow_len = 5.0 * thickness;
+        for (pt, angle) in road.center_pts.step_along(3.0 * arrow_len, arrow_len) {
+            let pl = PolyLine::must_new(vec![
+                pt.project_away(arrow_len / 2.0, angle.opposite()),
+                pt.project_away(arrow_len / 2.0, angle),
+            ])
+            .maybe_reverse(dir == Direction::Back);
+            batch.push(
+                colors::ONE_WAY_EDIT,
+                pl.make_arrow(thickness, ArrowCap::Triangle),
+            );
+        }
+    }
+    ctx.upload(batch)
+}
+
+/// The edited roads as they were originally, to draw on top of the basemap when comparing against
+/// the map without any changes. Only the width of the unzoomed road is baked into the basemap;
+/// directions of traffic are just in the one-way layer. So cover up edited roads with a different
+/// width, then draw them again at the original width.
+pub fn render_unedited_roads(
+    ctx: &EventCtx,
+    map: &Map,
+    cs: &ColorScheme,
+    edits: &crate::Edits,
+) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for r in edits.one_ways.keys() {
+        let road = map.get_r(*r);
+        let orig = EditRoad::get_orig_from_osm(road, map.get_config());
+        let orig_width = orig.lanes_ltr.iter().map(|lane| lane.width).sum();
+        if orig_width == road.get_width() {
+            continue;
+        }
+        // TODO Anything else under a widened road, like a park, gets covered too. And the
+        // intersections at either end keep their edited shape.
+        batch.push(cs.map_background.clone(), road.get_thick_polygon());
+        batch.push(
+            cs.unzoomed_road_surface(road.get_rank()),
+            road.center_pts.make_polygons(orig_width),
+        );
+    }
+    ctx.upload(batch)
+}
+
+/// Highlight roads whose speed limit differs from what OpenStreetMap says, labelled like "30→20
+/// mph" in the given units. Roads with a different limit in each direction show both, forwards first.
+pub fn render_speed_limit_edits(
+    ctx: &EventCtx,
+    map: &Map,
+    edits: &crate::Edits,
+    units: &UnitFmt,
+) -> Drawable {
+    // Only label the units once
+    let round = |speed: Speed| {
+        if units.metric {
+            (3.6 * speed.inner_meters_per_second()).round()
+        } else {
+            speed.to_miles_per_hour().round()
+        }
+    };
+    let suffix = if units.metric { "km/h" } else { "mph" };
+
+    let mut batch = GeomBatch::new();
+    let roads: BTreeSet<RoadID> = edits
+        .one_ways
+        .keys()
+        .chain(edits.speed_limits.keys())
+        .cloned()
+        .collect();
+    for r in roads {
+        let road = map.get_r(r);
+        let orig = round(EditRoad::get_orig_from_osm(road, map.get_config()).speed_limit);
+        let label = if let Some(limit) = edits.speed_limits.get(&r) {
+            let (fwd, back) = (round(limit.fwd), round(limit.back));
+            if fwd == orig && back == orig {
+                continue;
+            }
+            format!("{}→{}/{} {}", orig, fwd, back, suffix)
+        } else {
+            let new = round(road.speed_limit);
+            // Only the direction changed
+            if new == orig {
+                continue;
+            }
+            format!("{}→{} {}", orig, new, suffix)
+        };
+
+        batch.push(
+            colors::SPEED_LIMIT_EDIT.alpha(0.5),
+            road.get_thick_polygon(),
+        );
+        let pt = road.center_pts.middle();
+        batch.append(
+            Text::from(Line(label).fg(Color::WHITE))
+                .bg(colors::SPEED_LIMIT_EDIT)
+                .render_autocropped(ctx)
+                .scale_to_fit_height(0.8 * road.get_width().inner_meters())
+                .centered_on(pt),
+        );
+    }
+    ctx.upload(batch)
+}
+
+/// Red arrows through an intersection for each movement a diagonal filter there blocks. Roads
+/// nobody drives on are skipped.
+pub fn render_blocked_movements(
+    map: &Map,
+    i: IntersectionID,
+    movements: Vec<(RoadID, RoadID)>,
+) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let center = map.get_i(i).polygon.center();
+    for (from, to) in movements {
+        let from = map.get_r(from);
+        let to = map.get_r(to);
+        if !crate::is_driveable(from, map) || !crate::is_driveable(to, map) {
+            continue;
+        }
+        let pts = vec![pt_near(from, i), center, pt_near(to, i)];
+        if let Some(arrow) = banned_turn_arrow(pts) {
+            batch.push(Color::RED.alpha(0.8), arrow);
+        }
+    }
+    batch
+}
+
+/// A round sign banning one movement, in the style of the UK's "no left turn": a black arrow
+/// crossed out in red. It sits on the road drivers approach from, with the arrow pointing the way
+/// they're heading.
+pub fn draw_banned_turn_sign(
+    map: &Map,
+    i: IntersectionID,
+    from: RoadID,
+    turn_type: TurnType,
+    icon_scale: f64,
+) -> GeomBatch {
+    let road = map.get_r(from);
+    let center = pt_near(road, i);
+    let radius = icon_scale * road.get_width().inner_meters() / 2.0;
+    // The direction of travel towards the intersection
+    let heading = if road.dst_i == i {
+        road.center_pts.last_line().angle()
+    } else {
+        road.center_pts.first_line().angle().opposite()
+    };
+    let (sin, cos) = heading.normalized_radians().sin_cos();
+    // Describe points in units of the radius, forwards and to the right of the driver
+    let local = |fwd: f64, right: f64| {
+        center.offset(
+            radius * (fwd * cos - right * sin),
+            radius * (fwd * sin + right * cos),
+        )
+    };
+    let arrow_pts = match turn_type {
+        TurnType::Left => vec![local(-0.55, 0.15), local(0.1, 0.15), local(0.1, -0.5)],
+        TurnType::Right => vec![local(-0.55, -0.15), local(0.1, -0.15), local(0.1, 0.5)],
+        TurnType::UTurn => vec![
+            local(-0.5, 0.25),
+            local(0.3, 0.25),
+            local(0.3, -0.25),
+            local(-0.3, -0.25),
+        ],
+        _ => vec![local(-0.55, 0.0), local(0.55, 0.0)],
+    };
+
+    let mut batch = GeomBatch::new();
+    let circle = Circle::new(center, Distance::meters(radius));
+    batch.push(Color::WHITE, circle.to_polygon());
+    if let Ok(pl) = PolyLine::new(arrow_pts) {
+        batch.push(
+            Color::BLACK,
+            pl.make_arrow(Distance::meters(0.15 * radius), ArrowCap::Triangle),
+        );
+    }
+    if let Ok(ring) = circle.to_outline(Distance::meters(0.2 * radius)) {
+        batch.push(Color::RED, ring);
+    }
+    // The slash runs from the top-left to the bottom-right, from the driver's point of view
+    if let Ok(slash) = geom::Line::new(local(0.7, -0.7), local(-0.7, 0.7)) {
+        batch.push(
+            Color::RED,
+            slash.make_polygons(Distance::meters(0.18 * radius)),
+        );
+    }
+    batch
+}
+
+/// A point on the road a short distance away from one of its intersections
+fn pt_near(road: &Road, i: IntersectionID) -> Pt2D {
+    let dist = Distance::meters(10.0).min(road.length() / 2.0);
+    let dist = if road.src_i == i {
+        dist
+    } else {
+        road.length() - dist
+    };
+    road.center_pts.must_dist_along(dist).0
+}
+
+fn banned_turn_arrow(pts: Vec<Pt2D>) -> Option<Polygon> {
+    let pl = PolyLine::deduping_new(pts).ok()?;
+    Some(pl.make_arrow(Distance::meters(1.5), ArrowCap::Triangle))
+}
+
+/// Zoom levels controlling how one category of icons is drawn.
+#[derive(Clone, Copy)]
+pub struct ZoomThresholds {
+    /// At or above this zoom, draw icons at their real size
+    pub min_zoom_for_detail: f64,
+    /// Below this zoom, don't draw the icons at all
+    pub min_zoom_to_show: f64,
+    /// Between the two, icons are redrawn at a new size each time the zoom changes this much
+    pub step_size: f64,
+}
+
+impl ZoomThresholds {
+    pub const FILTERS: ZoomThresholds = ZoomThresholds {
+        min_zoom_for_detail: 5.0,
+        min_zoom_to_show: 0.0,
+        step_size: 0.1,
+    };
+    // Crossings are lower priority than filters, so they disappear first when zooming out
+    pub const CROSSINGS: ZoomThresholds = ZoomThresholds {
+        min_zoom_for_detail: 5.0,
+        min_zoom_to_show: 1.0,
+        step_size: 0.1,
+    };
+
+    pub fn per_zoom(self) -> PerZoom {
+        PerZoom::new(self.min_zoom_for_detail, self.step_size)
+    }
+}
+
 /// Depending on the canvas zoom level, draws one of 2 things.
 // TODO Rethink filter styles and do something better than this.
 pub struct Toggle3Zoomed {
     draw_zoomed: Drawable,
     unzoomed: DrawCustomUnzoomedShapes,
+    min_zoom_to_show: f64,
+    /// Drawn instead of everything else when zoomed out below `MAX_ZOOM_FOR_SUMMARY`
+    summary: Option<DrawCustomUnzoomedShapes>,
 }
 
 impl Toggle3Zoomed {
-    pub fn new(draw_zoomed: Drawable, unzoomed: DrawCustomUnzoomedShapes) -> Self {
+    /// `unzoomed` should be built with `zoom.per_zoom()`
+    pub fn new(
+        draw_zoomed: Drawable,
+        unzoomed: DrawCustomUnzoomedShapes,
+        zoom: ZoomThresholds,
+    ) -> Self {
         Self {
             draw_zoomed,
             unzoomed,
+            min_zoom_to_show: zoom.min_zoom_to_show,
+            summary: None,
         }
     }
 
+    /// `summary` should come from `render_filter_summary`
+    pub fn with_summary(mut self, summary: DrawCustomUnzoomedShapes) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    pub fn replace_summary(&mut self, summary: DrawCustomUnzoomedShapes) {
+        self.summary = Some(summary);
+    }
+
+    /// Draw nothing when zoomed out far enough for a summary, because another `Toggle3Zoomed`'s
+    /// summary already covers these filters
+    pub fn hidden_when_summarized(self) -> Self {
+        self.with_summary(
+            DrawCustomUnzoomedShapes::builder()
+                .build(PerZoom::new(MAX_ZOOM_FOR_SUMMARY, MAX_ZOOM_FOR_SUMMARY)),
+        )
+    }
+
     pub fn empty(ctx: &EventCtx) -> Self {
-        Self::new(Drawable::empty(ctx), DrawCustomUnzoomedShapes::empty())
+        Self::new(
+            Drawable::empty(ctx),
+            DrawCustomUnzoomedShapes::empty(),
+            ZoomThresholds::FILTERS,
+        )
     }
 
     pub fn draw(&self, g: &mut GfxCtx) {
+        if let Some(ref summary) = self.summary {
+            // This only draws below MAX_ZOOM_FOR_SUMMARY
+            if summary.maybe_draw(g) {
+                return;
+            }
+        }
+        if g.canvas.cam_zoom < self.min_zoom_to_show {
+            return;
+        }
         if !self.unzoomed.maybe_draw(g) {
             self.draw_zoomed.draw(g);
         }
     }
 }
+
+/// When the whole city is in view, individual filters give way to one marker per neighbourhood
+const MAX_ZOOM_FOR_SUMMARY: f64 = 0.2;
+
+/// One marker per neighbourhood with filters, sized and labelled by how many it has. Meant for
+/// `Toggle3Zoomed::with_summary`, when individual icons would be too small to make out. Since this
+/// depends on the neighbourhood boundaries, call `crate::redraw_filter_summary` after changing
+/// them.
+pub fn render_filter_summary(
+    ctx: &EventCtx,
+    map: &Map,
+    partitioning: &Partitioning,
+    edits: &Edits,
+) -> DrawCustomUnzoomedShapes {
+    let mut areas: Vec<(&BTreeSet<RoadID>, &Polygon)> = partitioning
+        .all_neighbourhoods()
+        .iter()
+        .filter(|(id, _)| !partitioning.custom_boundaries.contains_key(id))
+        .map(|(_, info)| (&info.block.perimeter.interior, &info.block.polygon))
+        .collect();
+    for custom in partitioning.custom_boundaries.values() {
+        areas.push((&custom.interior_roads, &custom.boundary_polygon));
+    }
+
+    let mut low_zoom = DrawCustomUnzoomedShapes::builder();
+    for (interior, polygon) in areas {
+        let num_road_filters = edits.roads.keys().filter(|r| interior.contains(r)).count();
+        let num_diagonal_filters: usize = edits
+            .intersections
+            .iter()
+            .filter(|(i, _)| map.get_i(**i).roads.iter().any(|r| interior.contains(r)))
+            .map(|(_, filters)| filters.len())
+            .sum();
+        let count = num_road_filters + num_diagonal_filters;
+        if count == 0 {
+            continue;
+        }
+
+        let center = polygon.polylabel();
+        // Area grows with the number of filters, so a few big markers don't swamp everything
+        let radius = 5.0 + 3.0 * (count as f64).sqrt();
+        let label = Text::from(Line(count.to_string()).fg(Color::WHITE)).render_autocropped(ctx);
+        low_zoom.add_custom(Box::new(move |batch, thickness| {
+            let circle = Circle::new(center, Distance::meters(radius * thickness)).to_polygon();
+            batch.push(colors::FILTER_SUMMARY, circle.clone());
+            batch.push(Color::WHITE, circle.to_outline(Distance::meters(thickness)));
+            batch.append(label.clone().scale(thickness).centered_on(center));
+        }));
+    }
+    low_zoom.build(PerZoom::new(MAX_ZOOM_FOR_SUMMARY, 0.02))
+}
+
+/// Draw a band along `seam`, `thickness` wide, filled with diagonal stripes, like the hatching on
+/// a barrier.
+pub fn draw_hatched_seam(batch: &mut GeomBatch, seam: &geom::Line, thickness: Distance) {
+    batch.push(Color::WHITE, seam.make_polygons(thickness));
+
+    let spacing = Distance::meters(1.0);
+    let stripe_width = Distance::meters(0.3);
+    let across = seam.angle().rotate_degs(90.0);
+    let mut dist = spacing / 2.0;
+    while dist + spacing / 2.0 <= seam.length() {
+        // Each stripe stays inside the band, leaning one spacing along the seam
+        let start = seam
+            .must_dist_along(dist - spacing / 2.0)
+            .project_away(thickness / 2.0, across.opposite());
+        let end = seam
+            .must_dist_along(dist + spacing / 2.0)
+            .project_away(thickness / 2.0, across);
+        if let Ok(stripe) = geom::Line::new(start, end) {
+            batch.push(Color::BLACK, stripe.make_polygons(stripe_width));
+        }
+        dist += spacing;
+    }
+}
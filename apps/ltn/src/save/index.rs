@@ -0,0 +1,152 @@
+use std::time::SystemTime;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use abstio::MapName;
+
+/// What the plan browser shows about one saved proposal. This is read straight from the file,
+/// without fixing up IDs or loading anything onto the map, so it's cheap to build for every plan.
+pub struct PlanSummary {
+    /// The name the proposal was saved as
+    pub name: String,
+    pub title: String,
+    pub neighbourhood: String,
+    /// Filters the plan added. Filters that already existed aren't counted.
+    pub num_filters: usize,
+    /// Unknown on the web, where proposals live in local storage
+    pub last_modified: Option<SystemTime>,
+}
+
+impl PlanSummary {
+    /// One line describing the plan, for picking it from a list
+    pub fn describe(&self) -> String {
+        let mut parts = vec![self.name.clone()];
+        if !self.title.is_empty() {
+            parts.push(format!("\"{}\"", self.title));
+        }
+        if !self.neighbourhood.is_empty() {
+            parts.push(format!("in {}", self.neighbourhood));
+        }
+        parts.push(match self.num_filters {
+            1 => "1 new filter".to_string(),
+            n => format!("{} new filters", n),
+        });
+        if let Some(time) = self.last_modified {
+            parts.push(format!("saved {}", describe_age(time)));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Summarizes every proposal saved for a map, most recently saved first. Files that can't be read
+/// are skipped with a warning.
+pub fn index_saved_plans(map: &MapName) -> Vec<PlanSummary> {
+    let mut plans = Vec::new();
+    // basename (and thus list_all_objects) only strips the ".gz"
+    for name in abstio::list_all_objects(abstio::path_all_ltn_proposals(map))
+        .into_iter()
+        .map(abstutil::basename)
+    {
+        let path = abstio::path_ltn_proposals(map, &name);
+        match summarize(name, &path) {
+            Ok(summary) => plans.push(summary),
+            Err(err) => warn!("Couldn't summarize {}: {}", path, err),
+        }
+    }
+    // Plans with no known time sort last
+    plans.sort_by_key(|plan| std::cmp::Reverse(plan.last_modified));
+    plans
+}
+
+fn summarize(name: String, path: &str) -> Result<PlanSummary> {
+    let bytes = abstio::slurp_file(path)?;
+    let decoder = flate2::read::GzDecoder::new(&bytes[..]);
+    let value: Value = serde_json::from_reader(decoder)?;
+
+    let text = |pointer: &str| {
+        value
+            .pointer(pointer)
+            .and_then(|x| x.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+
+    Ok(PlanSummary {
+        name,
+        title: text("/edits/metadata/title"),
+        neighbourhood: text("/edits/metadata/neighbourhood"),
+        num_filters: count_raw_filters(&value, true),
+        last_modified: last_modified(path),
+    })
+}
+
+/// Counts the road and diagonal filters in a proposal's raw JSON, without resolving any IDs. If
+/// `only_user_modified`, filters that already existed in the map aren't counted.
+pub fn count_raw_filters(value: &Value, only_user_modified: bool) -> usize {
+    let counts = |filter: &Value| {
+        !only_user_modified
+            || filter
+                .get("user_modified")
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false)
+    };
+
+    let mut num_filters = 0;
+    for pair in map_entries(value, "/edits/roads") {
+        if pair.get(1).map(counts).unwrap_or(false) {
+            num_filters += 1;
+        }
+    }
+    // Each entry is an intersection and its list of diagonal filters
+    for pair in map_entries(value, "/edits/intersections") {
+        if let Some(filters) = pair.get(1).and_then(|x| x.as_array()) {
+            num_filters += filters.iter().filter(|f| counts(f)).count();
+        }
+    }
+    num_filters
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn last_modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+// Proposals live in local storage, which doesn't track this
+#[cfg(target_arch = "wasm32")]
+fn last_modified(_: &str) -> Option<SystemTime> {
+    None
+}
+
+/// `serialize_btreemap` writes maps as a list of `[key, value]` pairs
+fn map_entries<'a>(value: &'a Value, pointer: &str) -> &'a [Value] {
+    value
+        .pointer(pointer)
+        .and_then(|x| x.as_array())
+        .map(|x| x.as_slice())
+        .unwrap_or(&[])
+}
+
+fn describe_age(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if secs < 60 {
+        return "just now".to_string();
+    }
+    let (amount, unit) = if secs < 60 * 60 {
+        (secs / 60, "minute")
+    } else if secs < 24 * 60 * 60 {
+        (secs / (60 * 60), "hour")
+    } else {
+        (secs / (24 * 60 * 60), "day")
+    };
+    if amount == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", amount, unit)
+    }
+}
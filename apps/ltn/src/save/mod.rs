@@ -1,5 +1,8 @@
+mod index;
 mod perma;
+mod plan_details;
 mod share;
+mod validate;
 
 use std::collections::BTreeSet;
 
@@ -12,13 +15,15 @@ use map_model::{BuildingID, EditRoad, Map};
 use widgetry::tools::{ChooseSomething, PopupMsg};
 use widgetry::{
     lctrl, Choice, DrawBaselayer, EventCtx, GfxCtx, Key, Line, MultiKey, Outcome, Panel, State,
-    TextBox, Widget,
+    TextBox, Toggle, Widget,
 };
 
+use crate::filters::SaveKey;
 use crate::logic::{BlockID, Partitioning};
 use crate::{pages, App, Edits, Transition};
 
-pub use share::PROPOSAL_HOST_URL;
+pub use share::{describe_shared_proposal, proposal_from_url_fragment, PROPOSAL_HOST_URL};
+pub use validate::validate_all_proposals;
 
 /// Captures all of the edits somebody makes to a map in the LTN tool. Note this is separate from
 /// `map_model::MapEdits`.
@@ -35,10 +40,29 @@ pub struct Proposal {
     /// If this proposal is an edit to another proposal, store its name
     #[serde(skip_serializing, skip_deserializing)]
     unsaved_parent: Option<String>,
+    /// The edits as they were last saved or loaded, to tell if there are unsaved changes
+    #[serde(skip_serializing, skip_deserializing)]
+    saved_key: SaveKey,
 }
 
 impl Proposal {
-    fn make_active(self, ctx: &EventCtx, app: &mut App) {
+    /// Have the edits changed since this proposal was last saved or loaded?
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.edits.get_save_key() != self.saved_key
+    }
+
+    /// The name to show people, with an asterisk when there are unsaved changes
+    fn display_name(&self) -> String {
+        if self.has_unsaved_changes() {
+            format!("{}*", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    fn make_active(self, ctx: &mut EventCtx, app: &mut App) {
+        let names_changed = app.edits().road_names != self.edits.road_names;
+
         // First undo any one-way changes
         let mut edits = app.per_map.map.new_edits();
         for r in app.edits().one_ways.keys().cloned() {
@@ -52,7 +76,21 @@ impl Proposal {
         }
 
         app.per_map.proposals.current_proposal = self;
-        app.per_map.draw_all_filters = app.edits().draw(ctx, &app.per_map.map);
+        app.per_map.draw_all_filters = app
+            .edits()
+            .draw(
+                ctx,
+                &app.per_map.map,
+                app.session.layers.filter_icon_scale,
+                app.session.layers.filter_zoom(),
+                app.session.layers.filter_style,
+            )
+            .with_summary(crate::render::render_filter_summary(
+                ctx,
+                &app.per_map.map,
+                app.partitioning(),
+                app.edits(),
+            ));
 
         // Then append any new one-way changes. Edits are applied in order, so the net effect
         // should be correct.
@@ -63,17 +101,23 @@ impl Proposal {
                     *new = r_edit.clone();
                 }));
         }
-        app.per_map
-            .map
-            .must_apply_edits(edits, &mut Timer::throwaway());
+        // Lane widths may differ between the proposals, so redraw roads if needed
+        crate::logic::map_edits::apply_and_redraw(ctx, app, edits);
+        app.per_map.draw_unedited_roads =
+            crate::render::render_unedited_roads(ctx, &app.per_map.map, &app.cs, app.edits());
+
+        if names_changed {
+            crate::relabel_roads(ctx, app);
+        }
     }
 
-    /// Try to load a proposal. If it fails, returns a popup message state.
+    /// Try to load a proposal. If it fails or some parts are stale, returns a popup message state.
     pub fn load_from_path(
         ctx: &mut EventCtx,
         app: &mut App,
         path: String,
     ) -> Option<Box<dyn State<App>>> {
+        share::forget_proposal_in_url();
         Self::load_from_bytes(ctx, app, &path, abstio::slurp_file(path.clone()))
     }
 
@@ -84,7 +128,14 @@ impl Proposal {
         bytes: Result<Vec<u8>>,
     ) -> Option<Box<dyn State<App>>> {
         match bytes.and_then(|bytes| Self::inner_load(ctx, app, bytes)) {
-            Ok(()) => None,
+            Ok(warnings) if warnings.is_empty() => None,
+            Ok(mut warnings) => {
+                warnings.insert(
+                    0,
+                    format!("Loaded proposal {}, but some parts were stale:", name),
+                );
+                Some(PopupMsg::new_state(ctx, "Warning", warnings))
+            }
             Err(err) => Some(PopupMsg::new_state(
                 ctx,
                 "Error",
@@ -98,10 +149,9 @@ impl Proposal {
         }
     }
 
-    fn inner_load(ctx: &mut EventCtx, app: &mut App, bytes: Vec<u8>) -> Result<()> {
-        let decoder = flate2::read::GzDecoder::new(&bytes[..]);
-        let value = serde_json::from_reader(decoder)?;
-        let proposal = perma::from_permanent(&app.per_map.map, value)?;
+    /// Returns warnings about parts of the proposal that couldn't be used
+    fn inner_load(ctx: &mut EventCtx, app: &mut App, bytes: Vec<u8>) -> Result<Vec<String>> {
+        let (proposal, warnings) = Self::decode(&app.per_map.map, bytes)?;
 
         // TODO We could try to detect if the file's partitioning (road IDs and such) still matches
         // this version of the map or not
@@ -118,7 +168,20 @@ impl Proposal {
 
         proposal.make_active(ctx, app);
 
-        Ok(())
+        Ok(warnings)
+    }
+
+    /// Parse a saved proposal and fix it up for the current version of the map, without
+    /// activating it. Also returns warnings about parts that couldn't be used.
+    fn decode(map: &Map, bytes: Vec<u8>) -> Result<(Proposal, Vec<String>)> {
+        let decoder = flate2::read::GzDecoder::new(&bytes[..]);
+        let value = serde_json::from_reader(decoder)?;
+        let mut proposal = perma::from_permanent(map, value)?;
+        // The map may have been regenerated since the proposal was saved
+        let mut warnings = proposal.edits.clamp_distances(map);
+        warnings.extend(proposal.edits.remove_stale_diagonal_filters(map));
+        proposal.saved_key = proposal.edits.get_save_key();
+        Ok((proposal, warnings))
     }
 
     fn to_gzipped_bytes(&self, app: &App) -> Result<Vec<u8>> {
@@ -150,6 +213,7 @@ fn stash_current_proposal(app: &mut App) {
 
 fn switch_to_existing_proposal(ctx: &mut EventCtx, app: &mut App, idx: usize) {
     stash_current_proposal(app);
+    share::forget_proposal_in_url();
 
     let proposal = app
         .per_map
@@ -164,14 +228,14 @@ fn switch_to_existing_proposal(ctx: &mut EventCtx, app: &mut App, idx: usize) {
     proposal.make_active(ctx, app);
 }
 
-struct SaveDialog {
+pub struct SaveDialog {
     panel: Panel,
     preserve_state: PreserveState,
     can_overwrite: bool,
 }
 
 impl SaveDialog {
-    fn new_state(
+    pub fn new_state(
         ctx: &mut EventCtx,
         app: &App,
         preserve_state: PreserveState,
@@ -208,8 +272,15 @@ impl SaveDialog {
                     TextBox::default_widget(ctx, "input", String::new()),
                     Widget::placeholder(ctx, "Save as"),
                 ]),
+                Toggle::checkbox(
+                    ctx,
+                    "Save my edits only",
+                    None,
+                    app.session.save_user_edits_only,
+                ),
                 Widget::placeholder(ctx, "warning"),
             ]))
+            .keyboard_navigation()
             .build(ctx),
             preserve_state,
             can_overwrite,
@@ -272,9 +343,13 @@ impl State<App> for SaveDialog {
 
                     app.per_map.proposals.current_proposal.name = name;
                     app.per_map.proposals.current_proposal.unsaved_parent = None;
+                    app.session.save_user_edits_only = self.panel.is_checked("Save my edits only");
                     return match inner_save(app) {
                         // If we changed the name, we'll want to recreate the panel
-                        Ok(()) => self.preserve_state.switch_to_state(ctx, app),
+                        Ok(()) => {
+                            app.per_map.proposals.mark_saved();
+                            self.preserve_state.switch_to_state(ctx, app)
+                        }
                         Err(err) => {
                             self.error(ctx, app, format!("Couldn't save proposal: {}", err))
                         }
@@ -287,9 +362,13 @@ impl State<App> for SaveDialog {
                     let proposals = &mut app.per_map.proposals;
                     proposals.current_proposal.name =
                         proposals.current_proposal.unsaved_parent.take().unwrap();
+                    app.session.save_user_edits_only = self.panel.is_checked("Save my edits only");
 
                     return match inner_save(app) {
-                        Ok(()) => self.preserve_state.switch_to_state(ctx, app),
+                        Ok(()) => {
+                            app.per_map.proposals.mark_saved();
+                            self.preserve_state.switch_to_state(ctx, app)
+                        }
                         // TODO If we fail to save for some reason, the Proposals panel gets out
                         // of sync with the filesystem
                         Err(err) => {
@@ -325,7 +404,14 @@ impl State<App> for SaveDialog {
 fn inner_save(app: &App) -> Result<()> {
     let proposal = &app.per_map.proposals.current_proposal;
     let path = abstio::path_ltn_proposals(app.per_map.map.get_name(), &proposal.name);
-    let output_buffer = proposal.to_gzipped_bytes(app)?;
+    let output_buffer = if app.session.save_user_edits_only {
+        // Only the file leaves out the suggested filters; the proposal being edited keeps them
+        let mut proposal = proposal.clone();
+        proposal.edits.retain_user_modified();
+        proposal.to_gzipped_bytes(app)?
+    } else {
+        proposal.to_gzipped_bytes(app)?
+    };
     abstio::write_raw(path, &output_buffer)
 }
 
@@ -336,25 +422,33 @@ fn load_picker_ui(
 ) -> Box<dyn State<App>> {
     // Don't bother trying to filter out proposals currently loaded -- by loading twice, somebody
     // effectively makes a copy to modify a bit
+    let plans = index::index_saved_plans(app.per_map.map.get_name());
+    if plans.is_empty() {
+        return PopupMsg::new_state(
+            ctx,
+            "No saved proposals",
+            vec!["You haven't saved any proposals for this area yet"],
+        );
+    }
     ChooseSomething::new_state(
         ctx,
         "Load which proposal?",
-        // basename (and thus list_all_objects) turn "foo.json.gz" into "foo.json", so further
-        // strip out the extension.
-        // TODO Fix basename, but make sure nothing downstream breaks
-        Choice::strings(
-            abstio::list_all_objects(abstio::path_all_ltn_proposals(app.per_map.map.get_name()))
-                .into_iter()
-                .map(abstutil::basename)
-                .collect(),
-        ),
+        plans
+            .into_iter()
+            .map(|plan| Choice::new(plan.describe(), plan.name))
+            .collect(),
         Box::new(move |name, ctx, app| {
             match Proposal::load_from_path(
                 ctx,
                 app,
                 abstio::path_ltn_proposals(app.per_map.map.get_name(), &name),
             ) {
-                Some(err_state) => Transition::Replace(err_state),
+                // This might be an error or just a warning. Either way, the current proposal is
+                // usable.
+                Some(popup) => Transition::Multi(vec![
+                    preserve_state.switch_to_state(ctx, app),
+                    Transition::Push(popup),
+                ]),
                 None => preserve_state.switch_to_state(ctx, app),
             }
         }),
@@ -365,6 +459,9 @@ pub struct Proposals {
     // All entries are filled out, except for the current proposal being worked on
     list: Vec<Option<Proposal>>,
     current: usize,
+    /// While this is above zero, `before_edit` does nothing, so a bulk operation becomes one step
+    /// in the undo history
+    edit_group_depth: usize,
 
     pub current_proposal: Proposal,
 }
@@ -372,9 +469,11 @@ pub struct Proposals {
 impl Proposals {
     // This calculates partitioning, which is expensive
     pub fn new(map: &Map, edits: Edits, timer: &mut Timer) -> Self {
+        let saved_key = edits.get_save_key();
         Self {
             list: vec![None],
             current: 0,
+            edit_group_depth: 0,
 
             current_proposal: Proposal {
                 map: map.get_name().clone(),
@@ -383,10 +482,21 @@ impl Proposals {
                 partitioning: Partitioning::seed_using_heuristics(map, timer),
                 edits,
                 unsaved_parent: None,
+                saved_key,
             },
         }
     }
 
+    /// Treat the current edits as saved, so the proposal no longer looks modified
+    pub fn mark_saved(&mut self) {
+        self.current_proposal.saved_key = self.current_proposal.edits.get_save_key();
+    }
+
+    /// Does the proposal being worked on have changes that'd be lost without saving?
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.current_proposal.has_unsaved_changes()
+    }
+
     // Special case for locking into a consultation mode
     pub fn clear_all_but_current(&mut self) {
         self.list = vec![None];
@@ -395,6 +505,13 @@ impl Proposals {
 
     /// Call before making any changes to fork a copy of the proposal and to preserve edit history
     pub fn before_edit(&mut self) {
+        share::forget_proposal_in_url();
+
+        // The group already forked and saved history when it started
+        if self.edit_group_depth > 0 {
+            return;
+        }
+
         // Fork the proposal or not?
         if self.current_proposal.unsaved_parent.is_none() {
             // Fork a new proposal if we're starting from the immutable baseline
@@ -405,14 +522,13 @@ impl Proposals {
                 self.current += 1;
                 assert!(self.list[self.current].is_none());
             }
-            // Otherwise, just replace the current proposal with something that's clearly edited
+            // Otherwise, just remember what the current proposal was forked from. The unsaved
+            // marker in its name comes from comparing against `saved_key`.
             self.current_proposal.unsaved_parent = Some(self.current_proposal.name.clone());
             if from_immutable {
                 // There'll be name collision if people start multiple unsaved files, but it
                 // shouldn't cause problems
-                self.current_proposal.name = "new proposal*".to_string();
-            } else {
-                self.current_proposal.name = format!("{}*", self.current_proposal.name);
+                self.current_proposal.name = "new proposal".to_string();
             }
         }
 
@@ -421,14 +537,34 @@ impl Proposals {
         self.current_proposal.edits.previous_version = Box::new(Some(copy));
     }
 
+    /// Start a bulk operation, like placing many filters at once, that should be undone all at
+    /// once. Everything until the matching `finish_edit_group` becomes a single undo step, even if
+    /// the helpers used along the way call `before_edit` themselves. Groups can nest.
+    pub fn start_edit_group(&mut self) {
+        self.before_edit();
+        self.edit_group_depth += 1;
+    }
+
+    /// Ends a group from `start_edit_group`. Like `cancel_empty_edit`, returns true if the group
+    /// changed nothing, in which case it leaves no undo step behind.
+    pub fn finish_edit_group(&mut self) -> bool {
+        assert!(
+            self.edit_group_depth > 0,
+            "finish_edit_group called without start_edit_group"
+        );
+        self.edit_group_depth -= 1;
+        self.cancel_empty_edit()
+    }
+
     /// If it's possible no edits were made, undo the previous call to `before_edit` and collapse
     /// the redundant piece of history. Returns true if the edit was indeed empty.
     pub fn cancel_empty_edit(&mut self) -> bool {
+        // Wait until the whole group is done to decide
+        if self.edit_group_depth > 0 {
+            return false;
+        }
         if let Some(prev) = self.current_proposal.edits.previous_version.take() {
-            if self.current_proposal.edits.roads == prev.roads
-                && self.current_proposal.edits.intersections == prev.intersections
-                && self.current_proposal.edits.one_ways == prev.one_ways
-            {
+            if self.current_proposal.edits.get_save_key() == prev.get_save_key() {
                 self.current_proposal.edits.previous_version = prev.previous_version;
 
                 // TODO Maybe "unfork" the proposal -- remove the unsaved marker. But that depends
@@ -451,6 +587,8 @@ impl Proposals {
             ("Save", "save", Some(MultiKey::from(lctrl(Key::S)))),
             ("Share", "share", None),
             ("Export GeoJSON", "export", None),
+            ("Export OSM tags", "map", None),
+            ("Plan details", "info", None),
         ] {
             col.push(
                 ctx.style()
@@ -465,7 +603,7 @@ impl Proposals {
             let button = if let Some(proposal) = proposal {
                 ctx.style()
                     .btn_solid_primary
-                    .text(format!("{} - {}", idx + 1, proposal.name))
+                    .text(format!("{} - {}", idx + 1, proposal.display_name()))
                     .hotkey(Key::NUM_KEYS[idx])
                     .build_widget(ctx, &format!("switch to proposal {}", idx))
             } else {
@@ -474,7 +612,7 @@ impl Proposals {
                     .text(format!(
                         "{} - {}",
                         idx + 1,
-                        app.per_map.proposals.current_proposal.name
+                        app.per_map.proposals.current_proposal.display_name()
                     ))
                     .disabled(true)
                     .build_def(ctx)
@@ -508,6 +646,8 @@ impl Proposals {
             ("Save", "save"),
             ("Share", "share"),
             ("Export GeoJSON", "export"),
+            ("Export OSM tags", "map"),
+            ("Plan details", "info"),
         ] {
             col.push(
                 ctx.style()
@@ -562,6 +702,28 @@ impl Proposals {
                     Err(err) => PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()]),
                 }));
             }
+            "Export OSM tags" => {
+                let result = crate::export::write_osm_tags_file(app);
+                return Some(Transition::Push(match result {
+                    Ok(path) => PopupMsg::new_state(
+                        ctx,
+                        "OSM tags exported",
+                        vec![
+                            format!("Suggested tags for each new filter written to {}", path),
+                            "Check them against local mapping conventions before uploading"
+                                .to_string(),
+                        ],
+                    ),
+                    Err(err) => PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()]),
+                }));
+            }
+            "Plan details" => {
+                return Some(Transition::Push(plan_details::PlanDetails::new_state(
+                    ctx,
+                    app,
+                    preserve_state.clone(),
+                )));
+            }
             _ => {
                 if let Some(x) = action.strip_prefix("switch to proposal ") {
                     let idx = x.parse::<usize>().unwrap();
@@ -626,7 +788,11 @@ impl PreserveState {
                     // shortcut maybe.
                     *maybe_focus = None;
                 }
-                if let pages::EditMode::FreehandFilters(_) = app.session.edit_mode {
+                if let pages::EditMode::FreehandFilters(_)
+                | pages::EditMode::SelectFilters(_)
+                | pages::EditMode::SketchCell(_)
+                | pages::EditMode::ConnectCells(_) = app.session.edit_mode
+                {
                     app.session.edit_mode = pages::EditMode::Filters;
                 }
 
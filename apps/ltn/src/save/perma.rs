@@ -73,7 +73,15 @@ fn is_road_id(path: &str) -> bool {
             Regex::new(r"^/edits/intersections/\d+/1/r2$").unwrap(),
             Regex::new(r"^/edits/intersections/\d+/1/group1/y$").unwrap(),
             Regex::new(r"^/edits/intersections/\d+/1/group2/y$").unwrap(),
+            Regex::new(r"^/edits/intersections/\d+/1/\d+/r1$").unwrap(),
+            Regex::new(r"^/edits/intersections/\d+/1/\d+/r2$").unwrap(),
+            Regex::new(r"^/edits/intersections/\d+/1/\d+/group1/y$").unwrap(),
+            Regex::new(r"^/edits/intersections/\d+/1/\d+/group2/y$").unwrap(),
+            Regex::new(r"^/edits/banned_turns/\d+/1/\d+/from$").unwrap(),
+            Regex::new(r"^/edits/banned_turns/\d+/1/\d+/to$").unwrap(),
             Regex::new(r"^/edits/one_ways/\d+/0$").unwrap(),
+            Regex::new(r"^/edits/road_names/\d+/0$").unwrap(),
+            Regex::new(r"^/edits/speed_limits/\d+/0$").unwrap(),
             // First place a Block is stored
             Regex::new(r"^/partitioning/single_blocks/\d+/perimeter/interior/\d+$").unwrap(),
             Regex::new(r"^/partitioning/single_blocks/\d+/perimeter/roads/\d+/road$").unwrap(),
@@ -91,6 +99,8 @@ fn is_intersection_id(path: &str) -> bool {
         static ref PATTERNS: Vec<Regex> = vec![
             Regex::new(r"^/edits/intersections/\d+/0$").unwrap(),
             Regex::new(r"^/edits/intersections/\d+/1/i$").unwrap(),
+            Regex::new(r"^/edits/intersections/\d+/1/\d+/i$").unwrap(),
+            Regex::new(r"^/edits/banned_turns/\d+/0$").unwrap(),
         ];
     }
 
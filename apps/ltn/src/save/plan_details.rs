@@ -0,0 +1,101 @@
+use map_gui::tools::grey_out_map;
+use widgetry::{
+    DrawBaselayer, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, TextBox, TextExt, Widget,
+};
+
+use super::PreserveState;
+use crate::{mut_edits, App, PlanMetadata, Transition};
+
+/// Edit the title, author, and so on for the whole plan. These get embedded in shared proposals
+/// and in the GeoJSON and OSM tags exports.
+pub struct PlanDetails {
+    panel: Panel,
+    preserve_state: PreserveState,
+}
+
+impl PlanDetails {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        preserve_state: PreserveState,
+    ) -> Box<dyn State<App>> {
+        let metadata = &app.edits().metadata;
+        let row = |label: &str, name: &str, value: &String| {
+            Widget::row(vec![
+                label.text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, name, value.clone()).align_right(),
+            ])
+        };
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Plan details").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            row("Title", "title", &metadata.title),
+            row("Neighbourhood", "neighbourhood", &metadata.neighbourhood),
+            row("Author", "author", &metadata.author),
+            row("Description", "description", &metadata.description),
+            row("Date", "date", &metadata.date),
+            Line("These are included when you share the proposal, and in GeoJSON and OSM tag exports")
+                .secondary()
+                .into_widget(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Update")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        ]))
+        .keyboard_navigation()
+        .build(ctx);
+
+        Box::new(Self {
+            panel,
+            preserve_state,
+        })
+    }
+}
+
+impl State<App> for PlanDetails {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Update" => {
+                    let metadata = PlanMetadata {
+                        title: self.panel.text_box("title").trim().to_string(),
+                        author: self.panel.text_box("author").trim().to_string(),
+                        description: self.panel.text_box("description").trim().to_string(),
+                        date: self.panel.text_box("date").trim().to_string(),
+                        neighbourhood: self.panel.text_box("neighbourhood").trim().to_string(),
+                    };
+                    if metadata == app.edits().metadata {
+                        return Transition::Pop;
+                    }
+
+                    app.per_map.proposals.before_edit();
+                    mut_edits!(app).metadata = metadata;
+                    // The proposal might've been forked, so the panel needs to change
+                    return self.preserve_state.switch_to_state(ctx, app);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if ctx.input.pressed(Key::Escape) {
+            return Transition::Pop;
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
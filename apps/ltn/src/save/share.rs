@@ -1,5 +1,8 @@
 use std::collections::BTreeSet;
 
+use anyhow::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
@@ -36,24 +39,19 @@ impl ShareProposal {
             Line("Share this proposal").small_heading().into_widget(ctx),
             ctx.style().btn_close_widget(ctx),
         ])];
+        let title = &app.edits().metadata.title;
+        if !title.is_empty() {
+            col.push(Line(title).secondary().into_widget(ctx));
+        }
         if UploadedProposals::load().md5sums.contains(&checksum) {
-            let map_path = app
-                .per_map
-                .map
-                .get_name()
-                .path()
-                .strip_prefix(&abstio::path(""))
-                .unwrap()
-                .to_string();
             let consultation = if let Some(ref x) = app.per_map.consultation_id {
                 format!("&--consultation={x}")
             } else {
                 String::new()
             };
             url = Some(format!(
-                "http://play.abstreet.org/{}/ltn.html?{}&--proposal=remote/{}{}",
-                map_gui::tools::version(),
-                map_path,
+                "{}&--proposal=remote/{}{}",
+                base_url(app),
                 checksum,
                 consultation
             ));
@@ -104,7 +102,20 @@ impl ShareProposal {
             ]));
         }
 
-        let panel = Panel::new_builder(Widget::col(col)).build(ctx);
+        col.push(Widget::horiz_separator(ctx, 1.0));
+        col.push(
+            "Or share a link with the proposal embedded in it, without uploading".text_widget(ctx),
+        );
+        col.push(
+            ctx.style()
+                .btn_outline
+                .text("Share without uploading")
+                .build_def(ctx),
+        );
+
+        let panel = Panel::new_builder(Widget::col(col))
+            .keyboard_navigation()
+            .build(ctx);
         <dyn SimpleState<_>>::new_state(panel, Box::new(ShareProposal { url }))
     }
 }
@@ -174,6 +185,26 @@ impl SimpleState<App> for ShareProposal {
                 open_browser(self.url.as_ref().unwrap());
                 Transition::Keep
             }
+            "Share without uploading" => {
+                let fragment = match proposal_to_url_fragment(app) {
+                    Ok(fragment) => fragment,
+                    Err(err) => {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![format!("Couldn't encode this proposal: {}", err)],
+                        ));
+                    }
+                };
+                let msg = if cfg!(target_arch = "wasm32") {
+                    URLManager::update_url_fragment(fragment);
+                    "Share your browser's URL."
+                } else {
+                    widgetry::tools::set_clipboard(format!("{}#{}", base_url(app), fragment));
+                    "The link was copied to your clipboard."
+                };
+                Transition::Replace(PopupMsg::new_state(ctx, "Link created", vec![msg]))
+            }
             _ => unreachable!(),
         }
     }
@@ -187,6 +218,85 @@ impl SimpleState<App> for ShareProposal {
     }
 }
 
+/// The web URL for the current map, with no proposal
+fn base_url(app: &App) -> String {
+    let map_path = app
+        .per_map
+        .map
+        .get_name()
+        .path()
+        .strip_prefix(&abstio::path(""))
+        .unwrap()
+        .to_string();
+    // On the web, link back with the same scheme as the page
+    let scheme = URLManager::get_url_scheme().unwrap_or_else(|| "https".to_string());
+    format!(
+        "{}://play.abstreet.org/{}/ltn.html?{}",
+        scheme,
+        map_gui::tools::version(),
+        map_path
+    )
+}
+
+/// Encodes the current proposal the same way as a saved file (gzipped JSON), then as base64, for
+/// use as a URL fragment
+fn proposal_to_url_fragment(app: &App) -> Result<String> {
+    let bytes = app
+        .per_map
+        .proposals
+        .current_proposal
+        .to_gzipped_bytes(app)?;
+    Ok(format!("plan={}", URL_SAFE_NO_PAD.encode(bytes)))
+}
+
+/// If the URL fragment contains a proposal, decode it into the bytes of a saved file
+pub fn proposal_from_url_fragment(fragment: &str) -> Option<Result<Vec<u8>>> {
+    let encoded = fragment.strip_prefix("plan=")?;
+    Some(URL_SAFE_NO_PAD.decode(encoded).map_err(|err| err.into()))
+}
+
+/// A proposal embedded in the URL goes stale once it's edited or another proposal is opened. Remove
+/// it, so reloading or sharing the page doesn't bring back the old version.
+pub fn forget_proposal_in_url() {
+    if URLManager::get_url_fragment()
+        .map(|fragment| fragment.starts_with("plan="))
+        .unwrap_or(false)
+    {
+        URLManager::clear_url_fragment();
+    }
+}
+
+/// After opening a shared proposal, say what it is, using the details the author set. Returns
+/// nothing if they didn't set any.
+pub fn describe_shared_proposal(ctx: &mut EventCtx, app: &App) -> Option<Box<dyn State<App>>> {
+    let metadata = &app.edits().metadata;
+    if metadata.is_empty() {
+        return None;
+    }
+    let mut lines = Vec::new();
+    for (label, value) in [
+        ("Neighbourhood", &metadata.neighbourhood),
+        ("Author", &metadata.author),
+        ("Date", &metadata.date),
+    ] {
+        if !value.is_empty() {
+            lines.push(format!("{}: {}", label, value));
+        }
+    }
+    if !metadata.description.is_empty() {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(metadata.description.clone());
+    }
+    let title = if metadata.title.is_empty() {
+        "Shared proposal"
+    } else {
+        &metadata.title
+    };
+    Some(PopupMsg::new_state(ctx, title, lines))
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UploadedProposals {
     pub md5sums: BTreeSet<String>,
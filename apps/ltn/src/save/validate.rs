@@ -0,0 +1,88 @@
+use abstio::MapName;
+use abstutil::Timer;
+use map_model::Map;
+
+use super::index::count_raw_filters;
+use super::Proposal;
+
+/// For maintainers: after re-importing maps, check that every locally saved proposal still loads
+/// onto its map. Prints a line per proposal, with details about anything that failed or was
+/// dropped. Returns true if everything loaded cleanly.
+pub fn validate_all_proposals(timer: &mut Timer) -> bool {
+    let mut num_ok = 0;
+    let mut num_problems = 0;
+
+    for name in MapName::list_all_maps_locally() {
+        // list_all_objects only strips the ".gz", so strip the ".json" too
+        let proposals: Vec<String> =
+            abstio::list_all_objects(abstio::path_all_ltn_proposals(&name))
+                .into_iter()
+                .map(abstutil::basename)
+                .collect();
+        if proposals.is_empty() {
+            continue;
+        }
+        let map = Map::load_synchronously(name.path(), timer);
+
+        for proposal_name in proposals {
+            let path = abstio::path_ltn_proposals(&name, &proposal_name);
+            let problems = check_proposal(&map, &path);
+            if problems.is_empty() {
+                println!("PASS {}", path);
+                num_ok += 1;
+            } else {
+                println!("FAIL {}", path);
+                for problem in problems {
+                    println!("  - {}", problem);
+                }
+                num_problems += 1;
+            }
+        }
+    }
+
+    println!(
+        "{} proposals loaded cleanly, {} had problems",
+        num_ok, num_problems
+    );
+    num_problems == 0
+}
+
+/// Returns everything wrong with one proposal. Empty means it's fine.
+fn check_proposal(map: &Map, path: &str) -> Vec<String> {
+    let bytes = match abstio::slurp_file(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return vec![format!("couldn't read the file: {}", err)];
+        }
+    };
+    match Proposal::decode(map, bytes) {
+        Ok((proposal, mut warnings)) => {
+            if &proposal.map != map.get_name() {
+                warnings.push(format!(
+                    "the proposal claims to be for {}",
+                    proposal.map.describe()
+                ));
+            }
+            warnings
+        }
+        Err(err) => vec![format!(
+            "couldn't load, so all {} filters would be lost: {}",
+            count_filters(path),
+            err
+        )],
+    }
+}
+
+/// When a proposal fails to remap onto the map at all, count the filters it contained, by looking
+/// at the raw JSON without resolving any IDs.
+fn count_filters(path: &str) -> String {
+    let count = || -> anyhow::Result<usize> {
+        let bytes = abstio::slurp_file(path)?;
+        let decoder = flate2::read::GzDecoder::new(&bytes[..]);
+        let value: serde_json::Value = serde_json::from_reader(decoder)?;
+        Ok(count_raw_filters(&value, false))
+    };
+    count()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|_| "?".to_string())
+}
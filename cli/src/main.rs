@@ -14,7 +14,7 @@ mod one_step_import;
 use std::io::Write;
 
 use abstio::CityName;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use fs_err::File;
 use importer::Job;
 use structopt::StructOpt;
@@ -222,6 +222,9 @@ enum Command {
         #[structopt()]
         scenario_path: String,
     },
+    /// Check that every locally saved LTN proposal still loads onto its map. Useful after
+    /// re-importing maps.
+    ValidateLtnProposals,
 }
 
 // See https://github.com/TeXitoi/structopt/issues/94
@@ -340,6 +343,11 @@ async fn main() -> Result<()> {
         Command::RegenerateEverythingExternally => regenerate_everything_externally()?,
         Command::Import { job } => job.run(&mut Timer::new("import one city")).await,
         Command::PrebakeScenario { scenario_path } => prebake_scenario(scenario_path),
+        Command::ValidateLtnProposals => {
+            if !ltn::validate_all_proposals(&mut Timer::new("validate LTN proposals")) {
+                bail!("Some LTN proposals didn't load cleanly");
+            }
+        }
     }
     Ok(())
 }
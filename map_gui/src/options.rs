@@ -43,6 +43,9 @@ pub struct Options {
     /// If true, draw an icon for traffic signals both when zoomed and unzoomed. If false, color
     /// the intersection when unzoomed and render the signal's current state when zoomed.
     pub show_traffic_signal_icon: bool,
+    /// Show a tooltip when hovering on roads and intersections in data layers
+    #[serde(default = "default_true")]
+    pub show_layer_tooltips: bool,
     /// If true, modify several basemap features to de-emphasize them: border intersections
     pub simplify_basemap: bool,
 
@@ -94,6 +97,7 @@ impl Options {
             show_stop_signs: true,
             show_crosswalks: true,
             show_traffic_signal_icon: false,
+            show_layer_tooltips: true,
             simplify_basemap: false,
 
             time_increment: Duration::minutes(10),
@@ -112,6 +116,11 @@ impl Options {
     }
 }
 
+// Settings saved before an option existed should keep working
+fn default_true() -> bool {
+    true
+}
+
 /// Different ways of drawing traffic signals. The names of these aren't super meaningful...
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TrafficSignalStyle {
@@ -276,6 +285,12 @@ impl OptionsPanel {
                         None,
                         app.opts().units.metric,
                     ),
+                    Toggle::checkbox(
+                        ctx,
+                        "Show tooltips when hovering on data layers",
+                        None,
+                        app.opts().show_layer_tooltips,
+                    ),
                 ])
                 .bg(app.cs().inner_panel_bg)
                 .padding(8),
@@ -383,6 +398,9 @@ impl<A: AppLike> State<A> for OptionsPanel {
                     }
 
                     opts.units.metric = self.panel.is_checked("metric / imperial units");
+                    opts.show_layer_tooltips = self
+                        .panel
+                        .is_checked("Show tooltips when hovering on data layers");
 
                     let language = self.panel.dropdown_value("language");
                     if language != opts.language {
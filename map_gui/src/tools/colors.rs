@@ -1,22 +1,25 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use abstutil::Counter;
 use geom::{Circle, Distance};
 use map_model::{BuildingID, IntersectionID, LaneID, Map, ParkingLotID, RoadID, TransitStopID};
 use widgetry::mapspace::{ToggleZoomed, ToggleZoomedBuilder};
 use widgetry::tools::{ColorLegend, ColorScale};
-use widgetry::{Color, EventCtx, GeomBatch, Widget};
+use widgetry::{Color, EventCtx, GeomBatch, Toggle, Widget};
 
 use crate::AppLike;
 
 // TODO Tooltips would almost be nice, for cases like pedestrian crowding
 pub struct ColorDiscrete<'a> {
     map: &'a Map,
-    // pub so callers can add stuff in before building
+    fade: GeomBatch,
+    // pub so callers can add stuff in before building. This is drawn on top of the categories.
     pub draw: ToggleZoomedBuilder,
     // Store both, so we can build the legend in the original order later
     pub categories: Vec<(String, Color)>,
     colors: HashMap<String, Color>,
+    // Kept separate from draw, so individual categories can be hidden later
+    per_category: HashMap<String, ToggleZoomedBuilder>,
 }
 
 impl<'a> ColorDiscrete<'a> {
@@ -24,17 +27,18 @@ impl<'a> ColorDiscrete<'a> {
         app: &'a dyn AppLike,
         categories: Vec<(I, Color)>,
     ) -> ColorDiscrete<'a> {
-        let mut draw = ToggleZoomed::builder();
-        draw.unzoomed.push(
+        let fade = GeomBatch::from(vec![(
             app.cs().fade_map_dark,
             app.map().get_boundary_polygon().clone(),
-        );
+        )]);
         let categories: Vec<(String, Color)> =
             categories.into_iter().map(|(k, v)| (k.into(), v)).collect();
         ColorDiscrete {
             map: app.map(),
-            draw,
+            fade,
+            draw: ToggleZoomed::builder(),
             colors: categories.iter().cloned().collect(),
+            per_category: HashMap::new(),
             categories,
         }
     }
@@ -44,70 +48,134 @@ impl<'a> ColorDiscrete<'a> {
         categories: Vec<(I, Color)>,
     ) -> ColorDiscrete<'a> {
         let mut c = ColorDiscrete::new(app, categories);
-        c.draw.unzoomed = GeomBatch::new();
+        c.fade = GeomBatch::new();
         c
     }
 
+    fn push<I: AsRef<str>>(&mut self, category: I, unzoomed: geom::Polygon, zoomed: geom::Polygon) {
+        let category = category.as_ref();
+        let color = self.colors[category];
+        let draw = self
+            .per_category
+            .entry(category.to_string())
+            .or_insert_with(ToggleZoomed::builder);
+        draw.unzoomed.push(color, unzoomed);
+        draw.zoomed.push(color.alpha(0.4), zoomed);
+    }
+
     pub fn add_l<I: AsRef<str>>(&mut self, l: LaneID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_parent(l).get_thick_polygon());
-        let lane = self.map.get_l(l);
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), lane.get_thick_polygon());
+        let unzoomed = self.map.get_parent(l).get_thick_polygon();
+        let zoomed = self.map.get_l(l).get_thick_polygon();
+        self.push(category, unzoomed, zoomed);
     }
 
     pub fn add_r<I: AsRef<str>>(&mut self, r: RoadID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_r(r).get_thick_polygon());
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), self.map.get_r(r).get_thick_polygon());
+        let polygon = self.map.get_r(r).get_thick_polygon();
+        self.push(category, polygon.clone(), polygon);
     }
 
     pub fn add_i<I: AsRef<str>>(&mut self, i: IntersectionID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_i(i).polygon.clone());
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), self.map.get_i(i).polygon.clone());
+        let polygon = self.map.get_i(i).polygon.clone();
+        self.push(category, polygon.clone(), polygon);
     }
 
     pub fn add_b<I: AsRef<str>>(&mut self, b: BuildingID, category: I) {
-        let color = self.colors[category.as_ref()];
-        self.draw
-            .unzoomed
-            .push(color, self.map.get_b(b).polygon.clone());
-        self.draw
-            .zoomed
-            .push(color.alpha(0.4), self.map.get_b(b).polygon.clone());
+        let polygon = self.map.get_b(b).polygon.clone();
+        self.push(category, polygon.clone(), polygon);
     }
 
     pub fn add_ts<I: AsRef<str>>(&mut self, ts: TransitStopID, category: I) {
-        let color = self.colors[category.as_ref()];
         let pt = self.map.get_ts(ts).sidewalk_pos.pt(self.map);
-        self.draw.zoomed.push(
-            color.alpha(0.4),
+        self.push(
+            category,
+            Circle::new(pt, Distance::meters(15.0)).to_polygon(),
             Circle::new(pt, Distance::meters(5.0)).to_polygon(),
         );
-        self.draw
-            .unzoomed
-            .push(color, Circle::new(pt, Distance::meters(15.0)).to_polygon());
     }
 
     pub fn build(self, ctx: &EventCtx) -> (ToggleZoomed, Widget) {
         let legend = self
             .categories
-            .into_iter()
-            .map(|(name, color)| ColorLegend::row(ctx, color, name))
+            .iter()
+            .map(|(name, color)| ColorLegend::row(ctx, *color, name))
+            .collect();
+        (self.into_builder().build(ctx), Widget::col(legend))
+    }
+
+    /// Combines the fading, every category, and anything callers added to `draw`, without a
+    /// legend
+    pub fn into_builder(mut self) -> ToggleZoomedBuilder {
+        let mut draw = ToggleZoomed::builder();
+        draw.unzoomed.append(self.fade);
+        for (name, _) in &self.categories {
+            if let Some(batch) = self.per_category.remove(name) {
+                draw.append(batch);
+            }
+        }
+        draw.append(self.draw);
+        draw
+    }
+
+    /// Like `build`, but the legend has a checkbox per category to hide or show it. Feed
+    /// `Outcome::Changed` from the panel into `HideableCategories::toggle`.
+    pub fn build_hideable(mut self, ctx: &EventCtx) -> (HideableCategories, ToggleZoomed, Widget) {
+        let legend = self
+            .categories
+            .iter()
+            .map(|(name, color)| Toggle::colored_checkbox(ctx, name, *color, true))
             .collect();
-        (self.draw.build(ctx), Widget::col(legend))
+        let hideable = HideableCategories {
+            fade: self.fade,
+            extra: self.draw,
+            per_category: self
+                .categories
+                .into_iter()
+                .map(|(name, _)| {
+                    let draw = self
+                        .per_category
+                        .remove(&name)
+                        .unwrap_or_else(ToggleZoomed::builder);
+                    (name, draw)
+                })
+                .collect(),
+            hidden: BTreeSet::new(),
+        };
+        let draw = hideable.build(ctx);
+        (hideable, draw, Widget::col(legend))
+    }
+}
+
+/// The output of `ColorDiscrete::build_hideable`, remembering each category's geometry
+pub struct HideableCategories {
+    fade: GeomBatch,
+    extra: ToggleZoomedBuilder,
+    per_category: Vec<(String, ToggleZoomedBuilder)>,
+    hidden: BTreeSet<String>,
+}
+
+impl HideableCategories {
+    /// If `category` is one of the legend entries, flip whether it's shown and return the new
+    /// drawable. Otherwise returns `None`, so callers can handle their own widgets.
+    pub fn toggle(&mut self, ctx: &EventCtx, category: &str) -> Option<ToggleZoomed> {
+        if !self.per_category.iter().any(|(name, _)| name == category) {
+            return None;
+        }
+        if !self.hidden.remove(category) {
+            self.hidden.insert(category.to_string());
+        }
+        Some(self.build(ctx))
+    }
+
+    fn build(&self, ctx: &EventCtx) -> ToggleZoomed {
+        let mut draw = ToggleZoomed::builder();
+        draw.unzoomed.append(self.fade.clone());
+        for (name, batch) in &self.per_category {
+            if !self.hidden.contains(name) {
+                draw.append(batch.clone());
+            }
+        }
+        draw.append(self.extra.clone());
+        draw.build(ctx)
     }
 }
 
@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -212,6 +212,7 @@ pub struct DrawSimpleRoadLabels {
     draw: Drawable,
     include_roads: Box<dyn Fn(&Road) -> bool>,
     fg_color: Color,
+    name_overrides: BTreeMap<RoadID, String>,
 
     pub label_covers_road: HashMap<RoadID, (Distance, Distance)>,
 }
@@ -223,11 +224,23 @@ impl DrawSimpleRoadLabels {
         app: &dyn AppLike,
         fg_color: Color,
         include_roads: Box<dyn Fn(&Road) -> bool>,
+    ) -> Self {
+        Self::new_with_name_overrides(ctx, app, fg_color, include_roads, BTreeMap::new())
+    }
+
+    /// Like `new`, but use a different name for some roads
+    pub fn new_with_name_overrides(
+        ctx: &mut EventCtx,
+        app: &dyn AppLike,
+        fg_color: Color,
+        include_roads: Box<dyn Fn(&Road) -> bool>,
+        name_overrides: BTreeMap<RoadID, String>,
     ) -> Self {
         let mut labels = Self {
             draw: Drawable::empty(ctx),
             include_roads,
             fg_color,
+            name_overrides,
             label_covers_road: HashMap::new(),
         };
         ctx.loading_screen("label roads", |ctx, timer| {
@@ -241,6 +254,7 @@ impl DrawSimpleRoadLabels {
             draw: Drawable::empty(ctx),
             include_roads: Box::new(|_| false),
             fg_color: Color::CLEAR,
+            name_overrides: BTreeMap::new(),
             label_covers_road: HashMap::new(),
         }
     }
@@ -275,7 +289,9 @@ impl DrawSimpleRoadLabels {
                 continue;
             }
 
-            let name = if let Some(x) = simplify_name(r.get_name(app.opts().language.as_ref())) {
+            let name = if let Some(x) = self.name_overrides.get(&r.id) {
+                x.clone()
+            } else if let Some(x) = simplify_name(r.get_name(app.opts().language.as_ref())) {
                 x
             } else {
                 continue;
@@ -1,9 +1,10 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
+use geom::Bounds;
 use map_model::RoadID;
 use widgetry::{
-    Autocomplete, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome,
-    Panel, State, Text, Transition, Widget,
+    Autocomplete, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment,
+    Key, Line, Outcome, Panel, State, Text, Transition, VerticalAlignment, Warper, Widget,
 };
 
 use crate::tools::grey_out_map;
@@ -13,6 +14,7 @@ use crate::{AppLike, ID};
 pub struct Navigator {
     panel: Panel,
     target_zoom: f64,
+    road_names: BTreeMap<RoadID, String>,
 }
 
 impl Navigator {
@@ -24,9 +26,19 @@ impl Navigator {
         ctx: &mut EventCtx,
         app: &A,
         target_zoom: f64,
+    ) -> Box<dyn State<A>> {
+        Self::new_state_with_road_names(ctx, app, target_zoom, BTreeMap::new())
+    }
+
+    /// Like `new_state_with_target_zoom`, but for apps that let people rename roads. Roads in
+    /// `road_names` are searched and shown by that name instead of the one from the map.
+    pub fn new_state_with_road_names<A: AppLike + 'static>(
+        ctx: &mut EventCtx,
+        app: &A,
+        target_zoom: f64,
+        road_names: BTreeMap<RoadID, String>,
     ) -> Box<dyn State<A>> {
         Box::new(Navigator {
-            target_zoom,
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::row(vec![
                     Line("Enter a street name").small_heading().into_widget(ctx),
@@ -37,7 +49,7 @@ impl Navigator {
                     app.map()
                         .all_roads()
                         .iter()
-                        .map(|r| (r.get_name(app.opts().language.as_ref()), r.id))
+                        .map(|r| (road_name(app, &road_names, r.id), r.id))
                         .collect(),
                     10,
                 )
@@ -49,6 +61,8 @@ impl Navigator {
                     .build_def(ctx),
             ]))
             .build(ctx),
+            target_zoom,
+            road_names,
         })
     }
 }
@@ -65,6 +79,7 @@ impl<A: AppLike + 'static> State<A> for Navigator {
                         ctx,
                         app,
                         self.target_zoom,
+                        std::mem::take(&mut self.road_names),
                     ));
                 }
                 _ => unreachable!(),
@@ -74,7 +89,13 @@ impl<A: AppLike + 'static> State<A> for Navigator {
             if roads.is_empty() {
                 return Transition::Pop;
             }
-            return Transition::Replace(CrossStreet::new_state(ctx, app, roads, self.target_zoom));
+            return Transition::Replace(CrossStreet::new_state(
+                ctx,
+                app,
+                roads,
+                self.target_zoom,
+                std::mem::take(&mut self.road_names),
+            ));
         }
 
         if self.panel.clicked_outside(ctx) {
@@ -99,6 +120,7 @@ struct CrossStreet {
     panel: Panel,
     draw: Drawable,
     target_zoom: f64,
+    road_names: BTreeMap<RoadID, String>,
 }
 
 impl CrossStreet {
@@ -107,6 +129,7 @@ impl CrossStreet {
         app: &A,
         first: Vec<RoadID>,
         target_zoom: f64,
+        road_names: BTreeMap<RoadID, String>,
     ) -> Box<dyn State<A>> {
         let map = app.map();
         let mut cross_streets = HashSet::new();
@@ -133,7 +156,7 @@ impl CrossStreet {
                         // TODO This isn't so clear...
                         txt.add_line(format!(
                             "(Or just quit to go to {})",
-                            map.get_r(first[0]).get_name(app.opts().language.as_ref()),
+                            road_name(app, &road_names, first[0]),
                         ));
                         txt.into_widget(ctx)
                     },
@@ -143,16 +166,21 @@ impl CrossStreet {
                     ctx,
                     cross_streets
                         .into_iter()
-                        .map(|r| (map.get_r(r).get_name(app.opts().language.as_ref()), r))
+                        .map(|r| (road_name(app, &road_names, r), r))
                         .collect(),
                     10,
                 )
                 .named("street"),
+                ctx.style()
+                    .btn_outline
+                    .text("Show the whole street")
+                    .build_def(ctx),
             ]))
             .build(ctx),
             first,
             draw: ctx.upload(batch),
             target_zoom,
+            road_names,
         })
     }
 }
@@ -173,6 +201,15 @@ impl<A: AppLike + 'static> State<A> for CrossStreet {
                         None,
                     ));
                 }
+                "Show the whole street" => {
+                    return Transition::Replace(ShowRoads::new_state(
+                        ctx,
+                        app,
+                        self.first.clone(),
+                        self.target_zoom,
+                        &self.road_names,
+                    ));
+                }
                 _ => unreachable!(),
             }
         }
@@ -219,9 +256,92 @@ impl<A: AppLike + 'static> State<A> for CrossStreet {
     }
 }
 
+/// Fly to fit every segment of a street on screen, and highlight them
+struct ShowRoads {
+    warper: Option<Warper>,
+    panel: Panel,
+    draw: Drawable,
+}
+
+impl ShowRoads {
+    fn new_state<A: AppLike + 'static>(
+        ctx: &mut EventCtx,
+        app: &A,
+        roads: Vec<RoadID>,
+        target_zoom: f64,
+        road_names: &BTreeMap<RoadID, String>,
+    ) -> Box<dyn State<A>> {
+        let map = app.map();
+        let mut batch = GeomBatch::new();
+        let mut bounds = Bounds::new();
+        for r in &roads {
+            let polygon = map.get_r(*r).get_thick_polygon();
+            bounds.union(polygon.get_bounds());
+            batch.push(Color::RED, polygon);
+        }
+
+        // Fit all of the segments on screen, but don't zoom in further than warping to one spot
+        // would
+        let zoom = (0.8 * ctx.canvas.window_width / bounds.width())
+            .min(0.8 * ctx.canvas.window_height / bounds.height())
+            .min(target_zoom);
+
+        let mut txt = Text::from(Line(road_name(app, road_names, roads[0])).small_heading());
+        txt.add_line(format!("{} road segments highlighted", roads.len()));
+
+        Box::new(ShowRoads {
+            warper: Some(Warper::new(ctx, bounds.center(), Some(zoom))),
+            panel: Panel::new_builder(Widget::row(vec![
+                txt.into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            draw: ctx.upload(batch),
+        })
+    }
+}
+
+impl<A: AppLike + 'static> State<A> for ShowRoads {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut A) -> Transition<A> {
+        if let Some(ref warper) = self.warper {
+            if warper.event(ctx) {
+                return Transition::Keep;
+            }
+            self.warper = None;
+        }
+
+        ctx.canvas_movement();
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+        if ctx.input.pressed(Key::Escape) || ctx.normal_left_click() {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
+
 struct SearchBuildings {
     panel: Panel,
     target_zoom: f64,
+    // Just to pass back to Navigator
+    road_names: BTreeMap<RoadID, String>,
 }
 
 impl SearchBuildings {
@@ -229,9 +349,11 @@ impl SearchBuildings {
         ctx: &mut EventCtx,
         app: &A,
         target_zoom: f64,
+        road_names: BTreeMap<RoadID, String>,
     ) -> Box<dyn State<A>> {
         Box::new(SearchBuildings {
             target_zoom,
+            road_names,
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::row(vec![
                     Line("Enter a business name or address")
@@ -290,10 +412,11 @@ impl<A: AppLike + 'static> State<A> for SearchBuildings {
                     return Transition::Pop;
                 }
                 "Search for streets" => {
-                    return Transition::Replace(Navigator::new_state_with_target_zoom(
+                    return Transition::Replace(Navigator::new_state_with_road_names(
                         ctx,
                         app,
                         self.target_zoom,
+                        std::mem::take(&mut self.road_names),
                     ));
                 }
                 _ => unreachable!(),
@@ -329,3 +452,10 @@ impl<A: AppLike + 'static> State<A> for SearchBuildings {
         self.panel.draw(g);
     }
 }
+
+fn road_name<A: AppLike>(app: &A, road_names: &BTreeMap<RoadID, String>, r: RoadID) -> String {
+    match road_names.get(&r) {
+        Some(name) => name.clone(),
+        None => app.map().get_r(r).get_name(app.opts().language.as_ref()),
+    }
+}
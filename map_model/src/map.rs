@@ -782,6 +782,19 @@ impl Map {
         self.pathfinder_dirty = false;
     }
 
+    /// Rebuilds pathfinding for cars to use different routing params. This is slow. Other modes
+    /// and `routing_params()` are unchanged. Any edits must be recalculated first.
+    pub fn hack_override_routing_params_for_cars(
+        &mut self,
+        params: RoutingParams,
+        timer: &mut Timer,
+    ) {
+        assert!(!self.pathfinder_dirty);
+        let mut pathfinder = std::mem::replace(&mut self.pathfinder, Pathfinder::empty());
+        pathfinder.rebuild_for_cars(self, params, timer);
+        self.pathfinder = pathfinder;
+    }
+
     pub fn get_languages(&self) -> BTreeSet<String> {
         let mut languages = BTreeSet::new();
         for r in self.all_roads() {
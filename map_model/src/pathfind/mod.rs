@@ -1,11 +1,11 @@
 //! Everything related to pathfinding through a map for different types of agents.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use enumset::EnumSetType;
 use serde::{Deserialize, Serialize};
 
-use geom::Duration;
+use geom::{Duration, Speed};
 
 pub use self::engine::CreateEngine;
 pub use self::pathfinder::{Pathfinder, PathfinderCache, PathfinderCaching};
@@ -13,7 +13,7 @@ pub use self::v1::{Path, PathRequest, PathStep};
 pub use self::v2::{PathStepV2, PathV2};
 pub use self::vehicles::vehicle_cost;
 pub use self::walking::WalkingNode;
-use crate::{osm, Lane, LaneID, LaneType, Map, MovementID, Road, RoadID, TurnType};
+use crate::{osm, DirectedRoadID, Lane, LaneID, LaneType, Map, MovementID, Road, RoadID, TurnType};
 
 mod engine;
 mod node_map;
@@ -198,6 +198,24 @@ pub struct RoutingParams {
     /// Don't allow movements between these roads at all. Only affects vehicle routing, not
     /// pedestrian.
     pub avoid_movements_between: BTreeSet<(RoadID, RoadID)>,
+
+    /// Add this much time to the cost of crossing these roads. Unlike `avoid_roads`, routes can
+    /// still use them if there's no better option. Only affects vehicle routing. This is only set
+    /// at runtime, so it's skipped to keep the map format the same.
+    #[serde(skip)]
+    pub road_penalties: BTreeMap<RoadID, Duration>,
+    /// Like `road_penalties`, but for movements between two roads
+    #[serde(skip)]
+    pub movement_penalties: BTreeMap<(RoadID, RoadID), Duration>,
+    /// Use these speed limits instead of the road's, for vehicles besides bikes travelling in one
+    /// direction. Also only set at runtime.
+    #[serde(skip)]
+    pub speed_limits: BTreeMap<DirectedRoadID, Speed>,
+    /// Add this much time to the cost of walking across these roads at an intersection, to
+    /// account for waiting at a crossing. Only affects pedestrian routing, and also only set at
+    /// runtime.
+    #[serde(skip)]
+    pub crossing_penalties: BTreeMap<RoadID, Duration>,
 }
 
 impl Default for RoutingParams {
@@ -219,6 +237,10 @@ impl Default for RoutingParams {
             avoid_roads: BTreeSet::new(),
             avoid_movements_between: BTreeSet::new(),
             only_use_roads: BTreeSet::new(),
+            road_penalties: BTreeMap::new(),
+            movement_penalties: BTreeMap::new(),
+            speed_limits: BTreeMap::new(),
+            crossing_penalties: BTreeMap::new(),
         }
     }
 }
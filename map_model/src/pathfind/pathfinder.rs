@@ -26,6 +26,9 @@ pub struct Pathfinder {
 
     // These params cover the main graphs
     params: RoutingParams,
+    // If set, the car graph was built with these instead. Only set at runtime.
+    #[serde(skip_serializing, skip_deserializing)]
+    car_params: Option<RoutingParams>,
 
     // Callers can opt into caching with pathfind_with_params
     // TODO VecMap is probably fast enough. RoutingParams is annoying to implement Hash.
@@ -57,6 +60,7 @@ impl Clone for Pathfinder {
             walking_graph: self.walking_graph.clone(),
             walking_with_transit_graph: self.walking_with_transit_graph.clone(),
             params: self.params.clone(),
+            car_params: self.car_params.clone(),
             cached_alternatives: ThreadLocal::new(),
         }
     }
@@ -74,6 +78,7 @@ impl Pathfinder {
             walking_graph: SidewalkPathfinder::empty(),
             walking_with_transit_graph: SidewalkPathfinder::empty(),
             params: RoutingParams::default(),
+            car_params: None,
             cached_alternatives: ThreadLocal::new(),
         }
     }
@@ -115,7 +120,7 @@ impl Pathfinder {
         timer.stop("prepare pathfinding for trains");
 
         timer.start("prepare pathfinding for pedestrians");
-        let walking_graph = SidewalkPathfinder::new(map, None, engine);
+        let walking_graph = SidewalkPathfinder::new(map, None, &params, engine);
         timer.stop("prepare pathfinding for pedestrians");
 
         // Transit routes haven't been created yet, so defer this step
@@ -130,6 +135,7 @@ impl Pathfinder {
             walking_with_transit_graph,
 
             params,
+            car_params: None,
             cached_alternatives: ThreadLocal::new(),
         }
     }
@@ -169,7 +175,7 @@ impl Pathfinder {
             timer.start(format!("prepare pathfinding for just {:?}", constraints));
             match constraints {
                 PathConstraints::Pedestrian => {
-                    p.walking_graph = SidewalkPathfinder::new(map, None, &engine);
+                    p.walking_graph = SidewalkPathfinder::new(map, None, &params, &engine);
                 }
                 PathConstraints::Car => {
                     p.car_graph = VehiclePathfinder::new(map, constraints, &params, &engine);
@@ -190,14 +196,42 @@ impl Pathfinder {
         p
     }
 
+    /// Replace just the graph for cars, using different routing params. Other modes keep their
+    /// graphs and params as they are.
+    pub(crate) fn rebuild_for_cars(&mut self, map: &Map, params: RoutingParams, timer: &mut Timer) {
+        timer.start("prepare pathfinding for cars");
+        self.car_graph =
+            VehiclePathfinder::new(map, PathConstraints::Car, &params, &CreateEngine::CH);
+        timer.stop("prepare pathfinding for cars");
+        self.car_params = Some(params);
+        self.cached_alternatives = ThreadLocal::new();
+    }
+
+    /// The params baked into the graph for one mode
+    fn params_for(&self, constraints: PathConstraints) -> &RoutingParams {
+        match (constraints, &self.car_params) {
+            (PathConstraints::Car, Some(params)) => params,
+            _ => &self.params,
+        }
+    }
+
     pub(crate) fn finalize_transit(&mut self, map: &Map, engine: &CreateEngine) {
-        self.walking_with_transit_graph =
-            SidewalkPathfinder::new(map, Some((&self.bus_graph, &self.train_graph)), engine);
+        self.walking_with_transit_graph = SidewalkPathfinder::new(
+            map,
+            Some((&self.bus_graph, &self.train_graph)),
+            &self.params,
+            engine,
+        );
     }
 
     /// Finds a path from a start to an end for a certain type of agent.
     pub fn pathfind(&self, req: PathRequest, map: &Map) -> Option<PathV2> {
-        self.pathfind_with_params(req, map.routing_params(), PathfinderCaching::NoCache, map)
+        // Cars might deliberately use their own params
+        let params = match (req.constraints, &self.car_params) {
+            (PathConstraints::Car, Some(params)) => params,
+            _ => map.routing_params(),
+        };
+        self.pathfind_with_params(req, params, PathfinderCaching::NoCache, map)
     }
 
     /// Finds a path from a start to an end for a certain type of agent. Uses the RoutingParams
@@ -224,7 +258,7 @@ impl Pathfinder {
         map: &Map,
     ) -> Option<PathV2> {
         let constraints = req.constraints;
-        if params == &self.params {
+        if params == self.params_for(constraints) {
             return match constraints {
                 PathConstraints::Pedestrian => self.walking_graph.pathfind(req, map),
                 PathConstraints::Car => self.car_graph.pathfind(req, map),
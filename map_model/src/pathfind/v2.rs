@@ -129,6 +129,20 @@ impl PathV2 {
         map: &Map,
         max_speed: Option<Speed>,
         main_road_penalty: Option<f64>,
+    ) -> Duration {
+        self.estimate_duration_with_speeds(map, max_speed, main_road_penalty, |dr| {
+            map.get_r(dr.road).speed_limit
+        })
+    }
+
+    /// Like `estimate_duration`, but the caller decides the speed limit of each road, in each
+    /// direction.
+    pub fn estimate_duration_with_speeds<F: Fn(DirectedRoadID) -> Speed>(
+        &self,
+        map: &Map,
+        max_speed: Option<Speed>,
+        main_road_penalty: Option<f64>,
+        speed_limit: F,
     ) -> Duration {
         let mut total = Duration::ZERO;
         for step in &self.steps {
@@ -138,7 +152,7 @@ impl PathV2 {
                 PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) => {
                     let road = map.get_r(dr.road);
                     dist = road.length();
-                    speed = road.speed_limit;
+                    speed = speed_limit(*dr);
 
                     if let Some(penalty) = main_road_penalty {
                         if road.get_rank() != osm::RoadRank::Local {
@@ -149,10 +163,7 @@ impl PathV2 {
                 PathStepV2::Movement(m) | PathStepV2::ContraflowMovement(m) => {
                     if let Some(movement) = map.get_movement(*m) {
                         dist = movement.geom.length();
-                        speed = map
-                            .get_r(m.from.road)
-                            .speed_limit
-                            .min(map.get_r(m.to.road).speed_limit);
+                        speed = speed_limit(m.from).min(speed_limit(m.to));
                     } else {
                         // Assume it's a SharedSidewalkCorner and just skip
                         continue;
@@ -317,7 +317,11 @@ pub fn vehicle_cost(
         PathConstraints::Bike => Some(crate::MAX_BIKE_SPEED),
         PathConstraints::Pedestrian => unreachable!(),
     };
-    let t1 = road.length() / Traversable::max_speed_along_road(dr, max_speed, constraints, map).0;
+    let speed = match params.speed_limits.get(&dr) {
+        Some(speed) if constraints != PathConstraints::Bike => *speed,
+        _ => Traversable::max_speed_along_road(dr, max_speed, constraints, map).0,
+    };
+    let t1 = road.length() / speed;
 
     let t2 = movement.geom.length()
         / Traversable::max_speed_along_movement(mvmnt, max_speed, constraints, map);
@@ -377,6 +381,15 @@ pub fn vehicle_cost(
     if map.is_unprotected_turn(dr.road, mvmnt.to.road, movement.turn_type) {
         extra += params.unprotected_turn_penalty
     }
+    if let Some(penalty) = params.road_penalties.get(&dr.road) {
+        extra += *penalty;
+    }
+    if let Some(penalty) = params
+        .movement_penalties
+        .get(&(mvmnt.from.road, mvmnt.to.road))
+    {
+        extra += *penalty;
+    }
 
     if (params.main_road_penalty - 1.0).abs() > f64::EPSILON
         && road.get_rank() != osm::RoadRank::Local
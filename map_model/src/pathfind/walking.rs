@@ -11,7 +11,7 @@ use crate::pathfind::engine::{CreateEngine, PathfindEngine};
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::pathfind::vehicles::VehiclePathfinder;
 use crate::pathfind::zone_cost;
-use crate::pathfind::{round, unround};
+use crate::pathfind::{round, unround, RoutingParams};
 use crate::{
     DirectedRoadID, IntersectionID, Map, PathConstraints, PathRequest, PathStep, PathStepV2,
     PathV2, Position, TransitRoute, TransitRouteID, TransitStopID, TurnType,
@@ -23,6 +23,9 @@ pub struct SidewalkPathfinder {
     nodes: NodeMap<WalkingNode>,
     use_transit: bool,
     engine: PathfindEngine,
+    // Only crossing_penalties matters, and it's only set at runtime
+    #[serde(skip)]
+    params: RoutingParams,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize)]
@@ -60,12 +63,14 @@ impl SidewalkPathfinder {
             nodes: NodeMap::new(),
             use_transit: false,
             engine: PathfindEngine::Empty,
+            params: RoutingParams::default(),
         }
     }
 
     pub fn new(
         map: &Map,
         use_transit: Option<(&VehiclePathfinder, &VehiclePathfinder)>,
+        params: &RoutingParams,
         engine: &CreateEngine,
     ) -> SidewalkPathfinder {
         let mut nodes = NodeMap::new();
@@ -89,13 +94,14 @@ impl SidewalkPathfinder {
             }
         }
 
-        let input_graph = make_input_graph(&nodes, use_transit, map);
+        let input_graph = make_input_graph(&nodes, use_transit, params, map);
         let engine = engine.create(input_graph);
 
         SidewalkPathfinder {
             nodes,
             use_transit: use_transit.is_some(),
             engine,
+            params: params.clone(),
         }
     }
 
@@ -108,7 +114,7 @@ impl SidewalkPathfinder {
             return;
         }
 
-        let input_graph = make_input_graph(&self.nodes, use_transit, map);
+        let input_graph = make_input_graph(&self.nodes, use_transit, &self.params, map);
         let engine = self.engine.reuse_ordering().create(input_graph);
         self.engine = engine;
     }
@@ -241,7 +247,7 @@ impl SidewalkPathfinder {
             self.engine.all_costs_from(start)
         } else {
             // The CH engine doesn't support this!
-            let input_graph = make_input_graph(&self.nodes, None, map);
+            let input_graph = make_input_graph(&self.nodes, None, &self.params, map);
             CreateEngine::Dijkstra
                 .create(input_graph)
                 .all_costs_from(start)
@@ -264,6 +270,7 @@ impl SidewalkPathfinder {
 fn make_input_graph(
     nodes: &NodeMap<WalkingNode>,
     use_transit: Option<(&VehiclePathfinder, &VehiclePathfinder)>,
+    params: &RoutingParams,
     map: &Map,
 ) -> InputGraph {
     let max_speed = Some(crate::MAX_WALKING_SPEED);
@@ -315,6 +322,12 @@ fn make_input_graph(
                 // TODO Add to RoutingParams
                 cost = 3.0 * cost;
             }
+            // Crossing the road, not turning the corner onto another one
+            if t.id.src.road == t.id.dst.road {
+                if let Some(penalty) = params.crossing_penalties.get(&t.id.src.road) {
+                    cost += *penalty;
+                }
+            }
 
             input_graph.add_edge(from, to, round(cost));
             input_graph.add_edge(to, from, round(cost));
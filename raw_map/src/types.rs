@@ -54,7 +54,12 @@ impl AmenityType {
             AmenityType::Bank => vec!["bank"],
             AmenityType::Bar => vec!["bar", "pub", "nightclub", "biergarten"],
             AmenityType::Beauty => vec!["hairdresser", "beauty", "chemist", "cosmetics"],
-            AmenityType::Bike => vec!["bicycle"],
+            AmenityType::Bike => vec![
+                "bicycle",
+                "bicycle_parking",
+                "bicycle_rental",
+                "bicycle_repair_station",
+            ],
             AmenityType::Cafe => vec!["cafe", "pastry", "coffee", "tea", "bakery"],
             AmenityType::CarRepair => vec!["car_repair"],
             AmenityType::CarShare => vec!["car_sharing"],
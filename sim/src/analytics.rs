@@ -832,6 +832,56 @@ impl<X: Ord + Clone> TimeSeriesCount<X> {
         pts_per_type.into_iter().collect()
     }
 
+    /// Sums the counts over a set of IDs into hourly buckets, from midnight up to `now`. Unlike
+    /// `all_total_counts`, this preserves how throughput changes over the day.
+    pub fn time_series(
+        &self,
+        ids: &BTreeSet<X>,
+        agent_types: &BTreeSet<AgentType>,
+        now: Time,
+    ) -> Vec<(Time, usize)> {
+        let mut results = Vec::new();
+        for hour in 0..=now.get_hours() {
+            let mut cnt = 0;
+            for id in ids {
+                for agent_type in agent_types {
+                    cnt += self
+                        .counts
+                        .get(&(id.clone(), *agent_type, hour))
+                        .cloned()
+                        .unwrap_or(0);
+                }
+            }
+            results.push((Time::START_OF_DAY + Duration::hours(hour), cnt));
+        }
+        results
+    }
+
+    /// Returns the contents of a CSV file, with one row per hour and one column per agent type,
+    /// summing the counts over all of the IDs
+    pub fn export_time_series_csv(&self, ids: &BTreeSet<X>, now: Time) -> String {
+        let agent_types = AgentType::all();
+        let per_type: Vec<Vec<(Time, usize)>> = agent_types
+            .iter()
+            .map(|agent_type| self.time_series(ids, &BTreeSet::from([*agent_type]), now))
+            .collect();
+
+        let mut out = String::new();
+        write!(out, "hour").unwrap();
+        for agent_type in &agent_types {
+            write!(out, ",{:?}", agent_type).unwrap();
+        }
+        writeln!(out).unwrap();
+        for hour in 0..=now.get_hours() {
+            write!(out, "{}", hour).unwrap();
+            for series in &per_type {
+                write!(out, ",{}", series[hour].1).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+        out
+    }
+
     /// Returns the contents of a CSV file
     pub fn export_csv<F: Fn(&X) -> usize>(&self, extract_id: F) -> String {
         let mut out = String::new();
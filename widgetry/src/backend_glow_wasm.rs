@@ -205,6 +205,8 @@ impl RenderOnly {
             canvas_movement_called: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
+            keyboard_focus_active: false,
+            next_keyboard_focus_active: false,
         }
     }
 
@@ -38,6 +38,10 @@ pub struct EventCtx<'a> {
     /// While handling an event, this widget (in some panel) this widget declared that it owns
     /// focus. This will become `focus_owned_by` during the next event.
     pub(crate) next_focus_owned_by: Option<String>,
+    /// Some panel with `keyboard_navigation` has a widget selected with Tab. Don't modify.
+    pub(crate) keyboard_focus_active: bool,
+    /// That panel renews this during every event, or else focus is released.
+    pub(crate) next_keyboard_focus_active: bool,
 }
 
 impl<'a> EventCtx<'a> {
@@ -90,6 +94,8 @@ impl<'a> EventCtx<'a> {
             canvas_movement_called: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
+            keyboard_focus_active: false,
+            next_keyboard_focus_active: false,
         };
         let result = cb(&mut tmp);
         self.updates_requested.extend(tmp.updates_requested);
@@ -118,6 +124,18 @@ impl<'a> EventCtx<'a> {
         false
     }
 
+    /// Right clicks never drag the canvas, so unlike `normal_left_click`, there's no drag check
+    pub fn normal_right_click(&mut self) -> bool {
+        if self.input.has_been_consumed() {
+            return false;
+        }
+        if self.input.right_mouse_button_released() {
+            self.input.consume_event();
+            return true;
+        }
+        false
+    }
+
     pub fn is_key_down(&self, key: Key) -> bool {
         self.canvas.keys_held.contains(&key)
     }
@@ -300,6 +318,8 @@ impl<'a> LoadingScreen<'a> {
             canvas_movement_called: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
+            keyboard_focus_active: false,
+            next_keyboard_focus_active: false,
         };
 
         let mut txt = Text::from(Line(&self.title).small_heading());
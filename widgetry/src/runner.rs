@@ -27,6 +27,7 @@ pub(crate) struct State<A: SharedAppState> {
     style: Style,
 
     focus_owned_by: Option<String>,
+    keyboard_focus_active: bool,
 }
 
 impl<A: 'static + SharedAppState> State<A> {
@@ -115,10 +116,14 @@ impl<A: 'static + SharedAppState> State<A> {
                 // If the widget owning focus doesn't renew it, then it'll expire by the end of
                 // this event.
                 next_focus_owned_by: None,
+                // Same idea for whichever panel has a widget focused with Tab
+                keyboard_focus_active: self.keyboard_focus_active,
+                next_keyboard_focus_active: false,
             };
             let started = Instant::now();
             self.app.event(&mut ctx);
             self.focus_owned_by = ctx.next_focus_owned_by.take();
+            self.keyboard_focus_active = ctx.next_keyboard_focus_active;
             if DEBUG_PERFORMANCE {
                 println!("- event() took {}s", elapsed_seconds(started));
             }
@@ -374,6 +379,8 @@ pub fn run<
             canvas_movement_called: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
+            keyboard_focus_active: false,
+            next_keyboard_focus_active: false,
         };
         if settings.load_default_textures {
             timer.start("load default texture");
@@ -398,6 +405,7 @@ pub fn run<
         canvas,
         style,
         focus_owned_by: None,
+        keyboard_focus_active: false,
     };
 
     let dump_raw_events = settings.dump_raw_events;
@@ -20,6 +20,50 @@ impl URLManager {
         must_update_url(Box::new(move |url| change_url_param(url, &key, &value)))
     }
 
+    /// Replace the fragment (everything after `#`) in the current URL.
+    pub fn update_url_fragment(fragment: String) {
+        if let Err(err) = update_url(Box::new(move |url| {
+            let base = url.split('#').next().unwrap();
+            format!("{}#{}", base, fragment)
+        })) {
+            warn!("Couldn't update URL: {}", err);
+        }
+    }
+
+    /// Remove the fragment (everything after `#`) from the current URL, if there is one.
+    pub fn clear_url_fragment() {
+        if let Err(err) = update_url(Box::new(|url| url.split('#').next().unwrap().to_string())) {
+            warn!("Couldn't update URL: {}", err);
+        }
+    }
+
+    /// Returns the fragment (everything after `#`) of the current URL, if there is one. Always
+    /// `None` on native.
+    pub fn get_url_fragment() -> Option<String> {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let hash = web_sys::window()?.location().hash().ok()?;
+            let fragment = hash.strip_prefix('#').unwrap_or(&hash);
+            if !fragment.is_empty() {
+                return Some(fragment.to_string());
+            }
+        }
+        None
+    }
+
+    /// Returns the scheme of the current URL, like `https`. Always `None` on native.
+    pub fn get_url_scheme() -> Option<String> {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let protocol = web_sys::window()?.location().protocol().ok()?;
+            let scheme = protocol.strip_suffix(':').unwrap_or(&protocol);
+            if !scheme.is_empty() {
+                return Some(scheme.to_string());
+            }
+        }
+        None
+    }
+
     /// Get an OSM-style `zoom/lat/lon` string
     /// (https://wiki.openstreetmap.org/wiki/Browsing#Other_URL_tricks) based on the current
     /// viewport.
@@ -96,6 +140,11 @@ impl URLManager {
 }
 
 fn must_update_url(transform: Box<dyn Fn(String) -> String>) {
+    // Only transform the query params, preserving any fragment
+    let transform = Box::new(move |url: String| match url.split_once('#') {
+        Some((base, fragment)) => format!("{}#{}", transform(base.to_string()), fragment),
+        None => transform(url),
+    });
     if let Err(err) = update_url(transform) {
         warn!("Couldn't update URL: {}", err);
     }
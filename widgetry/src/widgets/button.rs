@@ -139,6 +139,14 @@ impl WidgetImpl for Button {
             g.redraw_at(self.top_left, &self.draw_normal);
         }
     }
+
+    fn takes_keyboard_focus(&self) -> bool {
+        !self.is_disabled
+    }
+
+    fn activate_with_keyboard(&mut self, _: &mut EventCtx, output: &mut WidgetOutput) {
+        output.outcome = Outcome::Clicked(self.action.clone());
+    }
 }
 
 #[derive(Clone, Debug, Default)]
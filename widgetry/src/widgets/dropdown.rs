@@ -175,6 +175,18 @@ impl<T: 'static + Clone> WidgetImpl for Dropdown<T> {
             // menu.
         }
     }
+
+    fn takes_keyboard_focus(&self) -> bool {
+        self.btn.is_enabled()
+    }
+
+    fn activate_with_keyboard(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        // The menu itself handles the arrow keys and Enter from here
+        if self.menu.is_none() {
+            self.open_menu(ctx);
+        }
+        output.outcome = Outcome::Focused(self.label.clone());
+    }
 }
 
 fn make_btn(ctx: &EventCtx, label: &str, tooltip: &str, is_persisten_split: bool) -> Button {
@@ -66,6 +66,14 @@ pub trait WidgetImpl: downcast_rs::Downcast {
     fn restore(&mut self, _: &mut EventCtx, _prev: &dyn WidgetImpl) {
         unreachable!()
     }
+    /// In a panel built with `keyboard_navigation`, can the user Tab to this widget?
+    fn takes_keyboard_focus(&self) -> bool {
+        false
+    }
+    /// The user pressed Enter while this widget had keyboard focus. Behave as if it was clicked.
+    fn activate_with_keyboard(&mut self, _: &mut EventCtx, _output: &mut WidgetOutput) {}
+    /// This widget just gained or lost keyboard focus from Tab.
+    fn set_keyboard_focus(&mut self, _focused: bool) {}
 }
 
 /// The result of a Panel handling an event
@@ -725,6 +733,21 @@ impl Widget {
         }
     }
 
+    /// Collects the IDs of everything that can take keyboard focus, in layout order.
+    fn keyboard_focusable(&self, ids: &mut Vec<String>) {
+        if let Some(ref id) = self.id {
+            if self.widget.takes_keyboard_focus() {
+                ids.push(id.clone());
+                return;
+            }
+        }
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members {
+                w.keyboard_focusable(ids);
+            }
+        }
+    }
+
     fn currently_hovering(&self) -> Option<&String> {
         if let Some(btn) = self.widget.downcast_ref::<Button>() {
             if btn.hovering {
@@ -7,15 +7,15 @@ use taffy::layout::AvailableSpace;
 use taffy::node::{Node, Taffy};
 use taffy::style::{Dimension, Style};
 
-use geom::Polygon;
+use geom::{Distance, Polygon};
 
 use crate::widgets::slider;
 use crate::widgets::spinner::SpinnerValue;
 use crate::widgets::Container;
 use crate::{
-    Autocomplete, Button, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment, Menu, Outcome,
-    PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, Stash, TextBox,
-    Toggle, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
+    Autocomplete, Button, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment, Key, Menu,
+    Outcome, PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, Stash,
+    TextBox, Toggle, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Panel {
@@ -32,6 +32,10 @@ pub struct Panel {
     contents_dims: ScreenDims,
     container_dims: ScreenDims,
     clip_rect: Option<ScreenRectangle>,
+
+    keyboard_navigation: bool,
+    /// The ID of the widget selected by pressing Tab
+    keyboard_focus: Option<String>,
 }
 
 impl Panel {
@@ -43,6 +47,7 @@ impl Panel {
             dims_x: PanelDims::MaxPercent(1.0),
             dims_y: PanelDims::MaxPercent(1.0),
             ignore_initial_events: false,
+            keyboard_navigation: false,
         }
     }
 
@@ -274,6 +279,12 @@ impl Panel {
     }
 
     pub fn event(&mut self, ctx: &mut EventCtx) -> Outcome {
+        if self.keyboard_navigation {
+            if let Some(outcome) = self.keyboard_event(ctx) {
+                return outcome;
+            }
+        }
+
         if (self.scrollable_x || self.scrollable_y)
             && ctx
                 .canvas
@@ -320,6 +331,98 @@ impl Panel {
         output.outcome
     }
 
+    /// Tab and Shift+Tab move focus between buttons, checkboxes, dropdowns, and text boxes; Enter
+    /// activates the focused one. A focused text box takes typing. Returns `None` if the event should be handled normally.
+    ///
+    /// Only one panel on screen holds keyboard focus at a time. Tabbing past the last widget
+    /// releases it, so the next panel to see the Tab picks it up.
+    fn keyboard_event(&mut self, ctx: &mut EventCtx) -> Option<Outcome> {
+        // Don't interfere with an open dropdown menu or a text box
+        if ctx.focus_owned_by.is_some() {
+            if self.keyboard_focus.is_some() {
+                ctx.next_keyboard_focus_active = true;
+            }
+            return None;
+        }
+
+        let mut ids = Vec::new();
+        self.top_level.keyboard_focusable(&mut ids);
+        // The focused widget may have vanished or become disabled
+        if let Some(ref id) = self.keyboard_focus {
+            if !ids.contains(id) {
+                self.set_keyboard_focus(None);
+            }
+        }
+        if self.keyboard_focus.is_some() {
+            ctx.next_keyboard_focus_active = true;
+        } else if ctx.keyboard_focus_active {
+            // Another panel has it
+            return None;
+        }
+
+        if ctx.input.pressed(Key::Tab) {
+            let backwards = ctx.is_key_down(Key::LeftShift) || ctx.is_key_down(Key::RightShift);
+            let current = self
+                .keyboard_focus
+                .as_ref()
+                .and_then(|id| ids.iter().position(|x| x == id));
+            let next = match current {
+                Some(idx) if backwards => idx.checked_sub(1),
+                Some(idx) => Some(idx + 1).filter(|idx| *idx < ids.len()),
+                None if ids.is_empty() => None,
+                None if backwards => Some(ids.len() - 1),
+                None => Some(0),
+            };
+            if let Some(idx) = next {
+                self.set_keyboard_focus(Some(ids[idx].clone()));
+                ctx.keyboard_focus_active = true;
+                ctx.next_keyboard_focus_active = true;
+                return Some(Outcome::Nothing);
+            }
+
+            // Off the end. Let another panel take focus, if there is one.
+            self.set_keyboard_focus(None);
+            ctx.keyboard_focus_active = false;
+            ctx.next_keyboard_focus_active = false;
+            ctx.input.unconsume_event();
+            return None;
+        }
+
+        let id = self.keyboard_focus.clone()?;
+        if !ctx.input.pressed(Key::Enter) {
+            return None;
+        }
+        let mut output = WidgetOutput::new();
+        self.top_level
+            .find_mut(&id)
+            .unwrap()
+            .widget
+            .activate_with_keyboard(ctx, &mut output);
+        if output.redo_layout {
+            self.recompute_layout(ctx, true);
+        }
+        if let Outcome::Focused(ref id) = output.outcome {
+            assert!(ctx.next_focus_owned_by.is_none());
+            ctx.next_focus_owned_by = Some(id.clone());
+        }
+        Some(output.outcome)
+    }
+
+    /// Move keyboard focus, telling the widgets that lose and gain it
+    fn set_keyboard_focus(&mut self, id: Option<String>) {
+        if let Some(old) = self.keyboard_focus.take() {
+            if let Some(w) = self.top_level.find_mut(&old) {
+                w.widget.set_keyboard_focus(false);
+            }
+        }
+        if let Some(ref new) = id {
+            if let Some(w) = self.top_level.find_mut(new) {
+                w.widget.set_keyboard_focus(true);
+            }
+        }
+        self.keyboard_focus = id;
+    }
+
     pub fn draw(&self, g: &mut GfxCtx) {
         if let Some(ref rect) = self.clip_rect {
             g.enable_clipping(rect.clone());
@@ -345,6 +448,16 @@ impl Panel {
         }
 
         self.top_level.draw(g);
+        if let Some(w) = self
+            .keyboard_focus
+            .as_ref()
+            .and_then(|id| self.top_level.find(id))
+        {
+            let color = g.style().text_hotkey_color;
+            g.fork_screenspace();
+            g.draw_polygon(color, w.rect.to_polygon().to_outline(Distance::meters(2.0)));
+            g.unfork();
+        }
         if self.scrollable_x || self.scrollable_y {
             g.disable_clipping();
 
@@ -367,6 +480,9 @@ impl Panel {
 
     pub fn restore(&mut self, ctx: &mut EventCtx, prev: &Panel) {
         self.set_scroll_offset(ctx, prev.scroll_offset());
+        if self.keyboard_navigation {
+            self.set_keyboard_focus(prev.keyboard_focus.clone());
+        }
 
         self.top_level.restore(ctx, prev);
 
@@ -584,6 +700,7 @@ pub struct PanelBuilder {
     dims_x: PanelDims,
     dims_y: PanelDims,
     ignore_initial_events: bool,
+    keyboard_navigation: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -615,6 +732,9 @@ impl PanelBuilder {
             container_dims: ScreenDims::new(0.0, 0.0),
             clip_rect: None,
             cached_flexbox: None,
+
+            keyboard_navigation: self.keyboard_navigation,
+            keyboard_focus: None,
         };
         match self.dims_x {
             PanelDims::MaxPercent(_) => {}
@@ -714,4 +834,12 @@ impl PanelBuilder {
         self.ignore_initial_events = true;
         self
     }
+
+    /// Let the user move between buttons, checkboxes, dropdowns, and text boxes with Tab and
+    /// Shift+Tab, and press Enter to activate one. This is opt-in, because some apps bind Tab to
+    /// other things. It only covers the panel; picking things on the map still needs the mouse.
+    pub fn keyboard_navigation(mut self) -> PanelBuilder {
+        self.keyboard_navigation = true;
+        self
+    }
 }
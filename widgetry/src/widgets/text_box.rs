@@ -13,6 +13,8 @@ pub struct TextBox {
     label: String,
     cursor_x: usize,
     has_focus: bool,
+    // Focused with Tab, in a panel with keyboard navigation
+    keyboard_focus: bool,
     autofocus: bool,
     padding: EdgeInsets,
 
@@ -64,6 +66,7 @@ impl TextBox {
             cursor_x: prefilled.len(),
             line: prefilled,
             has_focus: false,
+            keyboard_focus: false,
             autofocus,
             padding,
             top_left: ScreenPt::new(0.0, 0.0),
@@ -106,9 +109,10 @@ impl WidgetImpl for TextBox {
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
         if !self.autofocus && ctx.redo_mouseover() {
             if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
-                self.has_focus = ScreenRectangle::top_left(self.top_left, self.dims).contains(pt);
+                self.has_focus = self.keyboard_focus
+                    || ScreenRectangle::top_left(self.top_left, self.dims).contains(pt);
             } else {
-                self.has_focus = false;
+                self.has_focus = self.keyboard_focus;
             }
         }
 
@@ -171,4 +175,14 @@ impl WidgetImpl for TextBox {
         let draw = g.upload(batch);
         g.redraw_at(self.top_left, &draw);
     }
+
+    fn takes_keyboard_focus(&self) -> bool {
+        // An autofocused box already gets every key
+        !self.autofocus
+    }
+
+    fn set_keyboard_focus(&mut self, focused: bool) {
+        self.keyboard_focus = focused;
+        self.has_focus = focused;
+    }
 }
@@ -257,6 +257,15 @@ impl Toggle {
             right_text_button.build_def(ctx).centered_vert(),
         ])
     }
+
+    fn flip(&mut self, output: &mut WidgetOutput) {
+        // Both buttons have the same label
+        output.outcome = Outcome::Changed(self.btn.action.clone());
+        std::mem::swap(&mut self.btn, &mut self.other_btn);
+        self.btn.set_pos(self.other_btn.top_left);
+        self.enabled = !self.enabled;
+        output.redo_layout = true;
+    }
 }
 
 impl WidgetImpl for Toggle {
@@ -271,16 +280,19 @@ impl WidgetImpl for Toggle {
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
         self.btn.event(ctx, output);
         if let Outcome::Clicked(_) = output.outcome {
-            // Both buttons have the same label
-            output.outcome = Outcome::Changed(self.btn.action.clone());
-            std::mem::swap(&mut self.btn, &mut self.other_btn);
-            self.btn.set_pos(self.other_btn.top_left);
-            self.enabled = !self.enabled;
-            output.redo_layout = true;
+            self.flip(output);
         }
     }
 
     fn draw(&self, g: &mut GfxCtx) {
         self.btn.draw(g);
     }
+
+    fn takes_keyboard_focus(&self) -> bool {
+        self.btn.is_enabled()
+    }
+
+    fn activate_with_keyboard(&mut self, _: &mut EventCtx, output: &mut WidgetOutput) {
+        self.flip(output);
+    }
 }